@@ -17,7 +17,7 @@
 // ---------------------------------------------------------------------------
 
 @interface DingDelegate : NSObject <UNUserNotificationCenterDelegate>
-@property (nonatomic, copy) void (^onResponse)(NSString *status);
+@property (nonatomic, copy) void (^onResponse)(NSString *status, NSString *action);
 @end
 
 @implementation DingDelegate
@@ -35,11 +35,12 @@ didReceiveNotificationResponse:(UNNotificationResponse *)response
          withCompletionHandler:(void (^)(void))handler {
     NSString *action = response.actionIdentifier;
     if ([action isEqualToString:UNNotificationDefaultActionIdentifier]) {
-        if (self.onResponse) self.onResponse(@"clicked");
+        if (self.onResponse) self.onResponse(@"clicked", nil);
     } else if ([action isEqualToString:UNNotificationDismissActionIdentifier]) {
-        if (self.onResponse) self.onResponse(@"closed");
+        if (self.onResponse) self.onResponse(@"closed", nil);
     } else {
-        if (self.onResponse) self.onResponse(action);
+        // Action buttons use their label as identifier.
+        if (self.onResponse) self.onResponse(@"action", action);
     }
     handler();
 }
@@ -50,10 +51,11 @@ didReceiveNotificationResponse:(UNNotificationResponse *)response
 // Helpers
 // ---------------------------------------------------------------------------
 
-// Write a JSON response to stdout and exit.
-static void respond(NSString *status, NSString *error) {
+// Write a JSON response to stdout.
+static void respondWithAction(NSString *status, NSString *action, NSString *error) {
     NSMutableDictionary *dict = [NSMutableDictionary dictionary];
     dict[@"status"] = status;
+    if (action) dict[@"action"] = action;
     if (error) dict[@"error"] = error;
     NSData *json = [NSJSONSerialization dataWithJSONObject:dict options:0 error:nil];
     NSFileHandle *out = [NSFileHandle fileHandleWithStandardOutput];
@@ -61,6 +63,10 @@ static void respond(NSString *status, NSString *error) {
     [out writeData:[@"\n" dataUsingEncoding:NSUTF8StringEncoding]];
 }
 
+static void respond(NSString *status, NSString *error) {
+    respondWithAction(status, nil, error);
+}
+
 static void respondAndExit(NSString *status, NSString *error) {
     respond(status, error);
     exit(error ? 1 : 0);
@@ -91,6 +97,7 @@ int main(int argc, const char *argv[]) {
         NSString *subtitle = req[@"subtitle"];
         NSString *sound   = req[@"sound"];
         BOOL waitForClick = [req[@"wait_for_click"] boolValue];
+        NSArray *actions  = req[@"actions"];
 
         UNUserNotificationCenter *center = [UNUserNotificationCenter currentNotificationCenter];
         if (!center) {
@@ -142,6 +149,41 @@ int main(int argc, const char *argv[]) {
         content.body  = message;
         if (subtitle) content.subtitle = subtitle;
 
+        // Action buttons need a category registered for this notification.
+        if ([actions isKindOfClass:[NSArray class]] && actions.count > 0) {
+            NSMutableArray *buttons = [NSMutableArray array];
+            for (id label in actions) {
+                if (![label isKindOfClass:[NSString class]]) continue;
+                [buttons addObject:[UNNotificationAction actionWithIdentifier:label
+                                                                        title:label
+                                                                      options:UNNotificationActionOptionForeground]];
+            }
+            // Named after the buttons, so repeated sends reuse one category.
+            NSArray *labels = [buttons valueForKey:@"identifier"];
+            NSString *categoryId = [@"ding-actions-" stringByAppendingString:
+                                       [labels componentsJoinedByString:@"\x1f"]];
+            UNNotificationCategory *category =
+                [UNNotificationCategory categoryWithIdentifier:categoryId
+                                                       actions:buttons
+                                             intentIdentifiers:@[]
+                                                       options:UNNotificationCategoryOptionCustomDismissAction];
+            // setNotificationCategories: replaces the whole set, which would
+            // strip the buttons from notifications still on screen.
+            dispatch_semaphore_t categorySema = dispatch_semaphore_create(0);
+            __block NSSet<UNNotificationCategory *> *existing = nil;
+            [center getNotificationCategoriesWithCompletionHandler:^(NSSet<UNNotificationCategory *> *categories) {
+                existing = categories;
+                dispatch_semaphore_signal(categorySema);
+            }];
+            dispatch_semaphore_wait(categorySema, DISPATCH_TIME_FOREVER);
+            NSMutableSet *categories = [NSMutableSet setWithObject:category];
+            for (UNNotificationCategory *other in existing) {
+                if (![other.identifier isEqualToString:categoryId]) [categories addObject:other];
+            }
+            [center setNotificationCategories:categories];
+            content.categoryIdentifier = categoryId;
+        }
+
         // Sound handling.
         if (!sound ||
             [sound caseInsensitiveCompare:@"default"] == NSOrderedSame) {
@@ -181,8 +223,8 @@ int main(int argc, const char *argv[]) {
 
         // Wait for the user to interact with the notification.
         __block BOOL responded = NO;
-        delegate.onResponse = ^(NSString *status) {
-            respond(status, nil);
+        delegate.onResponse = ^(NSString *status, NSString *action) {
+            respondWithAction(status, action, nil);
             responded = YES;
             CFRunLoopStop(CFRunLoopGetMain());
         };
@@ -1,3 +1,5 @@
+use crate::notification::Action;
+use clap::builder::{PossibleValue, StringValueParser, TypedValueParser};
 use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
@@ -10,6 +12,10 @@ pub struct Cli {
     /// Path to config file (TOML)
     #[arg(long, global = true)]
     pub config: Option<PathBuf>,
+
+    /// Config profile to apply (overrides DING_PROFILE and .ding.toml)
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
 }
 
 #[derive(Debug, Subcommand)]
@@ -31,14 +37,20 @@ pub enum Commands {
         #[command(subcommand)]
         command: SourcesCmd,
     },
-    /// Install integrations for Claude Code or Codex
+    /// Install integrations for Claude Code, Codex, Gemini CLI or OpenCode
     Install(InstallArgs),
-    /// Hook entrypoint for Claude Code or Codex notify
+    /// Remove ding's hooks from Claude Code, Codex, Gemini CLI or OpenCode
+    Uninstall(UninstallArgs),
+    /// Hook entrypoint for Claude Code, Codex, Gemini CLI, OpenCode or a [hooks.<name>] adapter
     Hook(HookArgs),
+    /// Print shell integration that notifies when long commands finish
+    ShellInit(ShellInitArgs),
     /// Focus the originating terminal/tmux context
     Focus(FocusArgs),
     /// Listen for remote notifications
     Listen(ListenArgs),
+    /// Stream notifications received by a listener
+    Tail(TailArgs),
     /// Remote provider utilities
     Remote {
         #[command(subcommand)]
@@ -54,20 +66,78 @@ pub enum Commands {
         #[command(subcommand)]
         command: TelegramCmd,
     },
+    /// Check that configured providers, listeners and integrations work
+    Doctor,
+    /// Print shell completions (e.g. `ding completions zsh > ~/.zfunc/_ding`)
+    Completions(CompletionsArgs),
     /// Internal macOS click-wait helper
     #[command(hide = true)]
     WaitMacos(WaitMacosArgs),
+    /// Internal remote batch queue flusher
+    #[command(hide = true)]
+    FlushRemoteQueue(FlushRemoteQueueArgs),
+    /// Internal entrypoint for the `shell-init` integration
+    #[command(hide = true)]
+    ShellNotify(ShellNotifyArgs),
 }
 
-#[derive(Debug, Args)]
+impl Commands {
+    /// Whether `--json` was given, in which case errors are reported as JSON
+    /// on stdout as well.
+    pub fn json_output(&self) -> bool {
+        match self {
+            Commands::Send(args) => args.json,
+            Commands::Providers {
+                command: ProvidersCmd::List(args),
+            }
+            | Commands::Sources {
+                command: SourcesCmd::List(args),
+            }
+            | Commands::Forward {
+                command: ForwardCmd::Status(args),
+            } => args.json,
+            Commands::Remote {
+                command: RemoteCmd::Ping(args),
+            } => args.json,
+            Commands::Remote {
+                command: RemoteCmd::Discover(args),
+            } => args.json,
+            Commands::Tail(args) => args.json,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Args)]
 pub struct SendArgs {
     /// Notification title (optional)
     #[arg(long)]
     pub title: Option<String>,
 
     /// Notification message/body
-    #[arg(value_name = "MESSAGE")]
-    pub message: String,
+    #[arg(
+        value_name = "MESSAGE",
+        required_unless_present_any = ["message_template", "json_input"]
+    )]
+    pub message: Option<String>,
+
+    /// Read Notification or RemoteEnvelope JSON (one document, or one per line) from FILE or stdin
+    #[arg(
+        long,
+        value_name = "FILE",
+        num_args = 0..=1,
+        default_missing_value = "-",
+        conflicts_with = "message"
+    )]
+    pub json_input: Option<PathBuf>,
+
+    /// Template for the title, e.g. "{{ context.project }}: {{ title }}"
+    #[arg(long)]
+    pub title_template: Option<String>,
+
+    /// Template for the message, e.g. "{{ message | truncate(80) }} ({{ context.host }})"
+    #[arg(long)]
+    pub message_template: Option<String>,
 
     /// Icon path (provider-specific)
     #[arg(long)]
@@ -121,7 +191,16 @@ pub struct SendArgs {
     #[arg(long)]
     pub on_click: Option<String>,
 
-    /// Wait for user click (blocking)
+    /// Attach metadata, e.g. --meta build=1234 (repeatable)
+    #[arg(long = "meta", value_name = "KEY=VALUE", value_parser = parse_meta)]
+    pub meta: Vec<(String, String)>,
+
+    /// Add a button: "Label=https://..." opens a URL, "Label=!command" runs a command (repeatable).
+    /// Labels must be unique and URLs http or https. With the remote provider, !command buttons need --wait-for-click and are dropped otherwise
+    #[arg(long = "action", value_name = "LABEL=TARGET", value_parser = parse_action)]
+    pub actions: Vec<Action>,
+
+    /// Wait for user click (blocking; with the remote provider, waits for the listener to report it)
     #[arg(long)]
     pub wait_for_click: bool,
 
@@ -157,6 +236,14 @@ pub struct SendArgs {
     #[arg(long)]
     pub remote_retries: Option<u32>,
 
+    /// URL the remote listener POSTs the delivery outcome to (remote provider only)
+    #[arg(long)]
+    pub remote_callback: Option<String>,
+
+    /// Queue and coalesce with other sends into one /notify/batch request (remote provider only)
+    #[arg(long)]
+    pub batch: bool,
+
     /// Disable fallback to local provider if remote delivery fails
     #[arg(long)]
     pub no_fallback: bool,
@@ -168,10 +255,20 @@ pub enum ConfigCmd {
     Init(ConfigInitArgs),
     /// Set a config key (supports dotted paths)
     Set(ConfigSetArgs),
+    /// Print the effective value of a config key
+    Get(ConfigKeyArgs),
+    /// Remove a config key from the config file
+    Unset(ConfigKeyArgs),
     /// Show the resolved config path
     Path,
     /// Show current config contents
     List,
+    /// Show the effective config after profiles and project overrides, with each value's origin
+    Resolve,
+    /// Check config files for unknown keys, bad values and unusable targets
+    Validate,
+    /// Print a JSON Schema for config.toml (for Taplo / Even Better TOML)
+    Schema,
 }
 
 #[derive(Debug, Args)]
@@ -188,46 +285,137 @@ pub struct ConfigInitArgs {
 #[derive(Debug, Args)]
 pub struct ConfigSetArgs {
     /// Config key to set (e.g. remote.host)
+    #[arg(value_parser = ConfigKeyParser, hide_possible_values = true)]
     pub key: String,
 
-    /// Value to set
+    /// Value to set; lists take `["a", "b"]` or `a,b`
     pub value: String,
+
+    /// Append the value to a list such as forward.targets
+    #[arg(long, conflicts_with = "remove")]
+    pub add: bool,
+
+    /// Remove the value from a list such as forward.targets
+    #[arg(long)]
+    pub remove: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ConfigKeyArgs {
+    /// Config key (e.g. remote.host)
+    #[arg(value_parser = ConfigKeyParser, hide_possible_values = true)]
+    pub key: String,
+}
+
+#[derive(Debug, Args)]
+pub struct CompletionsArgs {
+    /// Shell to generate completions for
+    pub shell: clap_complete::Shell,
 }
 
 #[derive(Debug, Subcommand)]
 pub enum ProvidersCmd {
     /// List providers available on this platform
-    List,
+    List(JsonArgs),
 }
 
 #[derive(Debug, Subcommand)]
 pub enum SourcesCmd {
     /// List configured sources
-    List,
+    List(JsonArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct JsonArgs {
+    /// Output JSON
+    #[arg(long)]
+    pub json: bool,
 }
 
 #[derive(Debug, Args)]
 pub struct InstallArgs {
-    /// Target tool (claude or codex)
+    /// Target tool (claude, codex, gemini, opencode or git)
+    #[arg(value_enum, required_unless_present = "status")]
+    pub target: Option<InstallTarget>,
+
+    /// Apply changes (default is dry-run)
+    #[arg(long)]
+    pub apply: bool,
+
+    /// Replace a notify command that isn't ding's (codex only)
+    #[arg(long)]
+    pub force: bool,
+
+    /// Install for every repository via core.hooksPath instead of the current one (git only)
+    #[arg(long)]
+    pub global: bool,
+
+    /// Report which integrations are installed and where
+    #[arg(long, conflicts_with_all = ["target", "apply", "force", "global"])]
+    pub status: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct UninstallArgs {
+    /// Target tool (claude, codex, gemini, opencode or git)
     #[arg(value_enum)]
     pub target: InstallTarget,
 
     /// Apply changes (default is dry-run)
     #[arg(long)]
     pub apply: bool,
+
+    /// Remove the global core.hooksPath hooks instead of the current repository's (git only)
+    #[arg(long)]
+    pub global: bool,
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
 pub enum InstallTarget {
     Claude,
     Codex,
+    Gemini,
+    Opencode,
+    Git,
 }
 
 #[derive(Debug, Args)]
-pub struct HookArgs {
-    /// Target tool (claude or codex)
+pub struct ShellInitArgs {
+    /// Shell to generate the integration for
     #[arg(value_enum)]
-    pub target: InstallTarget,
+    pub shell: ShellKind,
+
+    /// Minimum command duration in seconds (defaults to shell.threshold, then 10)
+    #[arg(long)]
+    pub threshold: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ShellKind {
+    Zsh,
+    Bash,
+    Fish,
+}
+
+#[derive(Debug, Args)]
+pub struct ShellNotifyArgs {
+    /// Exit status of the command
+    #[arg(long, default_value_t = 0, allow_negative_numbers = true)]
+    pub status: i32,
+
+    /// How long the command ran, in seconds
+    #[arg(long, default_value_t = 0)]
+    pub duration: u64,
+
+    /// The command line
+    #[arg(trailing_var_arg = true)]
+    pub command: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct HookArgs {
+    /// Hook adapter: claude, codex, gemini, opencode, git, or a [hooks.<name>] entry from the config
+    pub name: String,
 
     /// JSON payload (if not provided, read from stdin)
     pub json: Option<String>,
@@ -272,7 +460,18 @@ pub struct WaitMacosArgs {
 }
 
 #[derive(Debug, Args)]
+pub struct FlushRemoteQueueArgs {
+    /// How long to collect queued notifications before sending
+    #[arg(long, default_value_t = 500)]
+    pub window_ms: u64,
+}
+
+#[derive(Debug, Args)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct ListenArgs {
+    #[command(subcommand)]
+    pub command: Option<ListenCmd>,
+
     /// Bind address (default 0.0.0.0)
     #[arg(long)]
     pub bind: Option<String>,
@@ -304,12 +503,94 @@ pub struct ListenArgs {
     /// Disable click handling entirely
     #[arg(long)]
     pub no_click: bool,
+
+    /// Show each /notify/batch request as a single summary notification
+    #[arg(long)]
+    pub collapse_batches: bool,
+
+    /// Advertise the listener over mDNS (never for a loopback bind)
+    #[arg(long, conflicts_with = "no_advertise")]
+    pub advertise: bool,
+
+    /// Do not advertise the listener over mDNS, even if the config says to
+    #[arg(long)]
+    pub no_advertise: bool,
+
+    /// Also accept requests on this Unix socket (for ssh -R forwarding)
+    #[arg(long)]
+    pub socket: Option<String>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ListenCmd {
+    /// Install the listener as a user service (systemd or launchd)
+    Install(ListenServiceArgs),
+    /// Stop and remove the listener service
+    Uninstall(ListenServiceArgs),
+    /// Show the listener service state and health
+    Status,
+}
+
+#[derive(Debug, Args)]
+pub struct ListenServiceArgs {
+    /// Apply changes (default is dry-run)
+    #[arg(long)]
+    pub apply: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct TailArgs {
+    /// Listener host (defaults to remote.host, then 127.0.0.1)
+    #[arg(long)]
+    pub remote_host: Option<String>,
+
+    /// Listener port (defaults to remote.port, then listener.port)
+    #[arg(long)]
+    pub remote_port: Option<u16>,
+
+    /// Listener auth token (defaults to remote.token, then listener.token)
+    #[arg(long)]
+    pub remote_token: Option<String>,
+
+    /// Print raw JSON events, one per line
+    #[arg(long)]
+    pub json: bool,
 }
 
 #[derive(Debug, Subcommand)]
 pub enum RemoteCmd {
     /// Ping the configured remote listener
     Ping(RemotePingArgs),
+    /// Find listeners advertised on the local network (mDNS)
+    Discover(RemoteDiscoverArgs),
+    /// Print the ssh config needed to forward the listener socket to a remote host
+    SshConfig(RemoteSshConfigArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct RemoteSshConfigArgs {
+    /// Host pattern for the generated block (e.g. devbox)
+    #[arg(long)]
+    pub host: Option<String>,
+
+    /// Socket path on the remote machine (default /tmp/ding-$USER.sock)
+    #[arg(long)]
+    pub remote_socket: Option<String>,
+
+    /// Local listener socket (defaults to listener.socket)
+    #[arg(long)]
+    pub local_socket: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct RemoteDiscoverArgs {
+    /// How long to browse, in milliseconds
+    #[arg(long, default_value_t = 2000)]
+    pub timeout_ms: u64,
+
+    /// Output JSON
+    #[arg(long)]
+    pub json: bool,
 }
 
 #[derive(Debug, Subcommand)]
@@ -321,7 +602,7 @@ pub enum ForwardCmd {
     /// Toggle forwarding
     Toggle,
     /// Show forwarding status
-    Status,
+    Status(JsonArgs),
 }
 
 #[derive(Debug, Args)]
@@ -379,6 +660,10 @@ pub struct RemotePingArgs {
     /// Remote listener auth token
     #[arg(long)]
     pub remote_token: Option<String>,
+
+    /// Output JSON
+    #[arg(long)]
+    pub json: bool,
 }
 
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -387,3 +672,59 @@ pub enum UrgencyArg {
     Normal,
     High,
 }
+
+fn parse_meta(raw: &str) -> Result<(String, String), String> {
+    match raw.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!("expected KEY=VALUE, got {raw:?}")),
+    }
+}
+
+fn parse_action(raw: &str) -> Result<Action, String> {
+    let (label, target) = match raw.split_once('=') {
+        Some((label, target)) if !label.trim().is_empty() && !target.trim().is_empty() => {
+            (label.trim().to_string(), target.trim())
+        }
+        _ => return Err(format!("expected LABEL=URL or LABEL=!COMMAND, got {raw:?}")),
+    };
+    Ok(match target.strip_prefix('!') {
+        Some(command) => Action {
+            label,
+            url: None,
+            command: Some(command.trim().to_string()),
+        },
+        None => {
+            crate::notification::check_action_url(target)?;
+            Action {
+                label,
+                url: Some(target.to_string()),
+                command: None,
+            }
+        }
+    })
+}
+
+// Accepts any key (maps such as `sources.<name>` are open-ended) but offers
+// the fixed keys to shell completion.
+#[derive(Debug, Clone)]
+struct ConfigKeyParser;
+
+impl TypedValueParser for ConfigKeyParser {
+    type Value = String;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> Result<String, clap::Error> {
+        StringValueParser::new().parse_ref(cmd, arg, value)
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        let keys = crate::schema::keys().map(|(key, _)| PossibleValue::new(key));
+        Some(Box::new(keys))
+    }
+}
@@ -1,16 +1,40 @@
+use crate::error::NotifallError;
+use crate::notification::Urgency;
+use crate::secret::Secret;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+/// Project-local overrides, found by walking up from the working directory.
+pub const PROJECT_FILE: &str = ".ding.toml";
+
+/// Keys a project `.ding.toml` may set. A repository can change how its
+/// notifications look and pick a profile, but not where they are sent, the
+/// credentials used or anything that runs commands.
+pub const PROJECT_KEYS: &[&str] = &[
+    "profile",
+    "sources",
+    "shell",
+    "macos.title_template",
+    "macos.message_template",
+    "telegram.template",
+];
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
     pub default_provider: Option<String>,
+    /// Profile to apply when none is chosen with --profile or DING_PROFILE.
+    pub profile: Option<String>,
+    pub profiles: Option<BTreeMap<String, toml::Table>>,
     pub macos: Option<MacosConfig>,
     pub remote: Option<RemoteConfig>,
     pub listener: Option<ListenerConfig>,
     pub forward: Option<ForwardConfig>,
     pub telegram: Option<TelegramConfig>,
+    pub shell: Option<ShellConfig>,
+    pub log: Option<LogConfig>,
     pub sources: Option<BTreeMap<String, SourceConfig>>,
+    pub hooks: Option<BTreeMap<String, HookAdapterConfig>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -18,6 +42,8 @@ pub struct MacosConfig {
     pub sound: Option<String>,
     pub app_bundle_id: Option<String>,
     pub icon: Option<PathBuf>,
+    pub title_template: Option<String>,
+    pub message_template: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -25,6 +51,59 @@ pub struct SourceConfig {
     pub icon: Option<PathBuf>,
     pub app_bundle_id: Option<String>,
     pub display_name: Option<String>,
+    pub title_template: Option<String>,
+    pub message_template: Option<String>,
+    pub events: Option<BTreeMap<String, HookEventConfig>>,
+}
+
+/// Per-event overrides for hook sources, e.g. `[sources.claude.events.Stop]`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct HookEventConfig {
+    pub enabled: Option<bool>,
+    pub urgency: Option<Urgency>,
+    pub sound: Option<String>,
+}
+
+/// `[hooks.<name>]`: turns the JSON a tool emits into a notification for
+/// `ding hook <name>`. Rules are JSON pointers (`"/message"`), text with
+/// `{/pointer}` placeholders, or a list of either tried in order.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct HookAdapterConfig {
+    pub source: Option<String>,
+    pub event: Option<HookRule>,
+    pub subtype: Option<HookRule>,
+    pub events: Option<Vec<String>>,
+    pub title: Option<HookRule>,
+    pub message: Option<HookRule>,
+    pub project: Option<HookRule>,
+    pub urgency: Option<HookRule>,
+    pub urgency_map: Option<BTreeMap<String, Urgency>>,
+    pub on: Option<BTreeMap<String, HookEventRules>>,
+}
+
+/// Per-event overrides inside an adapter, e.g. `[hooks.<name>.on.Stop]`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct HookEventRules {
+    pub enabled: Option<bool>,
+    pub title: Option<HookRule>,
+    pub message: Option<HookRule>,
+    pub urgency: Option<Urgency>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum HookRule {
+    One(String),
+    Any(Vec<String>),
+}
+
+impl HookRule {
+    pub fn candidates(&self) -> &[String] {
+        match self {
+            HookRule::One(rule) => std::slice::from_ref(rule),
+            HookRule::Any(rules) => rules,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -32,29 +111,83 @@ pub struct RemoteConfig {
     pub host: Option<String>,
     pub port: Option<u16>,
     pub url: Option<String>,
-    pub token: Option<String>,
+    pub token: Option<Secret>,
     pub timeout_ms: Option<u64>,
     pub retries: Option<u32>,
     pub fallback_to_local: Option<bool>,
+    pub batch: Option<bool>,
+    pub batch_window_ms: Option<u64>,
+    pub listener_name: Option<String>,
+    pub socket: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ListenerConfig {
     pub bind: Option<String>,
     pub port: Option<u16>,
-    pub token: Option<String>,
+    pub token: Option<Secret>,
     pub require_token: Option<bool>,
     pub prefix_hostname: Option<bool>,
     pub allow_hosts: Option<Vec<String>>,
+    pub callback_hosts: Option<Vec<String>>,
+    pub cors_origin: Option<String>,
     pub on_click: Option<String>,
+    pub collapse_batches: Option<bool>,
+    pub metrics_require_token: Option<bool>,
+    pub advertise: Option<bool>,
+    pub name: Option<String>,
+    pub socket: Option<String>,
+}
+
+impl RemoteConfig {
+    /// `remote.token`, resolved on first use.
+    pub fn token(&self) -> Result<Option<String>, NotifallError> {
+        resolve_secret(self.token.as_ref(), "remote.token")
+    }
+}
+
+impl ListenerConfig {
+    /// `listener.token`, resolved on first use.
+    pub fn token(&self) -> Result<Option<String>, NotifallError> {
+        resolve_secret(self.token.as_ref(), "listener.token")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct LogConfig {
+    pub level: Option<String>,
+    pub format: Option<String>,
+    pub file: Option<bool>,
+    pub max_size: Option<u64>,
+    pub keep: Option<u32>,
+    pub modules: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ShellConfig {
+    pub threshold: Option<u64>,
+    pub notify_when_focused: Option<bool>,
+    pub ignore: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct TelegramConfig {
-    pub bot_token: Option<String>,
+    pub bot_token: Option<Secret>,
     pub chat_id: Option<String>,
     pub parse_mode: Option<String>,
     pub silent: Option<bool>,
+    pub template: Option<String>,
+}
+
+impl TelegramConfig {
+    /// `telegram.bot_token`, resolved on first use.
+    pub fn bot_token(&self) -> Result<Option<String>, NotifallError> {
+        resolve_secret(self.bot_token.as_ref(), "telegram.bot_token")
+    }
+}
+
+fn resolve_secret(secret: Option<&Secret>, key: &str) -> Result<Option<String>, NotifallError> {
+    secret.map(|secret| secret.resolve(key)).transpose()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -63,49 +196,539 @@ pub struct ForwardConfig {
     pub targets: Option<Vec<String>>,
 }
 
+/// Where an effective config value was set.
+#[derive(Debug, Clone)]
+pub enum ConfigOrigin {
+    Global(PathBuf),
+    Profile(String),
+    Project(PathBuf),
+    Env(String),
+}
+
+impl std::fmt::Display for ConfigOrigin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigOrigin::Global(path) => write!(f, "global {}", path.display()),
+            ConfigOrigin::Profile(name) => write!(f, "profile {name}"),
+            ConfigOrigin::Project(path) => write!(f, "project {}", path.display()),
+            ConfigOrigin::Env(var) => write!(f, "env {var}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum KeyKind {
+    String,
+    Bool,
+    Integer,
+    /// Comma-separated in environment variables.
+    List,
+}
+
+/// `remote.host` -> `DING_REMOTE_HOST`.
+pub fn env_var(key: &str) -> String {
+    format!("DING_{}", key.replace('.', "_").to_uppercase())
+}
+
+/// Parse an environment variable's text as a value of `kind`.
+pub fn parse_env_value(kind: KeyKind, raw: &str) -> Result<toml::Value, String> {
+    let raw = raw.trim();
+    match kind {
+        KeyKind::String => Ok(toml::Value::String(raw.to_string())),
+        KeyKind::Bool => match raw.to_ascii_lowercase().as_str() {
+            "1" | "true" | "yes" | "on" => Ok(toml::Value::Boolean(true)),
+            "0" | "false" | "no" | "off" => Ok(toml::Value::Boolean(false)),
+            _ => Err(format!("expected true or false, got {raw:?}")),
+        },
+        KeyKind::Integer => raw
+            .parse::<i64>()
+            .map(toml::Value::Integer)
+            .map_err(|_| format!("expected an integer, got {raw:?}")),
+        KeyKind::List => Ok(toml::Value::Array(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| toml::Value::String(item.to_string()))
+                .collect(),
+        )),
+    }
+}
+
+// Set `dotted` in a fresh table, e.g. `remote.host` -> `{ remote = { host } }`.
+fn nested_table(dotted: &str, value: toml::Value) -> toml::Table {
+    let mut parts = dotted.rsplit('.');
+    let mut table = toml::Table::new();
+    table.insert(parts.next().unwrap_or(dotted).to_string(), value);
+    for part in parts {
+        let mut outer = toml::Table::new();
+        outer.insert(part.to_string(), toml::Value::Table(table));
+        table = outer;
+    }
+    table
+}
+
+/// The config after merging every layer, with the origin of each leaf value
+/// keyed by dotted path (e.g. `remote.host`).
+#[derive(Debug, Clone)]
+pub struct ResolvedConfig {
+    pub config: Config,
+    pub values: toml::Table,
+    pub origins: BTreeMap<String, ConfigOrigin>,
+    pub profile: Option<String>,
+}
+
+impl ResolvedConfig {
+    /// Merge, lowest precedence first: the global file, the selected profile,
+    /// the project `.ding.toml`, then `DING_<SECTION>_<KEY>` environment
+    /// variables. `profile` is the explicit choice from --profile or
+    /// DING_PROFILE; otherwise the project's, then the global file's `profile`
+    /// key is used. `None` when there is no file and no variable is set.
+    /// Secret references are kept as written and only resolved when read.
+    pub fn load(
+        global_path: &Path,
+        profile: Option<&str>,
+        cwd: Option<&Path>,
+    ) -> Result<Option<Self>, NotifallError> {
+        let global = read_table(global_path)?;
+        let project_path = cwd.and_then(find_project_file);
+        let project = match project_path.as_deref() {
+            Some(path) => read_table(path)?,
+            None => None,
+        };
+        if let (Some(project), Some(path)) = (project.as_ref(), project_path.as_deref())
+            && let Some(key) = disallowed_project_keys(project).into_iter().next()
+        {
+            return Err(NotifallError::ConfigKey(format!(
+                "{}: {key} cannot be set in a project file (allowed: {})",
+                path.display(),
+                PROJECT_KEYS.join(", ")
+            )));
+        }
+        let mut env = Vec::new();
+        // Keys outside the `sources`, `hooks` and `profiles` maps.
+        for (key, field) in crate::schema::keys() {
+            if key == "profile" {
+                continue;
+            }
+            let var = env_var(&key);
+            let Ok(raw) = std::env::var(&var) else {
+                continue;
+            };
+            let value = parse_env_value(field.kind.key_kind(), &raw)
+                .map_err(|message| NotifallError::InvalidEnv(var.clone(), message))?;
+            env.push((var, nested_table(&key, value)));
+        }
+        if global.is_none() && project.is_none() && env.is_empty() {
+            return Ok(None);
+        }
+
+        let selected = profile
+            .map(|name| name.to_string())
+            .or_else(|| table_str(project.as_ref(), "profile"))
+            .or_else(|| table_str(global.as_ref(), "profile"));
+
+        let mut values = toml::Table::new();
+        let mut origins = BTreeMap::new();
+        let mut profiles = toml::Table::new();
+        if let Some(mut global) = global {
+            if let Some(toml::Value::Table(defined)) = global.remove("profiles") {
+                profiles = defined;
+            }
+            let origin = ConfigOrigin::Global(global_path.to_path_buf());
+            merge_table(&mut values, global, &origin, "", &mut origins);
+        }
+        if let Some(name) = selected.as_deref() {
+            let Some(toml::Value::Table(overlay)) = profiles.remove(name) else {
+                return Err(NotifallError::UnknownProfile(name.to_string()));
+            };
+            let origin = ConfigOrigin::Profile(name.to_string());
+            merge_table(&mut values, overlay, &origin, "", &mut origins);
+        }
+        if let (Some(project), Some(path)) = (project, project_path) {
+            let origin = ConfigOrigin::Project(path);
+            merge_table(&mut values, project, &origin, "", &mut origins);
+        }
+        for (var, layer) in env {
+            merge_table(
+                &mut values,
+                layer,
+                &ConfigOrigin::Env(var),
+                "",
+                &mut origins,
+            );
+        }
+        values.remove("profile");
+        origins.remove("profile");
+
+        check_project_secrets(&values, &origins)?;
+        for key in crate::secret::SECRET_KEYS {
+            if let Some((section, name)) = key.split_once('.')
+                && let Some(toml::Value::String(secret)) =
+                    values.get(section).and_then(|s| s.get(name))
+            {
+                crate::secret::register(secret);
+            }
+        }
+        let config = values.clone().try_into::<Config>()?;
+        Ok(Some(Self {
+            config,
+            values,
+            origins,
+            profile: selected,
+        }))
+    }
+}
+
+/// Value at a dotted path such as `remote.host`.
+pub fn lookup<'a>(table: &'a toml::Table, dotted: &str) -> Option<&'a toml::Value> {
+    let (head, rest) = match dotted.split_once('.') {
+        Some((head, rest)) => (head, Some(rest)),
+        None => (dotted, None),
+    };
+    let value = table.get(head)?;
+    match (value, rest) {
+        (_, None) => Some(value),
+        (toml::Value::Table(inner), Some(rest)) => lookup(inner, rest),
+        _ => None,
+    }
+}
+
+/// Nearest `.ding.toml` in `start` or one of its ancestors.
+pub fn find_project_file(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_FILE))
+        .find(|path| path.is_file())
+}
+
+/// Dotted keys in a project file outside `PROJECT_KEYS`.
+pub fn disallowed_project_keys(table: &toml::Table) -> Vec<String> {
+    let mut found = Vec::new();
+    collect_disallowed(table, "", &mut found);
+    found
+}
+
+fn collect_disallowed(table: &toml::Table, prefix: &str, found: &mut Vec<String>) {
+    for (key, value) in table {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        if PROJECT_KEYS.contains(&path.as_str()) {
+            continue;
+        }
+        let is_parent = PROJECT_KEYS
+            .iter()
+            .any(|allowed| allowed.starts_with(&format!("{path}.")));
+        match value {
+            toml::Value::Table(inner) if is_parent => collect_disallowed(inner, &path, found),
+            _ => found.push(path),
+        }
+    }
+}
+
+// A repository's .ding.toml must not get to run commands or read files just
+// because ding was started inside it.
+fn check_project_secrets(
+    values: &toml::Table,
+    origins: &BTreeMap<String, ConfigOrigin>,
+) -> Result<(), NotifallError> {
+    for key in crate::secret::SECRET_KEYS {
+        let Some((section, name)) = key.split_once('.') else {
+            continue;
+        };
+        if let (Some(toml::Value::Table(reference)), Some(ConfigOrigin::Project(path))) = (
+            values.get(section).and_then(|s| s.get(name)),
+            origins.get(*key),
+        ) && let Some(kind) = ["command", "file"]
+            .into_iter()
+            .find(|kind| reference.contains_key(*kind))
+        {
+            return Err(NotifallError::Secret(format!(
+                "{key}: {{ {kind} = ... }} references are only allowed in the global config, not in {}",
+                path.display()
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn read_table(path: &Path) -> Result<Option<toml::Table>, NotifallError> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = std::fs::read_to_string(path)?;
+    Ok(Some(toml::from_str(&contents)?))
+}
+
+fn table_str(table: Option<&toml::Table>, key: &str) -> Option<String> {
+    table?.get(key)?.as_str().map(|s| s.to_string())
+}
+
+// Tables merge key by key; anything else (including arrays) replaces.
+fn merge_table(
+    base: &mut toml::Table,
+    layer: toml::Table,
+    origin: &ConfigOrigin,
+    prefix: &str,
+    origins: &mut BTreeMap<String, ConfigOrigin>,
+) {
+    for (key, value) in layer {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match (base.get_mut(&key), value) {
+            // A secret reference is one value, not a table to merge into.
+            (Some(toml::Value::Table(existing)), toml::Value::Table(table))
+                if !crate::secret::is_secret_key(&path) =>
+            {
+                merge_table(existing, table, origin, &path, origins);
+            }
+            (_, value) => {
+                origins.retain(|existing, _| {
+                    existing != &path && !existing.starts_with(&format!("{path}."))
+                });
+                record_origins(&value, &path, origin, origins);
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+fn record_origins(
+    value: &toml::Value,
+    path: &str,
+    origin: &ConfigOrigin,
+    origins: &mut BTreeMap<String, ConfigOrigin>,
+) {
+    match value {
+        toml::Value::Table(table) if !crate::secret::is_secret_key(path) => {
+            for (key, value) in table {
+                record_origins(value, &format!("{path}.{key}"), origin, origins);
+            }
+        }
+        _ => {
+            origins.insert(path.to_string(), origin.clone());
+        }
+    }
+}
+
 impl Config {
-    pub fn template() -> &'static str {
-        r#"# ding config
-# default_provider = "macos"
-
-[macos]
-# sound = "default" # use "none" to disable
-# app_bundle_id = "com.apple.Terminal"
-# icon = "/path/to/icon.png"
-
-[remote]
-# host = "127.0.0.1"
-# port = 4280
-# token = "..."
-# timeout_ms = 2000
-# retries = 2
-# fallback_to_local = true
-
-[forward]
-# enabled = true
-# targets = ["remote", "telegram"]
-
-[listener]
-# bind = "0.0.0.0"
-# port = 4280
-# token = "..."
-# require_token = true
-# prefix_hostname = true
-# allow_hosts = ["127.0.0.1"]
-# on_click = "ding focus"
-
-[telegram]
-# bot_token = "123456:ABC..."
-# chat_id = "123456789"
-# parse_mode = "MarkdownV2"
-# silent = false
-
-[sources.claude]
-# icon = "/path/to/claude.icns"
-# app_bundle_id = "com.apple.Terminal"
-
-[sources.codex]
-# icon = "/path/to/openai.icns"
-"#
+    pub fn template() -> String {
+        crate::schema::template()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A fresh directory under the system temp dir for one test.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ding-config-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn table(text: &str) -> toml::Table {
+        toml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn merge_table_merges_tables_and_replaces_values() {
+        let mut base = toml::Table::new();
+        let mut origins = BTreeMap::new();
+        let global = table("[remote]\nhost = \"a\"\nport = 1\n[forward]\ntargets = [\"remote\"]");
+        let origin = ConfigOrigin::Global(PathBuf::from("g"));
+        merge_table(&mut base, global, &origin, "", &mut origins);
+
+        let layer = table("[remote]\nport = 2\n[forward]\ntargets = [\"telegram\"]");
+        let profile = ConfigOrigin::Profile("work".to_string());
+        merge_table(&mut base, layer, &profile, "", &mut origins);
+
+        assert_eq!(lookup(&base, "remote.host").unwrap().as_str(), Some("a"));
+        assert_eq!(lookup(&base, "remote.port").unwrap().as_integer(), Some(2));
+        // Arrays replace rather than append.
+        let targets = lookup(&base, "forward.targets")
+            .unwrap()
+            .as_array()
+            .unwrap();
+        assert_eq!(targets.len(), 1);
+        assert!(matches!(
+            origins.get("remote.host"),
+            Some(ConfigOrigin::Global(_))
+        ));
+        assert!(matches!(
+            origins.get("remote.port"),
+            Some(ConfigOrigin::Profile(_))
+        ));
+    }
+
+    #[test]
+    fn merge_table_replaces_secret_references_whole() {
+        let mut base = table("[remote]\ntoken = { env = \"A\" }");
+        let mut origins = BTreeMap::new();
+        let layer = table("[remote]\ntoken = { file = \"/b\" }");
+        merge_table(
+            &mut base,
+            layer,
+            &ConfigOrigin::Profile("p".into()),
+            "",
+            &mut origins,
+        );
+
+        let token = lookup(&base, "remote.token").unwrap().as_table().unwrap();
+        assert_eq!(token.len(), 1);
+        assert!(token.contains_key("file"));
+        assert!(origins.contains_key("remote.token"));
+        assert!(!origins.contains_key("remote.token.file"));
+    }
+
+    #[test]
+    fn load_applies_profile_then_project() {
+        let dir = scratch("layers");
+        let global = dir.join("config.toml");
+        std::fs::write(
+            &global,
+            "default_provider = \"macos\"\nprofile = \"work\"\n\
+             [profiles.work]\ndefault_provider = \"telegram\"\n\
+             [profiles.work.remote]\nhost = \"work.local\"\n\
+             [sources.claude]\ndisplay_name = \"Claude\"\n",
+        )
+        .unwrap();
+        let repo = dir.join("repo");
+        std::fs::create_dir_all(repo.join("sub")).unwrap();
+        std::fs::write(
+            repo.join(PROJECT_FILE),
+            "[sources.claude]\ndisplay_name = \"Repo\"\n",
+        )
+        .unwrap();
+
+        let resolved = ResolvedConfig::load(&global, None, Some(&repo.join("sub")))
+            .unwrap()
+            .unwrap();
+        assert_eq!(resolved.profile.as_deref(), Some("work"));
+        assert_eq!(
+            resolved.config.default_provider.as_deref(),
+            Some("telegram")
+        );
+        assert_eq!(
+            resolved.config.remote.unwrap().host.as_deref(),
+            Some("work.local")
+        );
+        let claude = &resolved.config.sources.unwrap()["claude"];
+        assert_eq!(claude.display_name.as_deref(), Some("Repo"));
+        assert!(matches!(
+            resolved.origins.get("sources.claude.display_name"),
+            Some(ConfigOrigin::Project(_))
+        ));
+        assert!(!resolved.values.contains_key("profile"));
+    }
+
+    #[test]
+    fn load_rejects_unknown_profile() {
+        let dir = scratch("unknown-profile");
+        let global = dir.join("config.toml");
+        std::fs::write(&global, "default_provider = \"macos\"\n").unwrap();
+        let err = ResolvedConfig::load(&global, Some("nope"), None).unwrap_err();
+        assert!(matches!(err, NotifallError::UnknownProfile(name) if name == "nope"));
+    }
+
+    #[test]
+    fn load_limits_project_files() {
+        let dir = scratch("project-keys");
+        let global = dir.join("config.toml");
+        std::fs::write(&global, "default_provider = \"macos\"\n").unwrap();
+        std::fs::write(dir.join(PROJECT_FILE), "[remote]\nhost = \"evil\"\n").unwrap();
+        let err = ResolvedConfig::load(&global, None, Some(&dir)).unwrap_err();
+        assert!(matches!(err, NotifallError::ConfigKey(message) if message.contains("remote")));
+    }
+
+    #[test]
+    fn project_secrets_cannot_run_commands_or_read_files() {
+        let values = table("[telegram]\nbot_token = { command = \"true\" }");
+        let project = ConfigOrigin::Project(PathBuf::from(".ding.toml"));
+        let mut origins = BTreeMap::new();
+        origins.insert("telegram.bot_token".to_string(), project);
+        let err = check_project_secrets(&values, &origins).unwrap_err();
+        assert!(matches!(
+            err,
+            NotifallError::Secret(message) if message == "telegram.bot_token: { command = ... } \
+                references are only allowed in the global config, not in .ding.toml"
+        ));
+
+        let values = table("[telegram]\nbot_token = { env = \"TOKEN\" }");
+        assert!(check_project_secrets(&values, &origins).is_ok());
+
+        let values = table("[telegram]\nbot_token = { file = \"~/token\" }");
+        origins.insert(
+            "telegram.bot_token".to_string(),
+            ConfigOrigin::Global(PathBuf::from("config.toml")),
+        );
+        assert!(check_project_secrets(&values, &origins).is_ok());
+    }
+
+    #[test]
+    fn disallowed_project_keys_lists_each_key() {
+        let project = table(
+            "profile = \"x\"\n[macos]\ntitle_template = \"t\"\nsound = \"s\"\n\
+             [sources.claude]\nicon = \"i\"\n[listener]\non_click = \"c\"",
+        );
+        assert_eq!(
+            disallowed_project_keys(&project),
+            vec!["listener".to_string(), "macos.sound".to_string()]
+        );
+    }
+
+    #[test]
+    fn parse_env_value_by_kind() {
+        assert_eq!(
+            parse_env_value(KeyKind::String, "  host  ").unwrap(),
+            toml::Value::String("host".into())
+        );
+        for raw in ["1", "true", "YES", "on"] {
+            assert_eq!(
+                parse_env_value(KeyKind::Bool, raw).unwrap(),
+                toml::Value::Boolean(true)
+            );
+        }
+        for raw in ["0", "false", "No", "off"] {
+            assert_eq!(
+                parse_env_value(KeyKind::Bool, raw).unwrap(),
+                toml::Value::Boolean(false)
+            );
+        }
+        assert_eq!(
+            parse_env_value(KeyKind::Integer, "4280").unwrap(),
+            toml::Value::Integer(4280)
+        );
+        assert_eq!(
+            parse_env_value(KeyKind::List, "remote, telegram,,").unwrap(),
+            toml::Value::Array(vec!["remote".into(), "telegram".into()])
+        );
+        assert_eq!(
+            parse_env_value(KeyKind::List, "").unwrap(),
+            toml::Value::Array(vec![])
+        );
+    }
+
+    #[test]
+    fn parse_env_value_rejects_bad_values() {
+        assert!(parse_env_value(KeyKind::Bool, "maybe").is_err());
+        assert!(parse_env_value(KeyKind::Integer, "12a").is_err());
+    }
+
+    #[test]
+    fn env_var_names() {
+        assert_eq!(env_var("remote.host"), "DING_REMOTE_HOST");
+        assert_eq!(env_var("default_provider"), "DING_DEFAULT_PROVIDER");
+        assert_eq!(
+            nested_table("remote.port", toml::Value::Integer(1)),
+            table("[remote]\nport = 1")
+        );
     }
 }
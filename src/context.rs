@@ -57,7 +57,10 @@ fn detect_tmux() -> Option<TmuxContext> {
         session: parts[0].to_string(),
         window: parts[1].to_string(),
         pane: parts[2].to_string(),
-        client: parts.get(3).map(|s| s.to_string()).filter(|s| !s.is_empty()),
+        client: parts
+            .get(3)
+            .map(|s| s.to_string())
+            .filter(|s| !s.is_empty()),
     })
 }
 
@@ -0,0 +1,149 @@
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+pub const SERVICE_TYPE: &str = "_ding._tcp.local.";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscoveredListener {
+    pub name: String,
+    pub hostname: String,
+    pub port: u16,
+    pub addresses: Vec<IpAddr>,
+    pub auth: Option<String>,
+}
+
+impl DiscoveredListener {
+    /// Address to connect to, preferring routable IPv4 over loopback and IPv6.
+    pub fn address(&self) -> Option<IpAddr> {
+        self.addresses
+            .iter()
+            .find(|ip| ip.is_ipv4() && !ip.is_loopback())
+            .or_else(|| self.addresses.iter().find(|ip| ip.is_ipv4()))
+            .or_else(|| self.addresses.first())
+            .copied()
+    }
+}
+
+/// Advertise a listener over mDNS/DNS-SD. The returned daemon keeps
+/// responding to queries until it is dropped or shut down.
+pub fn advertise(name: &str, port: u16, require_token: bool) -> Result<ServiceDaemon, String> {
+    let daemon = ServiceDaemon::new().map_err(|e| e.to_string())?;
+    let host = format!("{}.local.", sanitize_hostname(name));
+    let auth = if require_token { "token" } else { "none" };
+    let properties = [
+        ("version", env!("CARGO_PKG_VERSION")),
+        ("path", "/notify"),
+        ("auth", auth),
+    ];
+    let info = ServiceInfo::new(SERVICE_TYPE, name, &host, "", port, &properties[..])
+        .map_err(|e| e.to_string())?
+        .enable_addr_auto();
+    daemon.register(info).map_err(|e| e.to_string())?;
+    Ok(daemon)
+}
+
+/// Browse for listeners for `timeout`, returning everything that resolved.
+pub fn discover(timeout: Duration) -> Result<Vec<DiscoveredListener>, String> {
+    let mut found = HashMap::new();
+    browse(timeout, |listener| {
+        found.insert(listener.name.clone(), listener);
+        false
+    })?;
+    let mut listeners = found.into_values().collect::<Vec<_>>();
+    listeners.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(listeners)
+}
+
+/// Find a listener by advertised name (case-insensitive), or the first one
+/// that answers when `name` is `None`.
+pub fn resolve(
+    name: Option<&str>,
+    timeout: Duration,
+) -> Result<Option<DiscoveredListener>, String> {
+    let mut matched = None;
+    browse(timeout, |listener| {
+        let wanted = name.is_none_or(|name| listener.name.eq_ignore_ascii_case(name));
+        if wanted && listener.address().is_some() {
+            matched = Some(listener);
+            return true;
+        }
+        false
+    })?;
+    Ok(matched)
+}
+
+// Calls `on_found` for each resolved listener until it returns true or the
+// timeout expires.
+fn browse(
+    timeout: Duration,
+    mut on_found: impl FnMut(DiscoveredListener) -> bool,
+) -> Result<(), String> {
+    let daemon = ServiceDaemon::new().map_err(|e| e.to_string())?;
+    let receiver = daemon.browse(SERVICE_TYPE).map_err(|e| e.to_string())?;
+    let deadline = Instant::now() + timeout;
+    let suffix = format!(".{SERVICE_TYPE}");
+
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        let event = match receiver.recv_timeout(remaining) {
+            Ok(event) => event,
+            Err(_) => break,
+        };
+        let ServiceEvent::ServiceResolved(service) = event else {
+            continue;
+        };
+        let name = unescape_instance_name(
+            service
+                .fullname
+                .strip_suffix(&suffix)
+                .unwrap_or(&service.fullname),
+        );
+        let listener = DiscoveredListener {
+            name,
+            hostname: service.host.trim_end_matches('.').to_string(),
+            port: service.port,
+            addresses: service.addresses.iter().map(|ip| ip.to_ip_addr()).collect(),
+            auth: service
+                .txt_properties
+                .get_property_val_str("auth")
+                .map(|s| s.to_string()),
+        };
+        if on_found(listener) {
+            break;
+        }
+    }
+
+    let _ = daemon.shutdown();
+    Ok(())
+}
+
+// Undo the DNS-SD escaping of '.' and '\' in instance names.
+fn unescape_instance_name(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\\' {
+            if let Some(next) = chars.next() {
+                out.push(next);
+            }
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+fn sanitize_hostname(name: &str) -> String {
+    let host = name
+        .trim_end_matches(".local")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect::<String>();
+    if host.is_empty() {
+        "ding".to_string()
+    } else {
+        host
+    }
+}
@@ -23,8 +23,79 @@ pub enum NotifallError {
     NoProviderAvailable,
     #[error("--background requires --on-click")]
     BackgroundRequiresOnClick,
+    #[error("--batch cannot be combined with --wait-for-click")]
+    BatchWithWait,
+    #[error("invalid action: {0}")]
+    InvalidAction(String),
     #[error("{0}")]
     RemoteForwardMissingHost(String),
+    #[error("{0}")]
+    InstallConflict(String),
+    #[error("unknown hook adapter: {0} (add a [hooks.{0}] section to the config)")]
+    UnknownHook(String),
+    #[error("unknown profile: {0} (add a [profiles.{0}] section to the config)")]
+    UnknownProfile(String),
+    #[error("invalid {0}: {1}")]
+    InvalidEnv(String, String),
+    #[error("{0}")]
+    ConfigKey(String),
+    #[error("config has {0} problem(s)")]
+    ConfigInvalid(usize),
+    #[error("{0} check(s) failed")]
+    DoctorFailed(usize),
+    #[error("{failed} of {total} forward target(s) failed")]
+    ForwardPartial { failed: usize, total: usize },
+    #[error("notification was dismissed without a click")]
+    Dismissed,
+    #[error("secret error: {0}")]
+    Secret(String),
+    #[error("template error: {0}")]
+    Template(String),
+    #[error("not inside a git repository (use --global to install for every repository)")]
+    NotGitRepository,
     #[error("HOME is not set")]
     MissingHome,
 }
+
+impl NotifallError {
+    /// Exit status for this error; the values are documented in the README so
+    /// scripts can branch on them. clap exits with 2 on usage errors.
+    pub fn exit_code(&self) -> i32 {
+        match self.class() {
+            "config" => 3,
+            "unavailable" => 4,
+            "delivery" => 5,
+            "partial" => 6,
+            "timeout" => 7,
+            "dismissed" => 8,
+            _ => 1,
+        }
+    }
+
+    /// Failure class reported as `error.kind` in `--json` output.
+    pub fn class(&self) -> &'static str {
+        match self {
+            NotifallError::ConfigParse(_)
+            | NotifallError::ConfigEdit(_)
+            | NotifallError::ConfigExists(_)
+            | NotifallError::RemoteForwardMissingHost(_)
+            | NotifallError::UnknownHook(_)
+            | NotifallError::UnknownProfile(_)
+            | NotifallError::InvalidEnv(..)
+            | NotifallError::ConfigKey(_)
+            | NotifallError::ConfigInvalid(_)
+            | NotifallError::Secret(_)
+            | NotifallError::Template(_)
+            | NotifallError::Provider(ProviderError::NotConfigured(_)) => "config",
+            NotifallError::ProviderUnsupported(_)
+            | NotifallError::NoProviderAvailable
+            | NotifallError::Provider(ProviderError::Unsupported)
+            | NotifallError::Provider(ProviderError::Unreachable(_)) => "unavailable",
+            NotifallError::Provider(ProviderError::Message(_)) => "delivery",
+            NotifallError::ForwardPartial { .. } => "partial",
+            NotifallError::Provider(ProviderError::Timeout(_)) => "timeout",
+            NotifallError::Dismissed => "dismissed",
+            _ => "error",
+        }
+    }
+}
@@ -0,0 +1,752 @@
+use crate::config::{Config, HookAdapterConfig, HookRule, SourceConfig};
+use crate::logging;
+use crate::notification::Urgency;
+
+// Hook events `ding install claude` can register. Only the ones enabled when
+// installing are registered (see `enabled_events`); urgency, sound and
+// disabling are still decided at hook time.
+pub const CLAUDE_HOOK_EVENTS: &[&str] = &[
+    "Notification",
+    "Stop",
+    "SubagentStop",
+    "PreToolUse",
+    "PostToolUse",
+    "UserPromptSubmit",
+    "SessionStart",
+    "SessionEnd",
+    "PreCompact",
+];
+
+// Hook events registered by `ding install gemini`.
+pub const GEMINI_HOOK_EVENTS: &[&str] = &["Notification", "AfterAgent", "SessionEnd"];
+
+const CLAUDE_PRESET: &str = r#"
+event = "/hook_event_name"
+subtype = "/notification_type"
+message = ["/message", "/prompt", "/tool_name"]
+
+[on.Notification]
+title = ["/notification_type", "Notification"]
+
+[on.permission_prompt]
+urgency = "high"
+
+[on.Stop]
+title = "Finished"
+message = ["/message", "Task completed"]
+
+[on.SubagentStop]
+title = "Subagent Finished"
+message = ["/message", "Task completed"]
+urgency = "low"
+
+[on.PreToolUse]
+enabled = false
+title = "Tool Request"
+message = [
+    "{/tool_name}: {/tool_input/command}",
+    "{/tool_name}: {/tool_input/file_path}",
+    "{/tool_name}: {/tool_input/notebook_path}",
+    "{/tool_name}: {/tool_input/pattern}",
+    "{/tool_name}: {/tool_input/url}",
+    "{/tool_name}: {/tool_input/query}",
+    "{/tool_name}: {/tool_input/description}",
+    "/tool_name",
+]
+
+[on.PostToolUse]
+title = "Tool Failed"
+message = "{/tool_name}: {/tool_error}"
+urgency = "high"
+
+[on.UserPromptSubmit]
+enabled = false
+title = "Prompt Submitted"
+
+[on.SessionStart]
+enabled = false
+title = "Session Started"
+message = "/source"
+
+[on.SessionEnd]
+title = "Session Ended"
+message = "/reason"
+urgency = "low"
+
+[on.PreCompact]
+title = "Compacting"
+message = "{/trigger} compaction"
+urgency = "low"
+"#;
+
+const CODEX_PRESET: &str = r#"
+event = "/type"
+events = ["agent-turn-complete"]
+title = "Turn Complete"
+message = ["/last-assistant-message", "/input_messages"]
+"#;
+
+const GEMINI_PRESET: &str = r#"
+event = "/hook_event_name"
+subtype = "/notification_type"
+message = "/message"
+
+[on.Notification]
+title = ["/notification_type", "Notification"]
+
+[on.ToolPermission]
+title = "Permission Request"
+message = ["/message", "/details/title", "/details/command"]
+urgency = "high"
+
+[on.AfterAgent]
+title = "Turn Complete"
+message = ["/prompt_response", "/prompt"]
+
+[on.SessionEnd]
+enabled = false
+title = "Session Ended"
+message = "/reason"
+urgency = "low"
+"#;
+
+// The plugin forwards only the preset's `events`; everything else (message
+// streaming, file watching) is far too chatty to spawn a process for.
+const OPENCODE_PRESET: &str = r#"
+event = "/type"
+events = ["session.idle", "session.error", "permission.updated", "permission.asked"]
+project = "/directory"
+
+[on."session.idle"]
+title = "Finished"
+message = "Waiting for input"
+
+[on."session.error"]
+title = "Error"
+message = ["/properties/error/data/message", "/properties/error/name", "Session failed"]
+urgency = "high"
+
+[on."permission.updated"]
+title = "Permission Request"
+message = ["/properties/title", "/properties/type", "Permission required"]
+urgency = "high"
+
+[on."permission.asked"]
+title = "Permission Request"
+message = ["/properties/title", "/properties/permission", "Permission required"]
+urgency = "high"
+"#;
+
+// Payload built by `git_payload` from the environment of a ding git hook.
+const GIT_PRESET: &str = r#"
+event = "/hook"
+project = "/repo_root"
+urgency = "/outcome"
+urgency_map = { failed = "high" }
+
+[on.post-commit]
+title = "Committed on {/branch}"
+message = "{/commit} {/subject}"
+
+[on.post-merge]
+title = ["Merge {/outcome} on {/branch}", "Merged into {/branch}"]
+message = "{/subject}"
+
+[on.pre-push]
+title = ["Push checks {/outcome} ({/duration})", "Pushing {/branch}"]
+message = ["{/branch} to {/remote}: {/subject}", "{/branch}: {/subject}"]
+
+[on.post-checkout]
+enabled = false
+title = "Checked out {/branch}"
+message = "{/commit} {/subject}"
+"#;
+
+/// Notification fields produced by a hook adapter.
+#[derive(Debug, Clone)]
+pub struct HookNotification {
+    pub source: String,
+    pub title: String,
+    pub message: String,
+    pub project: Option<String>,
+    pub urgency: Option<Urgency>,
+    pub sound: Option<String>,
+}
+
+pub fn preset(name: &str) -> Option<HookAdapterConfig> {
+    let raw = match name {
+        "claude" => CLAUDE_PRESET,
+        "codex" => CODEX_PRESET,
+        "gemini" => GEMINI_PRESET,
+        "opencode" => OPENCODE_PRESET,
+        "git" => GIT_PRESET,
+        _ => return None,
+    };
+    Some(toml::from_str(raw).expect("built-in hook presets are valid"))
+}
+
+/// Source of the OpenCode plugin that runs `<exe> hook opencode <event json>`.
+pub fn opencode_plugin(exe: &str) -> String {
+    let exe = serde_json::Value::from(exe);
+    let events = preset("opencode")
+        .and_then(|adapter| adapter.events)
+        .unwrap_or_default();
+    let events = serde_json::Value::from(events);
+    format!(
+        r#"// Installed by `ding install opencode`: forwards OpenCode events to ding.
+const DING = {exe};
+const EVENTS = new Set({events});
+
+export const DingPlugin = async ({{ $, directory }}) => ({{
+  event: async ({{ event }}) => {{
+    if (!EVENTS.has(event.type)) return;
+    const payload = JSON.stringify({{ ...event, directory }});
+    await $`${{DING}} hook opencode ${{payload}}`.quiet().nothrow();
+  }},
+}});
+"#
+    )
+}
+
+pub fn is_opencode_plugin(source: &str) -> bool {
+    source.starts_with("// Installed by `ding install opencode`")
+}
+
+/// Adapter for `ding hook <name>`: a built-in preset, a `[hooks.<name>]`
+/// entry, or that entry layered over the preset of the same name.
+pub fn adapter(name: &str, config: Option<&Config>) -> Option<HookAdapterConfig> {
+    let configured = config
+        .and_then(|c| c.hooks.as_ref())
+        .and_then(|hooks| hooks.get(name))
+        .cloned();
+    match (preset(name), configured) {
+        (Some(base), Some(over)) => Some(layer(base, over)),
+        (base, over) => base.or(over),
+    }
+}
+
+/// The subset of `events` that `name` notifies for under `config`. Claude Code
+/// spawns a hook process for every registered event, so `ding install` skips
+/// the ones that are off instead of paying for a no-op on each tool call.
+pub fn enabled_events(
+    name: &str,
+    events: &[&'static str],
+    config: Option<&Config>,
+) -> Vec<&'static str> {
+    let adapter = adapter(name, config).unwrap_or_default();
+    let source = adapter.source.as_deref().unwrap_or(name);
+    let overrides = config
+        .and_then(|c| c.sources.as_ref())
+        .and_then(|sources| sources.get(source))
+        .and_then(|cfg| cfg.events.as_ref());
+    events
+        .iter()
+        .copied()
+        .filter(|event| {
+            let preset = adapter
+                .on
+                .as_ref()
+                .and_then(|on| on.get(*event))
+                .and_then(|rules| rules.enabled);
+            let configured = overrides
+                .and_then(|events| events.get(*event))
+                .and_then(|event| event.enabled);
+            configured.or(preset).unwrap_or(true)
+        })
+        .collect()
+}
+
+fn layer(base: HookAdapterConfig, over: HookAdapterConfig) -> HookAdapterConfig {
+    let mut on = base.on.unwrap_or_default();
+    for (key, rules) in over.on.unwrap_or_default() {
+        let entry = on.entry(key).or_default();
+        entry.enabled = rules.enabled.or(entry.enabled);
+        entry.title = rules.title.or(entry.title.take());
+        entry.message = rules.message.or(entry.message.take());
+        entry.urgency = rules.urgency.or(entry.urgency);
+    }
+    HookAdapterConfig {
+        source: over.source.or(base.source),
+        event: over.event.or(base.event),
+        subtype: over.subtype.or(base.subtype),
+        events: over.events.or(base.events),
+        title: over.title.or(base.title),
+        message: over.message.or(base.message),
+        project: over.project.or(base.project),
+        urgency: over.urgency.or(base.urgency),
+        urgency_map: over.urgency_map.or(base.urgency_map),
+        on: (!on.is_empty()).then_some(on),
+    }
+}
+
+/// Apply `adapter` to a hook payload. Returns `None` when the event is
+/// filtered out or disabled.
+pub fn render(
+    name: &str,
+    adapter: &HookAdapterConfig,
+    mut payload: serde_json::Value,
+    source_config: Option<&SourceConfig>,
+) -> Option<HookNotification> {
+    if !prepare(name, &mut payload) {
+        logging::debug("hooks", &format!("{name}: event dropped by the preset"));
+        return None;
+    }
+
+    let event = adapter.event.as_ref().and_then(|rule| eval(rule, &payload));
+    let subtype = adapter
+        .subtype
+        .as_ref()
+        .and_then(|rule| eval(rule, &payload));
+    // Least to most specific, for both `on.<key>` and `[sources.*.events.<key>]`.
+    let keys = event.iter().chain(subtype.iter()).collect::<Vec<_>>();
+    logging::debug(
+        "hooks",
+        &format!(
+            "{name}: event={} subtype={}",
+            event.as_deref().unwrap_or("(none)"),
+            subtype.as_deref().unwrap_or("(none)")
+        ),
+    );
+
+    if let Some(allowed) = adapter.events.as_ref()
+        && !keys.iter().any(|key| allowed.contains(key))
+    {
+        logging::debug("hooks", &format!("{name}: skipped, not in events"));
+        return None;
+    }
+
+    let mut enabled = true;
+    let mut title_rule = adapter.title.as_ref();
+    let mut message_rule = adapter.message.as_ref();
+    let mut urgency = adapter
+        .urgency
+        .as_ref()
+        .and_then(|rule| eval(rule, &payload))
+        .and_then(|value| map_urgency(&value, adapter));
+    for key in &keys {
+        let Some(rules) = adapter.on.as_ref().and_then(|on| on.get(key.as_str())) else {
+            continue;
+        };
+        enabled = rules.enabled.unwrap_or(enabled);
+        title_rule = rules.title.as_ref().or(title_rule);
+        message_rule = rules.message.as_ref().or(message_rule);
+        urgency = rules.urgency.or(urgency);
+    }
+
+    let mut sound = None;
+    let overrides = source_config.and_then(|cfg| cfg.events.as_ref());
+    for key in &keys {
+        let Some(event) = overrides.and_then(|events| events.get(key.as_str())) else {
+            continue;
+        };
+        enabled = event.enabled.unwrap_or(enabled);
+        urgency = event.urgency.or(urgency);
+        sound = event.sound.clone().or(sound);
+    }
+    if !enabled {
+        logging::debug(
+            "hooks",
+            &format!("{name}: skipped, disabled for this event"),
+        );
+        return None;
+    }
+    logging::debug("hooks", &format!("{name}: notifying"));
+
+    let title = title_rule
+        .and_then(|rule| eval(rule, &payload))
+        .map(|title| humanize_identifier(&title))
+        .or_else(|| event.as_deref().map(humanize_label))
+        .unwrap_or_else(|| humanize_label(name));
+    let message = message_rule
+        .and_then(|rule| eval(rule, &payload))
+        .unwrap_or_default();
+    let project = adapter
+        .project
+        .as_ref()
+        .and_then(|rule| eval(rule, &payload));
+
+    Some(HookNotification {
+        source: adapter.source.clone().unwrap_or_else(|| name.to_string()),
+        title,
+        message,
+        project,
+        urgency,
+        sound,
+    })
+}
+
+// Built-in presets may need a derived field that a pointer can't express.
+// Returns false to drop the event.
+fn prepare(name: &str, payload: &mut serde_json::Value) -> bool {
+    if name != "claude" {
+        return true;
+    }
+    if payload.get("hook_event_name").and_then(|v| v.as_str()) != Some("PostToolUse") {
+        return true;
+    }
+    // PostToolUse fires after every tool call; only failures are worth a notification.
+    match claude_tool_failure(payload) {
+        Some(error) => {
+            if let Some(obj) = payload.as_object_mut() {
+                obj.insert("tool_error".to_string(), serde_json::Value::String(error));
+            }
+            true
+        }
+        None => false,
+    }
+}
+
+fn claude_tool_failure(payload: &serde_json::Value) -> Option<String> {
+    let response = payload.get("tool_response")?;
+    if let Some(text) = response.as_str() {
+        return text.starts_with("Error").then(|| text.to_string());
+    }
+    if let Some(error) = response.get("error").and_then(|v| v.as_str()) {
+        return Some(error.to_string());
+    }
+    let exit_code = response
+        .get("exit_code")
+        .or_else(|| response.get("exitCode"))
+        .and_then(|v| v.as_i64());
+    let failed = response.get("is_error").and_then(|v| v.as_bool()) == Some(true)
+        || response.get("success").and_then(|v| v.as_bool()) == Some(false)
+        || exit_code.is_some_and(|code| code != 0);
+    if !failed {
+        return None;
+    }
+    let stderr = response
+        .get("stderr")
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty());
+    Some(match (stderr, exit_code) {
+        (Some(stderr), _) => stderr.to_string(),
+        (None, Some(code)) => format!("exit code {code}"),
+        (None, None) => "failed".to_string(),
+    })
+}
+
+/// Describe the repository state for a hook installed by `ding install git`,
+/// from the `DING_GIT_*` variables it sets. Returns `None` for events not worth
+/// reporting (file checkouts).
+pub fn git_payload() -> Option<serde_json::Value> {
+    let env = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+    let hook = env("DING_GIT_HOOK")?;
+    let arg = |n: u8| env(&format!("DING_GIT_ARG{n}"));
+    if hook == "post-checkout" && arg(3).as_deref() == Some("0") {
+        return None;
+    }
+    let status = env("DING_GIT_STATUS")
+        .and_then(|v| v.parse::<i64>().ok())
+        .unwrap_or(0);
+    let chained = env("DING_GIT_CHAINED").as_deref() == Some("1");
+    let duration_secs = env("DING_GIT_DURATION")
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(0);
+
+    let branch = crate::git_command(&["rev-parse", "--abbrev-ref", "HEAD"]);
+    let commit = crate::git_command(&["rev-parse", "--short", "HEAD"]);
+    let branch = match (branch.as_deref(), commit.as_deref()) {
+        (Some("HEAD"), Some(commit)) => Some(format!("detached at {commit}")),
+        _ => branch,
+    };
+
+    let mut payload = serde_json::json!({
+        "hook": hook,
+        "status": status,
+        "duration_secs": duration_secs,
+        "repo_root": crate::git_command(&["rev-parse", "--show-toplevel"]),
+        "branch": branch,
+        "commit": commit,
+        "subject": crate::git_command(&["log", "-1", "--format=%s"]),
+    });
+    // Only hooks that actually ran something have an outcome worth showing.
+    if chained || status != 0 {
+        payload["outcome"] = serde_json::Value::from(if status == 0 { "passed" } else { "failed" });
+        payload["duration"] = serde_json::Value::from(format_duration(duration_secs));
+    }
+    if hook == "pre-push" {
+        payload["remote"] = serde_json::Value::from(arg(1));
+        payload["remote_url"] = serde_json::Value::from(arg(2));
+    }
+    Some(payload)
+}
+
+/// "45s", "3m 20s", "1h 5m".
+pub fn format_duration(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m {}s", secs / 60, secs % 60),
+        _ => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+/// First candidate of `rule` that fully resolves against `payload`.
+pub fn eval(rule: &HookRule, payload: &serde_json::Value) -> Option<String> {
+    rule.candidates()
+        .iter()
+        .find_map(|candidate| eval_one(candidate, payload))
+}
+
+// "/a/b" is a JSON pointer; anything else is text whose `{/pointer}`
+// placeholders must all resolve.
+fn eval_one(rule: &str, payload: &serde_json::Value) -> Option<String> {
+    if rule.starts_with('/') {
+        return payload.pointer(rule).and_then(value_text);
+    }
+    let mut out = String::with_capacity(rule.len());
+    let mut rest = rule;
+    while let Some(start) = rest.find("{/") {
+        let end = start + rest[start..].find('}')?;
+        out.push_str(&rest[..start]);
+        out.push_str(
+            &payload
+                .pointer(&rest[start + 1..end])
+                .and_then(value_text)?,
+        );
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    let out = out.trim().to_string();
+    (!out.is_empty()).then_some(out)
+}
+
+fn value_text(value: &serde_json::Value) -> Option<String> {
+    let text = match value {
+        serde_json::Value::String(s) => s.trim().to_string(),
+        serde_json::Value::Number(n) => n.to_string(),
+        serde_json::Value::Bool(b) => b.to_string(),
+        serde_json::Value::Array(items) => items
+            .iter()
+            .filter_map(value_text)
+            .collect::<Vec<_>>()
+            .join(" "),
+        _ => return None,
+    };
+    (!text.is_empty()).then_some(text)
+}
+
+fn map_urgency(value: &str, adapter: &HookAdapterConfig) -> Option<Urgency> {
+    if let Some(urgency) = adapter.urgency_map.as_ref().and_then(|map| map.get(value)) {
+        return Some(*urgency);
+    }
+    match value.to_ascii_lowercase().as_str() {
+        "low" => Some(Urgency::Low),
+        "normal" => Some(Urgency::Normal),
+        "high" => Some(Urgency::High),
+        _ => None,
+    }
+}
+
+// Titles that come out as a bare identifier ("permission_prompt",
+// "agent-turn-complete") read better as words.
+fn humanize_identifier(raw: &str) -> String {
+    if raw.contains(char::is_whitespace) || !raw.contains(['_', '-']) {
+        return raw.to_string();
+    }
+    humanize_label(raw)
+}
+
+fn humanize_label(raw: &str) -> String {
+    if raw.trim().is_empty() {
+        return "Notification".to_string();
+    }
+    let mut out = String::with_capacity(raw.len() + 8);
+    let mut prev: Option<char> = None;
+    for ch in raw.chars() {
+        if ch == '_' || ch == '-' {
+            if !out.ends_with(' ') {
+                out.push(' ');
+            }
+            prev = Some(' ');
+            continue;
+        }
+        if let Some(p) = prev
+            && p.is_ascii_lowercase()
+            && ch.is_ascii_uppercase()
+            && !out.ends_with(' ')
+        {
+            out.push(' ');
+        }
+        out.push(ch);
+        prev = Some(ch);
+    }
+    let words: Vec<String> = out
+        .split_whitespace()
+        .map(|w| {
+            let mut chars = w.chars();
+            match chars.next() {
+                Some(first) => {
+                    first.to_uppercase().collect::<String>() + &chars.as_str().to_lowercase()
+                }
+                None => String::new(),
+            }
+        })
+        .filter(|w| !w.is_empty())
+        .collect();
+    if words.is_empty() {
+        "Notification".to_string()
+    } else {
+        words.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn adapter_from(text: &str) -> HookAdapterConfig {
+        toml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn eval_one_pointer() {
+        let payload = json!({"a": {"b": " text "}, "n": 3, "list": ["x", "y"], "empty": ""});
+        assert_eq!(eval_one("/a/b", &payload).as_deref(), Some("text"));
+        assert_eq!(eval_one("/n", &payload).as_deref(), Some("3"));
+        assert_eq!(eval_one("/list", &payload).as_deref(), Some("x y"));
+        assert_eq!(eval_one("/empty", &payload), None);
+        assert_eq!(eval_one("/missing", &payload), None);
+    }
+
+    #[test]
+    fn eval_one_placeholders() {
+        let payload = json!({"tool": "Bash", "input": {"command": "ls"}});
+        assert_eq!(
+            eval_one("{/tool}: {/input/command}", &payload).as_deref(),
+            Some("Bash: ls")
+        );
+        // Every placeholder has to resolve.
+        assert_eq!(eval_one("{/tool}: {/input/path}", &payload), None);
+        assert_eq!(
+            eval_one("plain text", &payload).as_deref(),
+            Some("plain text")
+        );
+        assert_eq!(eval_one("{/tool", &payload), None);
+    }
+
+    #[test]
+    fn eval_takes_first_candidate_that_resolves() {
+        let rule = HookRule::Any(vec![
+            "/missing".into(),
+            "{/b} and {/c}".into(),
+            "fallback".into(),
+        ]);
+        assert_eq!(eval(&rule, &json!({"b": 1})).as_deref(), Some("fallback"));
+        assert_eq!(
+            eval(&rule, &json!({"b": 1, "c": 2})).as_deref(),
+            Some("1 and 2")
+        );
+    }
+
+    #[test]
+    fn render_applies_event_rules() {
+        let adapter = adapter_from(
+            r#"
+            event = "/type"
+            title = "/title"
+            message = "{/type} in {/cwd}"
+            urgency = "/level"
+            urgency_map = { err = "high" }
+
+            [on.done]
+            title = "Done"
+            "#,
+        );
+        let payload = json!({"type": "done", "cwd": "/repo", "level": "err"});
+        let notification = render("aider", &adapter, payload, None).unwrap();
+        assert_eq!(notification.source, "aider");
+        assert_eq!(notification.title, "Done");
+        assert_eq!(notification.message, "done in /repo");
+        assert!(matches!(notification.urgency, Some(Urgency::High)));
+    }
+
+    #[test]
+    fn render_filters_events() {
+        let adapter =
+            adapter_from("event = \"/type\"\nevents = [\"done\"]\n[on.error]\nenabled = false");
+        assert!(render("tool", &adapter, json!({"type": "started"}), None).is_none());
+        assert!(render("tool", &adapter, json!({"type": "done"}), None).is_some());
+
+        let adapter = adapter_from("event = \"/type\"\n[on.error]\nenabled = false");
+        assert!(render("tool", &adapter, json!({"type": "error"}), None).is_none());
+    }
+
+    #[test]
+    fn render_source_event_overrides() {
+        let adapter = adapter_from("event = \"/type\"\n[on.done]\nenabled = false");
+        let source: SourceConfig =
+            toml::from_str("[events.done]\nenabled = true\nurgency = \"low\"\nsound = \"none\"")
+                .unwrap();
+        let notification =
+            render("tool", &adapter, json!({"type": "done"}), Some(&source)).unwrap();
+        assert!(matches!(notification.urgency, Some(Urgency::Low)));
+        assert_eq!(notification.sound.as_deref(), Some("none"));
+        // With no title rule the event name becomes the title.
+        assert_eq!(notification.title, "Done");
+    }
+
+    #[test]
+    fn render_claude_post_tool_use_only_on_failure() {
+        let adapter = preset("claude").unwrap();
+        let ok = json!({"hook_event_name": "PostToolUse", "tool_name": "Bash", "tool_response": {"exit_code": 0}});
+        assert!(render("claude", &adapter, ok, None).is_none());
+        let failed = json!({
+            "hook_event_name": "PostToolUse",
+            "tool_name": "Bash",
+            "tool_response": {"exit_code": 2, "stderr": "boom"},
+        });
+        let notification = render("claude", &adapter, failed, None).unwrap();
+        assert_eq!(notification.title, "Tool Failed");
+        assert_eq!(notification.message, "Bash: boom");
+    }
+
+    #[test]
+    fn presets_parse() {
+        for name in ["claude", "codex", "gemini", "opencode", "git"] {
+            let adapter = preset(name).unwrap();
+            assert!(adapter.event.is_some(), "{name} preset has no event rule");
+        }
+        assert!(preset("aider").is_none());
+    }
+
+    #[test]
+    fn opencode_plugin_forwards_preset_events() {
+        let plugin = opencode_plugin("/usr/bin/ding");
+        assert!(is_opencode_plugin(&plugin));
+        assert!(plugin.contains(
+            r#"new Set(["session.idle","session.error","permission.updated","permission.asked"])"#
+        ));
+    }
+
+    #[test]
+    fn enabled_events_follow_preset_and_sources() {
+        let events = enabled_events("claude", CLAUDE_HOOK_EVENTS, None);
+        assert!(events.contains(&"Stop"));
+        assert!(events.contains(&"PostToolUse"));
+        assert!(!events.contains(&"PreToolUse"));
+        assert!(!events.contains(&"UserPromptSubmit"));
+        assert!(!events.contains(&"SessionStart"));
+
+        let config: Config = toml::from_str(
+            "[sources.claude.events.PreToolUse]\nenabled = true\n[sources.claude.events.Stop]\nenabled = false",
+        )
+        .unwrap();
+        let events = enabled_events("claude", CLAUDE_HOOK_EVENTS, Some(&config));
+        assert!(events.contains(&"PreToolUse"));
+        assert!(!events.contains(&"Stop"));
+    }
+
+    #[test]
+    fn humanized_titles() {
+        assert_eq!(humanize_label("agent-turn-complete"), "Agent Turn Complete");
+        assert_eq!(humanize_label("SessionStart"), "Session Start");
+        assert_eq!(
+            humanize_identifier("permission_prompt"),
+            "Permission Prompt"
+        );
+        assert_eq!(humanize_identifier("Already fine"), "Already fine");
+        assert_eq!(format_duration(200), "3m 20s");
+    }
+}
@@ -0,0 +1,182 @@
+use std::path::Path;
+
+/// Whether `command` runs `hook <target>` of some ding binary. Any install
+/// path matches, so entries left by an older or moved binary are found too.
+pub fn is_ding_hook_command(command: &str, target: &str) -> bool {
+    let Some(program) = command.trim().strip_suffix(&format!(" hook {target}")) else {
+        return false;
+    };
+    is_ding_program(program.trim().trim_matches(|c| c == '"' || c == '\''))
+}
+
+/// Same check for an argv array such as Codex's `notify`.
+pub fn is_ding_hook_argv(argv: &[&str], target: &str) -> bool {
+    matches!(argv, [program, "hook", t] if *t == target && is_ding_program(program))
+}
+
+fn is_ding_program(program: &str) -> bool {
+    Path::new(program)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name == "ding" || name == "ding.exe")
+}
+
+/// Commands of ding's `hook <target>` entries in a Claude-style settings file
+/// (`hooks.<event>[].hooks[].command`), as (event, command) pairs.
+pub fn json_hook_commands(json: &serde_json::Value, target: &str) -> Vec<(String, String)> {
+    let Some(events) = json.get("hooks").and_then(|v| v.as_object()) else {
+        return Vec::new();
+    };
+    let mut found = Vec::new();
+    for (event, groups) in events {
+        for group in groups.as_array().into_iter().flatten() {
+            for hook in group
+                .get("hooks")
+                .and_then(|v| v.as_array())
+                .into_iter()
+                .flatten()
+            {
+                if let Some(command) = hook.get("command").and_then(|v| v.as_str())
+                    && is_ding_hook_command(command, target)
+                {
+                    found.push((event.clone(), command.to_string()));
+                }
+            }
+        }
+    }
+    found
+}
+
+/// Add `group` to each event's hook list, replacing any earlier ding entry
+/// for `target` but leaving the user's other hooks alone.
+pub fn merge_json_hooks(
+    json: &mut serde_json::Value,
+    events: &[&str],
+    target: &str,
+    group: &serde_json::Value,
+) {
+    remove_json_hooks(json, target);
+    if !json.is_object() {
+        *json = serde_json::json!({});
+    }
+    if !json.get("hooks").is_some_and(|v| v.is_object()) {
+        json["hooks"] = serde_json::json!({});
+    }
+    for event in events {
+        let list = &mut json["hooks"][*event];
+        if !list.is_array() {
+            *list = serde_json::json!([]);
+        }
+        if let Some(list) = list.as_array_mut() {
+            list.push(group.clone());
+        }
+    }
+}
+
+/// Drop ding's `hook <target>` entries, pruning groups and events that end up
+/// empty. Returns the number of entries removed.
+pub fn remove_json_hooks(json: &mut serde_json::Value, target: &str) -> usize {
+    let Some(events) = json.get_mut("hooks").and_then(|v| v.as_object_mut()) else {
+        return 0;
+    };
+    let mut removed = 0;
+    let mut emptied = Vec::new();
+    for (event, groups) in events.iter_mut() {
+        let Some(groups) = groups.as_array_mut() else {
+            continue;
+        };
+        let mut removed_here = 0;
+        groups.retain_mut(|group| {
+            let Some(hooks) = group.get_mut("hooks").and_then(|v| v.as_array_mut()) else {
+                return true;
+            };
+            let before = hooks.len();
+            hooks.retain(|hook| {
+                !hook
+                    .get("command")
+                    .and_then(|v| v.as_str())
+                    .is_some_and(|command| is_ding_hook_command(command, target))
+            });
+            removed_here += before - hooks.len();
+            hooks.len() == before || !hooks.is_empty()
+        });
+        if removed_here > 0 && groups.is_empty() {
+            emptied.push(event.clone());
+        }
+        removed += removed_here;
+    }
+    for event in emptied {
+        events.remove(&event);
+    }
+    if removed > 0
+        && events.is_empty()
+        && let Some(obj) = json.as_object_mut()
+    {
+        obj.remove("hooks");
+    }
+    removed
+}
+
+pub const GIT_HOOKS: &[&str] = &["post-commit", "post-merge", "pre-push", "post-checkout"];
+
+// Suffix for a user's own hook that ding moved aside; the ding hook runs it first.
+pub const GIT_CHAINED_SUFFIX: &str = "pre-ding";
+
+const GIT_HOOK_MARKER: &str = "# Installed by `ding install git`";
+
+pub fn is_git_hook_script(source: &str) -> bool {
+    source
+        .lines()
+        .nth(1)
+        .is_some_and(|line| line.starts_with(GIT_HOOK_MARKER))
+}
+
+/// POSIX sh hook that runs the hook it replaced (and, for global installs, the
+/// repository's own hook), then reports to `ding hook git` and exits with the
+/// chained hooks' status so a failing `pre-push` still blocks the push.
+pub fn git_hook_script(exe: &str, hook: &str, global: bool) -> String {
+    let repo_chain = if global {
+        r#"
+# core.hooksPath hides .git/hooks, so run the repository's own hook too.
+repo_hook="$(git rev-parse --git-dir 2>/dev/null)/hooks/$hook"
+if [ "$status" -eq 0 ] && [ -x "$repo_hook" ] && ! [ "$repo_hook" -ef "$0" ]; then
+    chained=1
+    run "$repo_hook" "$@" || status=$?
+fi
+"#
+    } else {
+        ""
+    };
+    format!(
+        r#"#!/bin/sh
+{GIT_HOOK_MARKER}: runs any hook it replaced, then
+# notifies through `ding hook git`. Remove with `ding uninstall git`.
+hook={hook}
+input=
+if [ "$hook" = pre-push ]; then
+    input=$(cat)
+fi
+run() {{
+    if [ -n "$input" ]; then
+        printf '%s\n' "$input" | "$@"
+    else
+        "$@" </dev/null
+    fi
+}}
+
+start=$(date +%s)
+status=0
+chained=0
+if [ -x "$0.{GIT_CHAINED_SUFFIX}" ]; then
+    chained=1
+    run "$0.{GIT_CHAINED_SUFFIX}" "$@" || status=$?
+fi
+{repo_chain}
+DING_GIT_HOOK=$hook DING_GIT_STATUS=$status DING_GIT_CHAINED=$chained \
+DING_GIT_DURATION=$(($(date +%s) - start)) \
+DING_GIT_ARG1=$1 DING_GIT_ARG2=$2 DING_GIT_ARG3=$3 \
+    {exe} hook git </dev/null >/dev/null 2>&1 || true
+exit $status
+"#
+    )
+}
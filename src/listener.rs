@@ -0,0 +1,441 @@
+use crate::provider::DeliveryOutcome;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Condvar, Mutex, Weak};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// Finished entries are kept around long enough for a slow sender to pick them up.
+const STATUS_RETENTION: Duration = Duration::from_secs(15 * 60);
+
+// Threads that may block at once on a click or a `/status` long-poll.
+const MAX_WAITERS: usize = 64;
+
+/// Shared state of a running `ding listen`, handed to request threads.
+#[derive(Default)]
+pub struct ListenerState {
+    pub status: StatusStore,
+    pub events: EventBus,
+    pub metrics: Metrics,
+    waiters: AtomicUsize,
+}
+
+impl ListenerState {
+    /// Room for one more blocked thread, given back when the slot is
+    /// dropped; `None` once `MAX_WAITERS` are taken.
+    pub fn try_wait(self: &Arc<Self>) -> Option<WaitSlot> {
+        self.waiters
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (n < MAX_WAITERS).then_some(n + 1)
+            })
+            .ok()?;
+        Some(WaitSlot(Arc::clone(self)))
+    }
+}
+
+pub struct WaitSlot(Arc<ListenerState>);
+
+impl Drop for WaitSlot {
+    fn drop(&mut self) {
+        self.0.waiters.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum DeliveryState {
+    Pending,
+    Finished(Option<DeliveryOutcome>),
+    Failed(String),
+}
+
+impl DeliveryState {
+    pub fn is_pending(&self) -> bool {
+        matches!(self, DeliveryState::Pending)
+    }
+
+    pub fn to_json(&self, id: &str) -> serde_json::Value {
+        match self {
+            DeliveryState::Pending => serde_json::json!({
+                "id": id,
+                "status": "pending",
+            }),
+            DeliveryState::Finished(outcome) => serde_json::json!({
+                "id": id,
+                "status": "done",
+                "outcome": outcome
+                    .as_ref()
+                    .map(DeliveryOutcome::to_json)
+                    .unwrap_or(serde_json::Value::Null),
+            }),
+            DeliveryState::Failed(error) => serde_json::json!({
+                "id": id,
+                "status": "error",
+                "error": error,
+            }),
+        }
+    }
+}
+
+struct StatusEntry {
+    updated: Instant,
+    state: DeliveryState,
+}
+
+/// Delivery outcomes of notifications received by the listener, keyed by id.
+#[derive(Default)]
+pub struct StatusStore {
+    entries: Mutex<HashMap<String, StatusEntry>>,
+    changed: Condvar,
+    counter: AtomicU64,
+}
+
+impl StatusStore {
+    pub fn register(&self) -> String {
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let seq = self.counter.fetch_add(1, Ordering::Relaxed);
+        let id = format!("{ts:x}-{seq:x}");
+
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|_, entry| entry.updated.elapsed() < STATUS_RETENTION);
+        entries.insert(
+            id.clone(),
+            StatusEntry {
+                updated: Instant::now(),
+                state: DeliveryState::Pending,
+            },
+        );
+        id
+    }
+
+    pub fn finish(&self, id: &str, state: DeliveryState) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = entries.get_mut(id) {
+            entry.updated = Instant::now();
+            entry.state = state;
+        }
+        self.changed.notify_all();
+    }
+
+    /// Notifications received but not delivered (or failed) yet.
+    pub fn pending(&self) -> usize {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .values()
+            .filter(|entry| entry.state.is_pending())
+            .count()
+    }
+
+    /// Block until the entry leaves `Pending` or `timeout` elapses.
+    /// Returns `None` for unknown (or expired) ids.
+    pub fn wait(&self, id: &str, timeout: Duration) -> Option<DeliveryState> {
+        let deadline = Instant::now() + timeout;
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            let state = entries.get(id)?.state.clone();
+            let now = Instant::now();
+            if !state.is_pending() || now >= deadline {
+                return Some(state);
+            }
+            entries = self
+                .changed
+                .wait_timeout(entries, deadline - now)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ListenerEvent {
+    pub kind: &'static str,
+    pub data: serde_json::Value,
+}
+
+impl ListenerEvent {
+    /// Render as a Server-Sent Events frame.
+    pub fn to_sse(&self) -> String {
+        format!("event: {}\ndata: {}\n\n", self.kind, self.data)
+    }
+}
+
+// Open `/events` streams, and the events each may fall behind by before it
+// is dropped.
+const MAX_SUBSCRIBERS: usize = 16;
+const SUBSCRIBER_BACKLOG: usize = 256;
+
+/// Fan-out of listener activity to `/events` subscribers.
+#[derive(Default)]
+pub struct EventBus {
+    subscribers: Mutex<Vec<Subscriber>>,
+}
+
+struct Subscriber {
+    tx: SyncSender<ListenerEvent>,
+    // Dead once the stream's `Subscription` is dropped.
+    alive: Weak<()>,
+}
+
+/// An `/events` stream's end of the bus; holding it keeps the stream counted.
+pub struct Subscription {
+    pub events: Receiver<ListenerEvent>,
+    _alive: Arc<()>,
+}
+
+impl EventBus {
+    /// `None` once `MAX_SUBSCRIBERS` streams are open.
+    pub fn subscribe(&self) -> Option<Subscription> {
+        let mut subscribers = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+        subscribers.retain(|subscriber| subscriber.alive.strong_count() > 0);
+        if subscribers.len() >= MAX_SUBSCRIBERS {
+            return None;
+        }
+        let (tx, events) = mpsc::sync_channel(SUBSCRIBER_BACKLOG);
+        let alive = Arc::new(());
+        subscribers.push(Subscriber {
+            tx,
+            alive: Arc::downgrade(&alive),
+        });
+        Some(Subscription {
+            events,
+            _alive: alive,
+        })
+    }
+
+    pub fn publish(&self, kind: &'static str, mut data: serde_json::Value) {
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        if let Some(obj) = data.as_object_mut() {
+            obj.insert("ts".to_string(), serde_json::Value::from(ts));
+        }
+        let event = ListenerEvent { kind, data };
+        // A failed send prunes streams that went away, and ones too slow to keep
+        // up, which then end once they have written what they were sent.
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|subscriber| subscriber.tx.try_send(event.clone()).is_ok());
+    }
+}
+
+// Upper bounds in seconds. Deliveries that wait for a click take as long as
+// the user does, hence the long tail.
+const LATENCY_BUCKETS: &[f64] = &[
+    0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 300.0,
+];
+
+#[derive(Default)]
+struct Histogram {
+    // Per bucket, not cumulative; the last slot counts values above every bound.
+    counts: Vec<u64>,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        if self.counts.is_empty() {
+            self.counts = vec![0; LATENCY_BUCKETS.len() + 1];
+        }
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.counts[bucket] += 1;
+        self.sum += value;
+    }
+}
+
+// Distinct `source` label values; later sources are counted as `other`, so a
+// sender cannot grow the metrics without bound.
+const MAX_SOURCES: usize = 32;
+
+#[derive(Default)]
+struct Counters {
+    sources: BTreeSet<String>,
+    received: BTreeMap<String, u64>,
+    delivered: BTreeMap<(String, String), u64>,
+    failed: BTreeMap<(String, String), u64>,
+    auth_failures: BTreeMap<&'static str, u64>,
+    latency: BTreeMap<(String, bool), Histogram>,
+}
+
+/// Counters behind `GET /metrics`, in the Prometheus text format.
+#[derive(Default)]
+pub struct Metrics {
+    counters: Mutex<Counters>,
+}
+
+impl Metrics {
+    pub fn received(&self, source: Option<&str>) {
+        let mut counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        let source = counters.source_label(source);
+        *counters.received.entry(source).or_default() += 1;
+    }
+
+    pub fn delivered(
+        &self,
+        source: Option<&str>,
+        provider: &str,
+        wait_for_click: bool,
+        ok: bool,
+        elapsed: Duration,
+    ) {
+        let mut counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        let key = (counters.source_label(source), provider.to_string());
+        let counter = if ok {
+            &mut counters.delivered
+        } else {
+            &mut counters.failed
+        };
+        *counter.entry(key).or_default() += 1;
+        counters
+            .latency
+            .entry((provider.to_string(), wait_for_click))
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// A request turned away by `allow_hosts` (`host`) or the token (`token`).
+    pub fn auth_failure(&self, reason: &'static str) {
+        let mut counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        *counters.auth_failures.entry(reason).or_default() += 1;
+    }
+
+    pub fn render(&self, queue_depth: usize) -> String {
+        let counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "ding_notifications_received_total",
+            "counter",
+            "Notifications accepted by the listener.",
+        );
+        for (source, count) in &counters.received {
+            sample(
+                &mut out,
+                "ding_notifications_received_total",
+                &[("source", source)],
+                *count,
+            );
+        }
+        for (name, help, values) in [
+            (
+                "ding_notifications_delivered_total",
+                "Notifications shown by the provider.",
+                &counters.delivered,
+            ),
+            (
+                "ding_notifications_failed_total",
+                "Notifications the provider failed to show.",
+                &counters.failed,
+            ),
+        ] {
+            header(&mut out, name, "counter", help);
+            for ((source, provider), count) in values {
+                sample(
+                    &mut out,
+                    name,
+                    &[("source", source), ("provider", provider)],
+                    *count,
+                );
+            }
+        }
+
+        header(
+            &mut out,
+            "ding_auth_failures_total",
+            "counter",
+            "Requests rejected by allow_hosts or the token.",
+        );
+        for reason in ["host", "token"] {
+            let count = counters.auth_failures.get(reason).copied().unwrap_or(0);
+            sample(
+                &mut out,
+                "ding_auth_failures_total",
+                &[("reason", reason)],
+                count,
+            );
+        }
+
+        header(
+            &mut out,
+            "ding_delivery_queue_depth",
+            "gauge",
+            "Notifications received but not yet delivered.",
+        );
+        let _ = writeln!(out, "ding_delivery_queue_depth {queue_depth}");
+
+        let name = "ding_delivery_latency_seconds";
+        header(
+            &mut out,
+            name,
+            "histogram",
+            "Time from receipt until the provider returns, including the click wait when wait_for_click is true.",
+        );
+        for ((provider, wait_for_click), histogram) in &counters.latency {
+            let wait = if *wait_for_click { "true" } else { "false" };
+            let labels = [("provider", provider.as_str()), ("wait_for_click", wait)];
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(&histogram.counts) {
+                cumulative += count;
+                let le = bound.to_string();
+                let bucket_labels = [labels[0], labels[1], ("le", le.as_str())];
+                sample(
+                    &mut out,
+                    &format!("{name}_bucket"),
+                    &bucket_labels,
+                    cumulative,
+                );
+            }
+            let total = histogram.counts.iter().sum::<u64>();
+            let bucket_labels = [labels[0], labels[1], ("le", "+Inf")];
+            sample(&mut out, &format!("{name}_bucket"), &bucket_labels, total);
+            let _ = writeln!(out, "{name}_sum{} {}", label_set(&labels), histogram.sum);
+            sample(&mut out, &format!("{name}_count"), &labels, total);
+        }
+        out
+    }
+}
+
+impl Counters {
+    fn source_label(&mut self, source: Option<&str>) -> String {
+        let source = source.unwrap_or("none");
+        if self.sources.contains(source) {
+            return source.to_string();
+        }
+        if self.sources.len() < MAX_SOURCES {
+            self.sources.insert(source.to_string());
+            return source.to_string();
+        }
+        "other".to_string()
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+fn sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: u64) {
+    let _ = writeln!(out, "{name}{} {value}", label_set(labels));
+}
+
+fn label_set(labels: &[(&str, &str)]) -> String {
+    let labels = labels
+        .iter()
+        .map(|(name, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{name}=\"{value}\"")
+        })
+        .collect::<Vec<_>>();
+    format!("{{{}}}", labels.join(","))
+}
@@ -0,0 +1,266 @@
+use crate::config::LogConfig;
+use crate::secret;
+use serde_json::{Map, Value};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const DEFAULT_MAX_SIZE: u64 = 1024 * 1024;
+const DEFAULT_KEEP: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    fn as_str(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        }
+    }
+}
+
+/// A level name from `schema::LOG_LEVELS`; `Some(None)` is `off`.
+fn parse_level(name: &str) -> Option<Option<Level>> {
+    Some(match name.trim().to_ascii_lowercase().as_str() {
+        "off" => None,
+        "error" => Some(Level::Error),
+        "warn" | "warning" => Some(Level::Warn),
+        "info" => Some(Level::Info),
+        "debug" => Some(Level::Debug),
+        "trace" => Some(Level::Trace),
+        _ => return None,
+    })
+}
+
+struct Settings {
+    // Whether a `[log]` section was given.
+    configured: bool,
+    level: Option<Level>,
+    // `module=level` overrides; the longest matching module wins.
+    modules: Vec<(String, Option<Level>)>,
+    json: bool,
+    file: Option<PathBuf>,
+    max_size: u64,
+    keep: u32,
+}
+
+impl Settings {
+    fn threshold(&self, module: &str) -> Option<Level> {
+        self.modules
+            .iter()
+            .filter(|(name, _)| {
+                module == name
+                    || module
+                        .strip_prefix(name.as_str())
+                        .is_some_and(|rest| rest.starts_with('.'))
+            })
+            .max_by_key(|(name, _)| name.len())
+            .map_or(self.level, |(_, level)| *level)
+    }
+}
+
+static SETTINGS: RwLock<Option<Settings>> = RwLock::new(None);
+// Serializes writes so lines from listener threads never interleave and only
+// one thread rotates the file.
+static OUTPUT: Mutex<()> = Mutex::new(());
+
+/// Apply the `[log]` settings. `default_level` is used when none is
+/// configured; `DING_DEBUG=1` raises it to debug and `DING_DEBUG_FILE` sends
+/// output to that file.
+pub fn configure(config: Option<&LogConfig>, default_level: Level) {
+    let configured = config.is_some();
+    let config = config.cloned().unwrap_or_default();
+    let level = if crate::debug_enabled() {
+        Some(Level::Debug)
+    } else {
+        config
+            .level
+            .as_deref()
+            .and_then(parse_level)
+            .unwrap_or(Some(default_level))
+    };
+    let modules = config
+        .modules
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|spec| {
+            let (name, level) = spec.split_once('=')?;
+            Some((name.trim().to_string(), parse_level(level)?))
+        })
+        .collect();
+    let file = std::env::var("DING_DEBUG_FILE")
+        .ok()
+        .filter(|path| !path.trim().is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            config
+                .file
+                .unwrap_or(false)
+                .then(|| crate::logs_dir().map(|dir| dir.join("ding.log")))
+                .flatten()
+        });
+    let settings = Settings {
+        configured,
+        level,
+        modules,
+        json: config.format.as_deref() == Some("json"),
+        file,
+        max_size: config.max_size.unwrap_or(DEFAULT_MAX_SIZE),
+        keep: config.keep.unwrap_or(DEFAULT_KEEP),
+    };
+    match SETTINGS.write() {
+        Ok(mut current) => *current = Some(settings),
+        Err(poisoned) => *poisoned.into_inner() = Some(settings),
+    }
+}
+
+/// Whether the config has a `[log]` section, for records that are only worth
+/// writing at `info` once logging has been set up deliberately.
+pub fn configured() -> bool {
+    SETTINGS
+        .read()
+        .is_ok_and(|settings| settings.as_ref().is_some_and(|s| s.configured))
+}
+
+/// Whether a record at `level` from `module` would be written, for skipping
+/// work that only feeds a log line.
+pub fn enabled(level: Level, module: &str) -> bool {
+    let Ok(settings) = SETTINGS.read() else {
+        return false;
+    };
+    match settings.as_ref() {
+        Some(settings) => settings.threshold(module).is_some_and(|max| level <= max),
+        None => level <= Level::Warn,
+    }
+}
+
+pub fn error(module: &str, message: &str) {
+    log(Level::Error, module, message, &[]);
+}
+
+pub fn warn(module: &str, message: &str) {
+    log(Level::Warn, module, message, &[]);
+}
+
+pub fn info(module: &str, message: &str) {
+    log(Level::Info, module, message, &[]);
+}
+
+pub fn debug(module: &str, message: &str) {
+    log(Level::Debug, module, message, &[]);
+}
+
+/// Write one record with structured `fields`. Known secrets are redacted.
+pub fn log(level: Level, module: &str, message: &str, fields: &[(&str, Value)]) {
+    if !enabled(level, module) {
+        return;
+    }
+    let Ok(settings) = SETTINGS.read() else {
+        return;
+    };
+    let (json, file, max_size, keep) = match settings.as_ref() {
+        Some(s) => (s.json, s.file.as_deref(), s.max_size, s.keep),
+        None => (false, None, DEFAULT_MAX_SIZE, DEFAULT_KEEP),
+    };
+    let ts = timestamp(SystemTime::now());
+    let line = if json {
+        let mut record = Map::new();
+        record.insert("ts".to_string(), Value::String(ts));
+        record.insert("level".to_string(), Value::from(level.as_str()));
+        record.insert("module".to_string(), Value::from(module));
+        record.insert("message".to_string(), Value::from(message));
+        for (key, value) in fields {
+            record.insert(key.to_string(), value.clone());
+        }
+        Value::Object(record).to_string()
+    } else {
+        let mut line = format!(
+            "{ts} {:<5} {module}: {message}",
+            level.as_str().to_ascii_uppercase()
+        );
+        for (key, value) in fields {
+            line.push_str(&format!(" {key}={}", text_value(value)));
+        }
+        line
+    };
+    let line = secret::redact(&line);
+
+    let _guard = OUTPUT.lock();
+    if let Some(path) = file
+        && append(path, &line, max_size, keep).is_ok()
+    {
+        return;
+    }
+    eprintln!("{line}");
+}
+
+fn text_value(value: &Value) -> String {
+    match value {
+        Value::String(text) if !text.is_empty() && !text.contains([' ', '"', '=']) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn append(path: &Path, line: &str, max_size: u64, keep: u32) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if max_size > 0 && fs::metadata(path).is_ok_and(|meta| meta.len() >= max_size) {
+        rotate(path, keep)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{line}")
+}
+
+// ding.log -> ding.log.1 -> ... -> ding.log.<keep>, dropping the oldest.
+fn rotate(path: &Path, keep: u32) -> std::io::Result<()> {
+    if keep == 0 {
+        return fs::remove_file(path);
+    }
+    let numbered = |n: u32| {
+        let mut name = path.as_os_str().to_owned();
+        name.push(format!(".{n}"));
+        PathBuf::from(name)
+    };
+    let _ = fs::remove_file(numbered(keep));
+    for n in (1..keep).rev() {
+        let _ = fs::rename(numbered(n), numbered(n + 1));
+    }
+    fs::rename(path, numbered(1))
+}
+
+// RFC 3339 in UTC with milliseconds, e.g. 2024-05-01T12:00:00.123Z.
+fn timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60,
+        since_epoch.subsec_millis()
+    )
+}
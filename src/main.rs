@@ -1,70 +1,99 @@
 mod cli;
 mod config;
 mod context;
+mod discovery;
 mod error;
+mod hooks;
+mod install;
+mod listener;
+mod logging;
 mod notification;
 mod payload;
 mod provider;
 mod remote;
+mod schema;
+mod secret;
+mod service;
+mod shell;
+mod template;
+mod transport;
+mod validate;
 
 use crate::cli::{
-    Cli, Commands, ConfigCmd, ConfigSetArgs, FocusArgs, ForwardCmd, ForwardOnArgs, ForwardTarget,
-    HookArgs, InstallArgs, ListenArgs, ProvidersCmd, RemoteCmd, RemotePingArgs, SendArgs,
-    SourcesCmd, TelegramChatIdArgs, TelegramCmd, UrgencyArg,
+    Cli, Commands, CompletionsArgs, ConfigCmd, ConfigKeyArgs, ConfigSetArgs, FlushRemoteQueueArgs,
+    FocusArgs, ForwardCmd, ForwardOnArgs, ForwardTarget, HookArgs, InstallArgs, InstallTarget,
+    JsonArgs, ListenArgs, ListenCmd, ListenServiceArgs, ProvidersCmd, RemoteCmd,
+    RemoteDiscoverArgs, RemotePingArgs, RemoteSshConfigArgs, SendArgs, ShellInitArgs,
+    ShellNotifyArgs, SourcesCmd, TailArgs, TelegramChatIdArgs, TelegramCmd, UninstallArgs,
+    UrgencyArg,
 };
-use crate::config::{Config, MacosConfig, SourceConfig, TelegramConfig};
-use crate::context::{detect_context, Context};
+use crate::config::{
+    Config, ConfigOrigin, MacosConfig, RemoteConfig, ResolvedConfig, SourceConfig, TelegramConfig,
+};
+use crate::context::{Context, detect_context};
 use crate::error::NotifallError;
-use crate::notification::{Notification, Urgency};
+use crate::listener::{DeliveryState, ListenerState, Metrics, WaitSlot};
+use crate::logging::Level;
+use crate::notification::{Action, Notification, Urgency};
 use crate::payload::WaitPayload;
 use crate::provider::{
-    macos::MacosProvider, telegram::TelegramProvider, DeliveryOutcome, Provider, ProviderError,
-    SendOptions,
+    DeliveryOutcome, Provider, ProviderError, SendOptions, macos::MacosProvider,
+    telegram::TelegramProvider,
 };
-use crate::remote::{RemoteContext, RemoteEnvelope};
+use crate::remote::{QueuedEnvelope, RemoteContext, RemoteEnvelope};
 use clap::Parser;
 use std::fs;
 use std::fs::OpenOptions;
+use std::net::IpAddr;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
-use std::time::{SystemTime, UNIX_EPOCH};
 use std::str::FromStr;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// How long a remote `send --wait-for-click` waits for the listener to report
+// back; slightly longer than the macOS helper's own 5 minute click timeout.
+const REMOTE_CLICK_TIMEOUT_SECS: u64 = 310;
+// Long-poll window for a single `/status/<id>` request.
+const STATUS_POLL_SECS: u64 = 25;
+const STATUS_MAX_WAIT_SECS: u64 = 60;
+// Idle `/events` streams get a comment frame this often to keep proxies happy.
+const EVENTS_KEEPALIVE_SECS: u64 = 15;
 
 fn main() {
-    if let Err(err) = run() {
-        eprintln!("{err}");
-        std::process::exit(1);
+    let cli = Cli::parse();
+    let json = cli.command.json_output();
+    if let Err(err) = run(cli) {
+        let message = secret::redact(&err.to_string());
+        // Partial forwards and dismissals already printed their own result.
+        let reported = matches!(
+            err,
+            NotifallError::ForwardPartial { .. } | NotifallError::Dismissed
+        );
+        if json && !reported {
+            let output = serde_json::json!({
+                "ok": false,
+                "error": {
+                    "kind": err.class(),
+                    "exit_code": err.exit_code(),
+                    "message": message,
+                },
+            });
+            println!("{output}");
+        }
+        eprintln!("{message}");
+        std::process::exit(err.exit_code());
     }
 }
 
-fn debug_enabled() -> bool {
+pub(crate) fn debug_enabled() -> bool {
     std::env::var("DING_DEBUG")
         .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
         .unwrap_or(false)
 }
 
-fn debug_log(message: &str) {
-    if !debug_enabled() {
-        return;
-    }
-    if let Ok(path) = std::env::var("DING_DEBUG_FILE") {
-        if !path.trim().is_empty() {
-            if let Ok(mut file) = std::fs::OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(&path)
-            {
-                use std::io::Write;
-                let _ = writeln!(file, "[ding debug] {message}");
-                return;
-            }
-        }
-    }
-    eprintln!("[ding debug] {message}");
-}
-
-fn logs_dir() -> Option<PathBuf> {
+pub(crate) fn logs_dir() -> Option<PathBuf> {
     if let Ok(dir) = std::env::var("XDG_CONFIG_HOME") {
         return Some(PathBuf::from(dir).join("ding/logs"));
     }
@@ -74,16 +103,12 @@ fn logs_dir() -> Option<PathBuf> {
     None
 }
 
-fn log_hook_payload(
-    source: &str,
-    payload: &serde_json::Value,
-    project: Option<&str>,
-) {
+fn log_hook_payload(source: &str, payload: &serde_json::Value, project: Option<&str>) {
     let Some(dir) = logs_dir() else {
         return;
     };
     if fs::create_dir_all(&dir).is_err() {
-        debug_log("failed to create logs directory");
+        logging::warn("hooks", "failed to create logs directory");
         return;
     }
     let path = dir.join("hooks.jsonl");
@@ -102,7 +127,7 @@ fn log_hook_payload(
             use std::io::Write;
             let _ = writeln!(file, "{}", entry);
         }
-        Err(_) => debug_log("failed to open hook log file"),
+        Err(_) => logging::warn("hooks", "failed to open hook log file"),
     }
 }
 
@@ -170,99 +195,101 @@ fn shell_escape(value: &str) -> String {
     out
 }
 
-fn humanize_label(raw: &str) -> String {
-    if raw.trim().is_empty() {
-        return "Notification".to_string();
-    }
-    let mut out = String::with_capacity(raw.len() + 8);
-    let mut prev: Option<char> = None;
-    for ch in raw.chars() {
-        if ch == '_' || ch == '-' {
-            if !out.ends_with(' ') {
-                out.push(' ');
-            }
-            prev = Some(' ');
-            continue;
-        }
-        if let Some(p) = prev {
-            if p.is_ascii_lowercase() && ch.is_ascii_uppercase() && !out.ends_with(' ') {
-                out.push(' ');
-            }
-        }
-        out.push(ch);
-        prev = Some(ch);
-    }
-    let words: Vec<String> = out
-        .split_whitespace()
-        .map(|w| {
-            let mut chars = w.chars();
-            match chars.next() {
-                Some(first) => first.to_uppercase().collect::<String>() + &chars.as_str().to_lowercase(),
-                None => String::new(),
-            }
-        })
-        .filter(|w| !w.is_empty())
-        .collect();
-    if words.is_empty() {
-        "Notification".to_string()
-    } else {
-        words.join(" ")
-    }
-}
-
-fn run() -> Result<(), NotifallError> {
-    let cli = Cli::parse();
-
+fn run(cli: Cli) -> Result<(), NotifallError> {
     let config_path = cli.config.clone();
+    if let Some(profile) = cli.profile {
+        let _ = PROFILE.set(profile);
+    }
+    // The listener reports reloads and the like at info.
+    let default_level = match &cli.command {
+        Commands::Listen(ListenArgs { command: None, .. }) => Level::Info,
+        _ => Level::Warn,
+    };
+    let log_config = resolve_config(config_path.as_ref())
+        .ok()
+        .flatten()
+        .and_then(|resolved| resolved.config.log);
+    logging::configure(log_config.as_ref(), default_level);
 
     match cli.command {
-        Commands::Send(args) => handle_send(config_path.as_ref(), args),
-        Commands::Config { command: ConfigCmd::Init(args) } => {
-            handle_config_init(config_path.as_ref(), args)
-        }
-        Commands::Config { command: ConfigCmd::Set(args) } => {
-            handle_config_set(config_path.as_ref(), args)
+        Commands::Send(args) => {
+            let resolved_path = config_path.unwrap_or_else(default_config_path);
+            logging::debug("send", &format!("config_path={}", resolved_path.display()));
+            let config = load_config(Some(&resolved_path))?;
+            handle_send(config.as_ref(), args)
         }
-        Commands::Config { command: ConfigCmd::Path } => {
-            handle_config_path(config_path.as_ref())
-        }
-        Commands::Config { command: ConfigCmd::List } => {
-            handle_config_list(config_path.as_ref())
+        Commands::Config {
+            command: ConfigCmd::Init(args),
+        } => handle_config_init(config_path.as_ref(), args),
+        Commands::Config {
+            command: ConfigCmd::Set(args),
+        } => handle_config_set(config_path.as_ref(), args),
+        Commands::Config {
+            command: ConfigCmd::Get(args),
+        } => handle_config_get(config_path.as_ref(), args),
+        Commands::Config {
+            command: ConfigCmd::Unset(args),
+        } => handle_config_unset(config_path.as_ref(), args),
+        Commands::Config {
+            command: ConfigCmd::Path,
+        } => handle_config_path(config_path.as_ref()),
+        Commands::Config {
+            command: ConfigCmd::List,
+        } => handle_config_list(config_path.as_ref()),
+        Commands::Config {
+            command: ConfigCmd::Resolve,
+        } => handle_config_resolve(config_path.as_ref()),
+        Commands::Config {
+            command: ConfigCmd::Validate,
+        } => handle_config_validate(config_path.as_ref()),
+        Commands::Config {
+            command: ConfigCmd::Schema,
+        } => {
+            println!("{}", serde_json::to_string_pretty(&schema::json_schema())?);
+            Ok(())
         }
         Commands::Providers {
-            command: ProvidersCmd::List,
-        } => handle_providers_list(),
+            command: ProvidersCmd::List(args),
+        } => handle_providers_list(args),
         Commands::Sources {
-            command: SourcesCmd::List,
-        } => handle_sources_list(config_path.as_ref()),
-        Commands::Install(args) => handle_install(args),
-        Commands::Hook(args) => handle_hook(args),
+            command: SourcesCmd::List(args),
+        } => handle_sources_list(config_path.as_ref(), args),
+        Commands::Install(args) => handle_install(args, config_path.as_ref()),
+        Commands::Uninstall(args) => handle_uninstall(args),
+        Commands::Hook(args) => handle_hook(args, config_path.as_ref()),
+        Commands::ShellInit(args) => handle_shell_init(args, config_path.as_ref()),
+        Commands::ShellNotify(args) => handle_shell_notify(args, config_path.as_ref()),
         Commands::Focus(args) => handle_focus(args),
         Commands::WaitMacos(args) => handle_wait_macos(args),
+        Commands::FlushRemoteQueue(args) => handle_flush_remote_queue(args, config_path.as_ref()),
+        Commands::Listen(ListenArgs {
+            command: Some(command),
+            ..
+        }) => handle_listen_service(command, config_path.as_ref()),
         Commands::Listen(args) => handle_listen(config_path.as_ref(), args),
+        Commands::Tail(args) => handle_tail(args, config_path.as_ref()),
         Commands::Remote { command } => handle_remote(command, config_path.as_ref()),
         Commands::Forward { command } => handle_forward(command, config_path.as_ref()),
         Commands::Telegram { command } => handle_telegram(command, config_path.as_ref()),
+        Commands::Doctor => handle_doctor(config_path.as_ref()),
+        Commands::Completions(args) => handle_completions(args),
     }
 }
 
-fn handle_send(config_path: Option<&PathBuf>, args: SendArgs) -> Result<(), NotifallError> {
-    let resolved_path = config_path
-        .cloned()
-        .unwrap_or_else(default_config_path);
-    debug_log(&format!("config_path={}", resolved_path.display()));
-    let config = load_config(Some(&resolved_path))?;
-    let provider_name = resolve_provider(args.provider.as_deref(), config.as_ref())?;
-    debug_log(&format!("provider={}", provider_name));
+// Takes the config already loaded by the caller, so secrets are never resolved
+// twice for one notification.
+fn handle_send(config: Option<&Config>, args: SendArgs) -> Result<(), NotifallError> {
+    let provider_name = resolve_provider(args.provider.as_deref(), config)?;
+    logging::debug("send", &format!("provider={}", provider_name));
     let source = args.source.as_ref().map(|s| s.to_lowercase());
     if let Some(source) = source.as_deref() {
-        debug_log(&format!("source={source}"));
+        logging::debug("send", &format!("source={source}"));
     }
-    let source_config = resolve_source_config(config.as_ref(), source.as_deref());
+    let source_config = resolve_source_config(config, source.as_deref());
     let context = detect_context();
 
-    if args.background && args.on_click.is_none() && provider_name == "macos" {
-        return Err(NotifallError::BackgroundRequiresOnClick);
+    if let Some(input) = args.json_input.as_deref() {
+        return send_json_input(config, &provider_name, &args, input, context);
     }
 
     let title = resolve_title(args.title.clone(), source_config, source.as_deref());
@@ -279,9 +306,9 @@ fn handle_send(config_path: Option<&PathBuf>, args: SendArgs) -> Result<(), Noti
     } else {
         args.sound.clone()
     };
-    let notification = Notification {
+    let mut notification = Notification {
         title,
-        message: args.message.clone(),
+        message: args.message.clone().unwrap_or_default(),
         source: source.clone(),
         icon,
         link: args.link.clone(),
@@ -290,15 +317,184 @@ fn handle_send(config_path: Option<&PathBuf>, args: SendArgs) -> Result<(), Noti
         tag: args.tag.clone(),
         sender: None,
         dedupe_key: None,
-        metadata: None,
-        actions: Vec::new(),
+        metadata: (!args.meta.is_empty()).then(|| args.meta.iter().cloned().collect()),
+        actions: args.actions.clone(),
+    };
+    check_background(&args, &notification, &provider_name)?;
+    notification
+        .check_actions()
+        .map_err(NotifallError::InvalidAction)?;
+    let origin = RemoteContext::from_local(context.clone());
+    apply_send_templates(&mut notification, &args, source_config, &origin)?;
+    dispatch_notification(config, &provider_name, &args, notification, origin, context)
+}
+
+// `--background` leaves nothing to wait for unless a click or button runs a
+// command, so it needs `--on-click` or an action.
+fn check_background(
+    args: &SendArgs,
+    notification: &Notification,
+    provider_name: &str,
+) -> Result<(), NotifallError> {
+    if args.background
+        && args.on_click.is_none()
+        && notification.actions.is_empty()
+        && provider_name == "macos"
+    {
+        return Err(NotifallError::BackgroundRequiresOnClick);
+    }
+    Ok(())
+}
+
+// `--title-template`/`--message-template`, falling back to the source's templates.
+fn apply_send_templates(
+    notification: &mut Notification,
+    args: &SendArgs,
+    source_config: Option<&SourceConfig>,
+    origin: &RemoteContext,
+) -> Result<(), NotifallError> {
+    let title_template = args
+        .title_template
+        .as_deref()
+        .or_else(|| source_config.and_then(|cfg| cfg.title_template.as_deref()));
+    let message_template = args
+        .message_template
+        .as_deref()
+        .or_else(|| source_config.and_then(|cfg| cfg.message_template.as_deref()));
+    template::apply(notification, title_template, message_template, Some(origin))
+        .map_err(NotifallError::Template)
+}
+
+/// `ding send --json-input`: send every JSON document in `input` ("-" for
+/// stdin), either a `Notification` or a `RemoteEnvelope` whose context and
+/// click settings are kept.
+fn send_json_input(
+    config: Option<&Config>,
+    provider_name: &str,
+    args: &SendArgs,
+    input: &std::path::Path,
+    context: Option<Context>,
+) -> Result<(), NotifallError> {
+    let raw = if input == std::path::Path::new("-") {
+        let mut buf = String::new();
+        std::io::Read::read_to_string(&mut std::io::stdin(), &mut buf)?;
+        buf
+    } else {
+        fs::read_to_string(input)?
+    };
+    let mut total = 0usize;
+    let mut failed = 0usize;
+    let mut last_err = None;
+    // A stream of values covers a single (pretty-printed) document and NDJSON alike.
+    for document in serde_json::Deserializer::from_str(&raw).into_iter::<serde_json::Value>() {
+        let document = document?;
+        total += 1;
+        if let Err(err) = send_json_document(config, provider_name, args, document, context.clone())
+        {
+            failed += 1;
+            eprintln!("notification {total}: {}", secret::redact(&err.to_string()));
+            last_err = Some(err);
+        }
+    }
+    // A single document keeps its own error, and with it its exit code.
+    if total == 1
+        && let Some(err) = last_err
+    {
+        return Err(err);
+    }
+    if failed > 0 {
+        return Err(NotifallError::Provider(ProviderError::Message(format!(
+            "{failed} of {total} notifications failed"
+        ))));
+    }
+    Ok(())
+}
+
+fn send_json_document(
+    config: Option<&Config>,
+    provider_name: &str,
+    args: &SendArgs,
+    document: serde_json::Value,
+    context: Option<Context>,
+) -> Result<(), NotifallError> {
+    let mut args = args.clone();
+    let (mut notification, origin) = if document.get("notification").is_some() {
+        let envelope: RemoteEnvelope = serde_json::from_value(document)?;
+        args.wait_for_click |= envelope.wait_for_click;
+        if envelope.callback_url.is_some() {
+            args.remote_callback = envelope.callback_url;
+        }
+        let origin = envelope
+            .context
+            .unwrap_or_else(|| RemoteContext::from_local(context.clone()));
+        (envelope.notification, origin)
+    } else {
+        let notification: Notification = serde_json::from_value(document)?;
+        (notification, RemoteContext::from_local(context.clone()))
     };
+
+    // Flags given on the command line fill in what the document leaves out.
+    notification.source = notification
+        .source
+        .or_else(|| args.source.clone())
+        .map(|source| source.to_lowercase());
+    let source = notification.source.clone();
+    let source_config = resolve_source_config(config, source.as_deref());
+    if notification.title.trim().is_empty() {
+        notification.title = resolve_title(args.title.clone(), source_config, source.as_deref());
+    }
+    if args.no_icon || !allow_image_icons() {
+        notification.icon = None;
+    } else if notification.icon.is_none() {
+        notification.icon = resolve_icon(args.icon.clone(), source_config, source.as_deref());
+    }
+    if args.silent {
+        notification.sound = Some("none".to_string());
+    }
+    notification.sound = notification.sound.or_else(|| args.sound.clone());
+    notification.link = notification.link.or_else(|| args.link.clone());
+    notification.tag = notification.tag.or_else(|| args.tag.clone());
+    notification.urgency = notification.urgency.or(args.urgency.map(map_urgency));
+    if !args.meta.is_empty() {
+        let metadata = notification.metadata.get_or_insert_default();
+        for (key, value) in &args.meta {
+            metadata.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+    }
+    if notification.actions.is_empty() {
+        notification.actions = args.actions.clone();
+    }
+
+    check_background(&args, &notification, provider_name)?;
+    notification
+        .check_actions()
+        .map_err(NotifallError::InvalidAction)?;
+    apply_send_templates(&mut notification, &args, source_config, &origin)?;
+    // An envelope's context describes the sender's terminal, which a click
+    // here can't focus; local delivery uses this host's.
+    dispatch_notification(config, provider_name, &args, notification, origin, context)
+}
+
+// Deliver a fully built notification through `provider_name`. `origin`
+// describes where it came from (this host unless it was read from an
+// envelope) and is passed on when forwarding; `local` is what a click on a
+// notification shown here focuses.
+fn dispatch_notification(
+    config: Option<&Config>,
+    provider_name: &str,
+    args: &SendArgs,
+    notification: Notification,
+    origin: RemoteContext,
+    local: Option<Context>,
+) -> Result<(), NotifallError> {
+    let source = notification.source.clone();
+    let source_config = resolve_source_config(config, source.as_deref());
     let mut remote_notification = notification.clone();
     remote_notification.icon = None;
 
-    match provider_name.as_str() {
+    match provider_name {
         "macos" => {
-            let macos_config = resolve_macos_config(config.as_ref(), source_config, source.as_deref());
+            let macos_config = resolve_macos_config(config, source_config, source.as_deref());
             deliver_macos(
                 notification,
                 macos_config,
@@ -306,49 +502,49 @@ fn handle_send(config_path: Option<&PathBuf>, args: SendArgs) -> Result<(), Noti
                 args.background,
                 args.wait_for_click,
                 args.json,
-                context,
+                local,
             )?;
         }
         "forward" => {
             handle_forward_send(
-                config.as_ref(),
-                &args,
+                config,
+                args,
                 notification,
                 remote_notification,
-                context,
-                source_config,
-                source.as_deref(),
+                origin,
+                local,
             )?;
         }
         "telegram" => {
-            let telegram_config = resolve_telegram_config(config.as_ref(), &args)?;
-            debug_log(&format!(
-                "telegram chat_id={} parse_mode={} silent={}",
-                telegram_config
-                    .chat_id
-                    .as_deref()
-                    .unwrap_or("(unset)"),
-                telegram_config
-                    .parse_mode
-                    .as_deref()
-                    .unwrap_or("(unset)"),
-                telegram_config.silent.unwrap_or(false)
-            ));
+            let telegram_config = resolve_telegram_config(config, args)?;
+            logging::debug(
+                "telegram",
+                &format!(
+                    "telegram chat_id={} parse_mode={} silent={}",
+                    telegram_config.chat_id.as_deref().unwrap_or("(unset)"),
+                    telegram_config.parse_mode.as_deref().unwrap_or("(unset)"),
+                    telegram_config.silent.unwrap_or(false)
+                ),
+            );
             let provider = TelegramProvider::new(telegram_config)?;
-            let report = provider.send(&notification, SendOptions { wait_for_click: false })?;
+            let report = provider.send(
+                &notification,
+                SendOptions {
+                    wait_for_click: false,
+                },
+            )?;
             if args.json {
-                print_send_output("telegram", report.outcome, false, None)?;
+                SendOutput::new("telegram", report.outcome.as_ref()).print()?;
             }
         }
         "remote" => {
             handle_remote_send(
-                config.as_ref(),
-                &args,
+                config,
+                args,
                 notification,
                 remote_notification,
-                context,
-                source_config,
-                source.as_deref(),
+                origin,
+                local,
             )?;
         }
         other => return Err(NotifallError::ProviderUnsupported(other.to_string())),
@@ -358,7 +554,7 @@ fn handle_send(config_path: Option<&PathBuf>, args: SendArgs) -> Result<(), Noti
 }
 
 fn deliver_macos(
-    notification: Notification,
+    mut notification: Notification,
     macos_config: Option<MacosConfig>,
     on_click: Option<String>,
     background: bool,
@@ -366,6 +562,15 @@ fn deliver_macos(
     json: bool,
     context: Option<Context>,
 ) -> Result<(), NotifallError> {
+    if let Some(macos) = macos_config.as_ref() {
+        template::apply(
+            &mut notification,
+            macos.title_template.as_deref(),
+            macos.message_template.as_deref(),
+            Some(&RemoteContext::from_local(context.clone())),
+        )
+        .map_err(NotifallError::Template)?;
+    }
     if background {
         let payload = WaitPayload {
             notification,
@@ -375,17 +580,17 @@ fn deliver_macos(
         };
         let payload_path = spawn_background_wait(payload)?;
         if json {
-            print_send_output(
-                "macos",
-                None,
-                true,
-                Some(payload_path.to_string_lossy().to_string()),
-            )?;
+            SendOutput {
+                background: true,
+                payload: Some(payload_path.to_string_lossy().to_string()),
+                ..SendOutput::new("macos", None)
+            }
+            .print()?;
         }
         return Ok(());
     }
 
-    let wait_for_click = wait_for_click || on_click.is_some();
+    let wait_for_click = wait_for_click || on_click.is_some() || !notification.actions.is_empty();
     let provider = MacosProvider::new(macos_config)?;
     let report = provider.send(&notification, SendOptions { wait_for_click })?;
     if wait_for_click {
@@ -394,12 +599,13 @@ fn deliver_macos(
             on_click.as_deref(),
             &notification,
             context.as_ref(),
+            ClickSide::Local,
         )?;
     }
     if json {
-        print_send_output("macos", report.outcome, false, None)?;
+        SendOutput::new("macos", report.outcome.as_ref()).print()?;
     }
-    Ok(())
+    ensure_not_dismissed(report.outcome.as_ref(), wait_for_click)
 }
 
 fn handle_config_init(
@@ -424,7 +630,21 @@ fn handle_config_init(
     Ok(())
 }
 
-fn handle_providers_list() -> Result<(), NotifallError> {
+fn handle_providers_list(args: JsonArgs) -> Result<(), NotifallError> {
+    if args.json {
+        let providers = schema::PROVIDERS
+            .iter()
+            .map(|name| {
+                let available = *name != "macos" || cfg!(target_os = "macos");
+                serde_json::json!({ "name": name, "available": available })
+            })
+            .collect::<Vec<_>>();
+        println!(
+            "{}",
+            serde_json::json!({ "ok": true, "providers": providers })
+        );
+        return Ok(());
+    }
     println!("forward");
     println!("remote");
     println!("telegram");
@@ -436,23 +656,195 @@ fn handle_providers_list() -> Result<(), NotifallError> {
     Ok(())
 }
 
-fn handle_install(args: InstallArgs) -> Result<(), NotifallError> {
-    match args.target {
-        crate::cli::InstallTarget::Claude => install_claude(args.apply),
-        crate::cli::InstallTarget::Codex => install_codex(args.apply),
+fn handle_install(args: InstallArgs, config_path: Option<&PathBuf>) -> Result<(), NotifallError> {
+    let config = load_config(config_path)?;
+    let Some(target) = args.target else {
+        return handle_install_status(config.as_ref());
+    };
+    match target {
+        InstallTarget::Claude => install_claude(args.apply, config.as_ref()),
+        InstallTarget::Codex => install_codex(args.apply, args.force),
+        InstallTarget::Gemini => install_gemini(args.apply, config.as_ref()),
+        InstallTarget::Opencode => install_opencode(args.apply),
+        InstallTarget::Git => install_git(args.apply, args.global),
     }
 }
 
-fn handle_hook(args: HookArgs) -> Result<(), NotifallError> {
-    let payload = read_hook_payload(args.json.as_deref())?;
+fn handle_uninstall(args: UninstallArgs) -> Result<(), NotifallError> {
     match args.target {
-        crate::cli::InstallTarget::Claude => handle_claude_hook(payload),
-        crate::cli::InstallTarget::Codex => handle_codex_hook(payload),
+        InstallTarget::Claude => {
+            uninstall_json_hooks(&claude_settings_path()?, "claude", args.apply)
+        }
+        InstallTarget::Codex => uninstall_codex(args.apply),
+        InstallTarget::Gemini => {
+            uninstall_json_hooks(&gemini_settings_path()?, "gemini", args.apply)
+        }
+        InstallTarget::Opencode => uninstall_opencode(args.apply),
+        InstallTarget::Git => uninstall_git(args.apply, args.global),
+    }
+}
+
+fn handle_hook(args: HookArgs, config_path: Option<&PathBuf>) -> Result<(), NotifallError> {
+    let mut payload = read_hook_payload(args.json.as_deref())?;
+    // Hooks installed by `ding install git` pass their state through DING_GIT_*.
+    if args.name == "git" && payload.is_null() {
+        let Some(git) = hooks::git_payload() else {
+            return Ok(());
+        };
+        payload = git;
+    }
+    let config = load_config(config_path)?;
+    let adapter = hooks::adapter(&args.name, config.as_ref())
+        .ok_or_else(|| NotifallError::UnknownHook(args.name.clone()))?;
+    let source = adapter.source.clone().unwrap_or_else(|| args.name.clone());
+    let source_config = resolve_source_config(config.as_ref(), Some(&source));
+    let Some(hook) = hooks::render(&args.name, &adapter, payload.clone(), source_config) else {
+        return Ok(());
+    };
+
+    let project = hook
+        .project
+        .as_deref()
+        .and_then(project_name_from_path)
+        .or_else(|| project_name_from_payload(&payload));
+    log_hook_payload(&hook.source, &payload, project.as_deref());
+    let title = match project.as_deref() {
+        Some(project) => format!("[{project}] {}", hook.title),
+        None => hook.title,
+    };
+    let message = if hook.message.is_empty() {
+        " ".to_string()
+    } else {
+        hook.message
+    };
+    let (title, message) = truncate_message(title, message);
+
+    let exe = std::env::current_exe()?;
+    let on_click = match project.as_deref() {
+        Some(project) => format!(
+            "{} focus --project {}",
+            exe.display(),
+            shell_escape(project)
+        ),
+        None => format!("{} focus", exe.display()),
+    };
+    let args = SendArgs {
+        title: Some(title),
+        message: Some(message),
+        sound: hook.sound,
+        urgency: hook.urgency.map(urgency_arg),
+        source: Some(hook.source),
+        on_click: Some(on_click),
+        background: true,
+        ..Default::default()
+    };
+    handle_send(config.as_ref(), args)
+}
+
+fn handle_shell_init(
+    args: ShellInitArgs,
+    config_path: Option<&PathBuf>,
+) -> Result<(), NotifallError> {
+    let config = load_config(config_path)?;
+    let threshold = args
+        .threshold
+        .or_else(|| {
+            config
+                .as_ref()
+                .and_then(|c| c.shell.as_ref())
+                .and_then(|s| s.threshold)
+        })
+        .unwrap_or(shell::DEFAULT_THRESHOLD_SECS);
+    let exe = shell_escape(&std::env::current_exe()?.display().to_string());
+    print!("{}", shell::init_script(args.shell, &exe, threshold));
+    Ok(())
+}
+
+fn handle_shell_notify(
+    args: ShellNotifyArgs,
+    config_path: Option<&PathBuf>,
+) -> Result<(), NotifallError> {
+    let config = load_config(config_path)?;
+    let shell_cfg = config
+        .as_ref()
+        .and_then(|c| c.shell.clone())
+        .unwrap_or_default();
+
+    let command = args.command.join(" ");
+    let command = command.trim();
+    // 130 is Ctrl-C: whoever interrupted the command is already looking at it.
+    if command.is_empty() || args.status == 130 {
+        return Ok(());
     }
+    let ignored = shell::command_name(command).is_some_and(|name| match &shell_cfg.ignore {
+        Some(ignore) => ignore.iter().any(|i| i == name),
+        None => shell::DEFAULT_IGNORE.contains(&name),
+    });
+    if ignored {
+        return Ok(());
+    }
+    if !shell_cfg.notify_when_focused.unwrap_or(false) && shell::terminal_focused() == Some(true) {
+        return Ok(());
+    }
+
+    let project = std::env::current_dir()
+        .ok()
+        .and_then(|dir| project_name_from_path(&dir.display().to_string()));
+    let outcome = if args.status == 0 {
+        "Command finished".to_string()
+    } else {
+        format!("Command failed (exit {})", args.status)
+    };
+    let title = match project.as_deref() {
+        Some(project) => format!("[{project}] {outcome}"),
+        None => outcome,
+    };
+    let message = format!("{command} ({})", hooks::format_duration(args.duration));
+    let (title, message) = truncate_message(title, message);
+
+    // The click handler gets this shell's tmux pane from detect_context().
+    let exe = std::env::current_exe()?;
+    let on_click = match project.as_deref() {
+        Some(project) => format!(
+            "{} focus --project {}",
+            exe.display(),
+            shell_escape(project)
+        ),
+        None => format!("{} focus", exe.display()),
+    };
+    let meta = vec![
+        ("command".to_string(), command.to_string()),
+        ("status".to_string(), args.status.to_string()),
+        ("duration_secs".to_string(), args.duration.to_string()),
+    ];
+    let args = SendArgs {
+        title: Some(title),
+        message: Some(message),
+        meta,
+        urgency: (args.status != 0).then_some(UrgencyArg::High),
+        source: Some("shell".to_string()),
+        on_click: Some(on_click),
+        background: true,
+        ..Default::default()
+    };
+    handle_send(config.as_ref(), args)
 }
 
-fn handle_sources_list(config_path: Option<&PathBuf>) -> Result<(), NotifallError> {
+fn handle_sources_list(config_path: Option<&PathBuf>, args: JsonArgs) -> Result<(), NotifallError> {
     let config = load_config(config_path)?;
+    if args.json {
+        let mut sources = Vec::new();
+        for (name, source) in config.and_then(|c| c.sources).unwrap_or_default() {
+            let mut entry = serde_json::Map::new();
+            entry.insert("name".to_string(), serde_json::Value::String(name));
+            if let serde_json::Value::Object(fields) = serde_json::to_value(source)? {
+                entry.extend(fields);
+            }
+            sources.push(serde_json::Value::Object(entry));
+        }
+        println!("{}", serde_json::json!({ "ok": true, "sources": sources }));
+        return Ok(());
+    }
     let sources = match config.and_then(|c| c.sources) {
         Some(sources) if !sources.is_empty() => sources,
         _ => {
@@ -467,10 +859,7 @@ fn handle_sources_list(config_path: Option<&PathBuf>) -> Result<(), NotifallErro
             .as_ref()
             .map(|p| p.display().to_string())
             .unwrap_or_else(|| "-".to_string());
-        let bundle = source
-            .app_bundle_id
-            .as_deref()
-            .unwrap_or("-");
+        let bundle = source.app_bundle_id.as_deref().unwrap_or("-");
         println!("{name}\t{icon}\t{bundle}");
     }
     Ok(())
@@ -482,9 +871,7 @@ fn handle_focus(args: FocusArgs) -> Result<(), NotifallError> {
         .clone()
         .or_else(|| std::env::var("DING_TERMINAL_APP").ok())
         .or_else(|| std::env::var("TERM_PROGRAM").ok());
-    let project = args
-        .project
-        .or_else(|| std::env::var("DING_PROJECT").ok());
+    let project = args.project.or_else(|| std::env::var("DING_PROJECT").ok());
     let ghostty_focus = is_ghostty_terminal(terminal.as_deref());
 
     if !args.no_activate {
@@ -540,1500 +927,4115 @@ fn handle_focus(args: FocusArgs) -> Result<(), NotifallError> {
     Ok(())
 }
 
-fn handle_listen(
-    config_path: Option<&PathBuf>,
-    args: ListenArgs,
-) -> Result<(), NotifallError> {
+struct ListenerSettings {
+    token: Option<String>,
+    require_token: bool,
+    prefix_hostname: bool,
+    allow_hosts: Vec<String>,
+    callback_hosts: Vec<String>,
+    cors_origin: Option<String>,
+    on_click: Option<String>,
+    collapse_batches: bool,
+    metrics_require_token: bool,
+    config: Option<Config>,
+}
+
+impl ListenerSettings {
+    // Command-line flags win over the config, so a reload never undoes them.
+    fn new(config: Option<Config>, args: &ListenArgs) -> Result<Self, NotifallError> {
+        let listener_cfg = config
+            .as_ref()
+            .and_then(|c| c.listener.clone())
+            .unwrap_or_default();
+        let token = match args.token.clone() {
+            Some(token) => Some(token),
+            None => listener_cfg.token()?,
+        };
+        let require_token = if args.require_token {
+            true
+        } else {
+            listener_cfg.require_token.unwrap_or(token.is_some())
+        };
+        let prefix_hostname = if args.prefix_hostname {
+            true
+        } else {
+            listener_cfg.prefix_hostname.unwrap_or(true)
+        };
+        let allow_hosts = if !args.allow_host.is_empty() {
+            args.allow_host.clone()
+        } else {
+            listener_cfg.allow_hosts.unwrap_or_default()
+        };
+        let on_click = if args.no_click {
+            None
+        } else {
+            args.on_click
+                .clone()
+                .or(listener_cfg.on_click)
+                .or_else(default_focus_command)
+        };
+        let callback_hosts = listener_cfg.callback_hosts.unwrap_or_default();
+        let cors_origin = listener_cfg.cors_origin;
+        let collapse_batches =
+            args.collapse_batches || listener_cfg.collapse_batches.unwrap_or(false);
+        let metrics_require_token = listener_cfg.metrics_require_token.unwrap_or(require_token);
+        Ok(ListenerSettings {
+            token,
+            require_token,
+            prefix_hostname,
+            allow_hosts,
+            callback_hosts,
+            cors_origin,
+            on_click,
+            collapse_batches,
+            metrics_require_token,
+            config,
+        })
+    }
+}
+
+// The settings requests are served with. Each request takes a snapshot, so a
+// reload swapping them in never affects one already in flight.
+struct LiveSettings(RwLock<Arc<ListenerSettings>>);
+
+impl LiveSettings {
+    fn current(&self) -> Arc<ListenerSettings> {
+        match self.0.read() {
+            Ok(settings) => Arc::clone(&settings),
+            Err(poisoned) => Arc::clone(&poisoned.into_inner()),
+        }
+    }
+
+    fn replace(&self, settings: ListenerSettings) {
+        match self.0.write() {
+            Ok(mut current) => *current = Arc::new(settings),
+            Err(poisoned) => *poisoned.into_inner() = Arc::new(settings),
+        }
+    }
+}
+
+fn handle_listen(config_path: Option<&PathBuf>, args: ListenArgs) -> Result<(), NotifallError> {
     let config = load_config(config_path)?;
-    let listener_cfg = config.as_ref().and_then(|c| c.listener.clone()).unwrap_or_default();
+    let listener_cfg = config
+        .as_ref()
+        .and_then(|c| c.listener.clone())
+        .unwrap_or_default();
 
     let bind = args
         .bind
+        .clone()
         .or(listener_cfg.bind)
         .unwrap_or_else(|| "0.0.0.0".to_string());
     let port = args.port.or(listener_cfg.port).unwrap_or(4280);
-    let token = args.token.or(listener_cfg.token);
-    let require_token = if args.require_token {
-        true
-    } else {
-        listener_cfg.require_token.unwrap_or(token.is_some())
-    };
-    let prefix_hostname = if args.prefix_hostname {
-        true
-    } else {
-        listener_cfg.prefix_hostname.unwrap_or(true)
-    };
-    let allow_hosts = if !args.allow_host.is_empty() {
-        args.allow_host
-    } else {
-        listener_cfg.allow_hosts.unwrap_or_default()
-    };
-    let on_click = if args.no_click {
-        None
-    } else {
-        args.on_click
-            .or(listener_cfg.on_click)
-            .or_else(default_focus_command)
-    };
+    let advertise =
+        !args.no_advertise && (args.advertise || listener_cfg.advertise.unwrap_or(false));
+    let advertised_name = listener_cfg
+        .name
+        .clone()
+        .or_else(crate::remote::local_hostname)
+        .unwrap_or_else(|| "ding".to_string());
+    let socket = args
+        .socket
+        .clone()
+        .or(listener_cfg.socket)
+        .map(|path| expand_home(&path));
+    let settings = Arc::new(LiveSettings(RwLock::new(Arc::new(ListenerSettings::new(
+        config, &args,
+    )?))));
+    let state = Arc::new(ListenerState::default());
+
+    if let Some(socket) = socket {
+        let server = bind_listener_socket(&socket)?;
+        println!("ding listener on unix:{}", socket.display());
+        let state = Arc::clone(&state);
+        let settings = Arc::clone(&settings);
+        std::thread::spawn(move || serve_listener(server, &state, &settings));
+    }
 
     let addr = format!("{}:{}", bind, port);
     let server = tiny_http::Server::http(&addr)
         .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
     println!("ding listener on {addr}");
 
-    for mut request in server.incoming_requests() {
-        let path = request.url().split('?').next().unwrap_or("");
-        if debug_enabled() {
-            let remote = request
-                .remote_addr()
-                .map(|addr| addr.ip().to_string())
-                .unwrap_or_else(|| "unknown".to_string());
-            debug_log(&format!(
-                "listener request path={path} remote={remote}"
-            ));
-        }
-        if path == "/ping" {
-            let response = json_response(200, r#"{"status":"ok"}"#);
-            let _ = request.respond(response);
-            continue;
+    // Held for the lifetime of the accept loop; dropping it stops advertising.
+    let require_token = settings.current().require_token;
+    let loopback = bind == "localhost"
+        || bind
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback());
+    let _advertisement = if advertise && loopback {
+        logging::warn(
+            "discovery",
+            &format!("not advertising over mDNS: {bind} is only reachable from this machine"),
+        );
+        None
+    } else if advertise {
+        match discovery::advertise(&advertised_name, port, require_token) {
+            Ok(daemon) => {
+                println!(
+                    "advertising {advertised_name} as {}",
+                    discovery::SERVICE_TYPE
+                );
+                Some(daemon)
+            }
+            Err(err) => {
+                logging::warn("discovery", &format!("mDNS advertisement failed: {err}"));
+                None
+            }
         }
+    } else {
+        None
+    };
 
-        if path != "/notify" {
-            let response = json_response(404, r#"{"error":"not found"}"#);
-            let _ = request.respond(response);
-            continue;
-        }
+    {
+        let config_path = config_path.cloned();
+        let settings = Arc::clone(&settings);
+        std::thread::spawn(move || watch_listener_config(config_path, args, &settings));
+    }
 
-        if request.method() != &tiny_http::Method::Post {
-            let response = json_response(405, r#"{"error":"method not allowed"}"#);
-            let _ = request.respond(response);
-            continue;
-        }
+    serve_listener(server, &state, &settings);
+    Ok(())
+}
 
-        if !allow_hosts.is_empty() {
-            if let Some(remote) = request.remote_addr() {
-                let host = remote.ip().to_string();
-                if !allow_hosts.iter().any(|allowed| allowed == &host) {
-                    let response = json_response(403, r#"{"error":"forbidden"}"#);
-                    let _ = request.respond(response);
-                    continue;
-                }
-            }
-        }
+static RELOAD_REQUESTED: AtomicBool = AtomicBool::new(false);
 
-        if require_token {
-            let incoming = extract_token(request.headers());
-            if token.as_deref() != incoming.as_deref() {
-                let response = json_response(401, r#"{"error":"unauthorized"}"#);
-                let _ = request.respond(response);
-                continue;
+#[cfg(unix)]
+extern "C" fn request_reload(_: libc::c_int) {
+    RELOAD_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+// Reloads the config when the global file or the project .ding.toml changes,
+// or on SIGHUP. A config that fails validation is logged and ignored, leaving
+// the running settings in place. The bind address, port, socket and mDNS
+// advertisement are fixed at startup.
+fn watch_listener_config(config_path: Option<PathBuf>, args: ListenArgs, settings: &LiveSettings) {
+    #[cfg(unix)]
+    unsafe {
+        libc::signal(
+            libc::SIGHUP,
+            request_reload as *const () as libc::sighandler_t,
+        );
+    }
+    let mut seen = config_fingerprint(config_path.as_ref());
+    loop {
+        std::thread::sleep(CONFIG_POLL_INTERVAL);
+        let fingerprint = config_fingerprint(config_path.as_ref());
+        let signalled = RELOAD_REQUESTED.swap(false, Ordering::SeqCst);
+        if fingerprint == seen && !signalled {
+            continue;
+        }
+        seen = fingerprint;
+        match reload_listener_settings(config_path.as_ref(), &args, &settings.current()) {
+            Ok(new_settings) => {
+                let log = new_settings.config.as_ref().and_then(|c| c.log.as_ref());
+                logging::configure(log, Level::Info);
+                settings.replace(new_settings);
+                logging::info("config", "reloaded config");
             }
+            Err(err) => logging::error(
+                "config",
+                &format!("config reload failed, keeping the previous config: {err}"),
+            ),
         }
+    }
+}
 
-        let mut body = String::new();
-        if request.as_reader().read_to_string(&mut body).is_err() {
-            let response = json_response(400, r#"{"error":"invalid body"}"#);
-            let _ = request.respond(response);
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// Modification time and size of each config file in play; editors that
+// replace the file are caught as well as in-place writes.
+fn config_fingerprint(config_path: Option<&PathBuf>) -> Vec<(PathBuf, Option<(SystemTime, u64)>)> {
+    let global = config_path.cloned().unwrap_or_else(default_config_path);
+    let project = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| config::find_project_file(&cwd));
+    std::iter::once(global)
+        .chain(project)
+        .map(|path| {
+            let stamp = fs::metadata(&path)
+                .ok()
+                .and_then(|meta| Some((meta.modified().ok()?, meta.len())));
+            (path, stamp)
+        })
+        .collect()
+}
+
+// A missing or empty config is a failed reload rather than a reset to the
+// defaults, and the token can only be relaxed by saying so in the config.
+fn reload_listener_settings(
+    config_path: Option<&PathBuf>,
+    args: &ListenArgs,
+    current: &ListenerSettings,
+) -> Result<ListenerSettings, String> {
+    let resolved = validated_config(config_path).map_err(|issues| match issues.len() {
+        1 => issues[0].clone(),
+        n => format!("{} (and {} more)", issues[0], n - 1),
+    })?;
+    let Some(resolved) = resolved.filter(|resolved| !resolved.values.is_empty()) else {
+        return Err("the config is missing or empty".to_string());
+    };
+    let explicit_require = resolved
+        .config
+        .listener
+        .as_ref()
+        .and_then(|listener| listener.require_token);
+    let settings =
+        ListenerSettings::new(Some(resolved.config), args).map_err(|err| err.to_string())?;
+    if current.token.is_some() && settings.token.is_none() {
+        return Err(
+            "listener.token is no longer set; restart `ding listen` to drop it".to_string(),
+        );
+    }
+    if current.require_token && !settings.require_token && explicit_require != Some(false) {
+        return Err(
+            "the token would no longer be required; set listener.require_token = false to allow that"
+                .to_string(),
+        );
+    }
+    Ok(settings)
+}
+
+// Requests arriving over the Unix socket have no remote address, so
+// allow_hosts does not apply to them; the socket's file mode guards access.
+fn serve_listener(server: tiny_http::Server, state: &Arc<ListenerState>, live: &LiveSettings) {
+    for mut request in server.incoming_requests() {
+        let settings = live.current();
+        let access = AccessLog::start(&request);
+        let path = access.path.clone();
+        if path == "/ping" {
+            let response = json_response(200, r#"{"status":"ok"}"#);
+            access.respond(request, response, None);
             continue;
         }
 
-        let envelope: RemoteEnvelope = match serde_json::from_str(&body) {
-            Ok(payload) => payload,
-            Err(_) => {
-                let response = json_response(400, r#"{"error":"invalid json"}"#);
-                let _ = request.respond(response);
+        let expected_method =
+            if path == "/events" || path == "/metrics" || path.starts_with("/status/") {
+                tiny_http::Method::Get
+            } else if path == "/notify" || path == "/notify/batch" {
+                tiny_http::Method::Post
+            } else {
+                let response = json_response(404, r#"{"error":"not found"}"#);
+                access.respond(request, response, None);
                 continue;
-            }
-        };
+            };
 
-        let mut notification = envelope.notification;
-        if debug_enabled() {
-            let source = notification.source.as_deref().unwrap_or("(none)");
-            debug_log(&format!(
-                "listener notify title=\"{}\" source={source}",
-                notification.title
-            ));
+        if request.method() != &expected_method {
+            let response = json_response(405, r#"{"error":"method not allowed"}"#);
+            access.respond(request, response, None);
+            continue;
         }
-        notification.icon = None;
-        if notification.title.trim().is_empty() {
-            notification.title = "Notification".to_string();
+
+        let require_token = if path == "/metrics" {
+            settings.metrics_require_token
+        } else {
+            settings.require_token
+        };
+        if let Some(response) = authorize_request(
+            &request,
+            &settings.allow_hosts,
+            require_token,
+            settings.token.as_deref(),
+            path == "/events",
+        ) {
+            let reason = if response.status_code().0 == 403 {
+                "host"
+            } else {
+                "token"
+            };
+            state.metrics.auth_failure(reason);
+            access.respond(request, response, None);
+            continue;
         }
 
-        if prefix_hostname {
-            if let Some(host) = envelope
-                .context
-                .as_ref()
-                .and_then(|ctx| ctx.origin_host.as_deref())
+        if path == "/metrics" {
+            let body = state.metrics.render(state.status.pending());
+            let mut response = tiny_http::Response::from_string(body);
+            if let Ok(header) =
+                tiny_http::Header::from_bytes("Content-Type", "text/plain; version=0.0.4")
             {
-                let suffix = format!(" [{host}]");
-                if !notification.title.ends_with(&suffix) {
-                    notification.title = format!("{}{}", notification.title, suffix);
-                }
+                response.add_header(header);
             }
+            access.respond(request, response, None);
+            continue;
         }
 
-        let source_key = notification.source.as_deref();
-        let source_config = resolve_source_config(config.as_ref(), source_key);
-        let macos_config = resolve_macos_config(config.as_ref(), source_config, source_key);
+        if let Some(id) = path.strip_prefix("/status/") {
+            let id = id.to_string();
+            let wait = query_param(request.url(), "wait")
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(0)
+                .min(STATUS_MAX_WAIT_SECS);
+            // Long-polls block, so answer them off the accept loop.
+            let Some(slot) = state.try_wait() else {
+                let response = json_response(503, r#"{"error":"too many waiting requests"}"#);
+                access.respond(request, response, None);
+                continue;
+            };
+            let state = Arc::clone(state);
+            std::thread::spawn(move || {
+                let _slot = slot;
+                let response = match state.status.wait(&id, Duration::from_secs(wait)) {
+                    Some(state) => json_response(200, &state.to_json(&id).to_string()),
+                    None => json_response(404, r#"{"error":"unknown id"}"#),
+                };
+                access.respond(request, response, None);
+            });
+            continue;
+        }
 
-        let local_context = detect_context();
-        let _ = deliver_macos(
-            notification,
-            macos_config,
-            on_click.clone(),
-            on_click.is_some(),
-            false,
-            false,
-            local_context,
-        );
+        if path == "/events" {
+            let Some(subscription) = state.events.subscribe() else {
+                let response = json_response(503, r#"{"error":"too many event streams"}"#);
+                access.respond(request, response, None);
+                continue;
+            };
+            // Logged when the stream opens; it stays open until the client leaves.
+            access.finish(200, None);
+            let cors_origin = settings.cors_origin.clone();
+            std::thread::spawn(move || {
+                stream_listener_events(request, subscription, cors_origin.as_deref())
+            });
+            continue;
+        }
 
-        let response = json_response(200, r#"{"status":"ok"}"#);
-        let _ = request.respond(response);
+        let mut body = String::new();
+        if request.as_reader().read_to_string(&mut body).is_err() {
+            let response = json_response(400, r#"{"error":"invalid body"}"#);
+            access.respond(request, response, None);
+            continue;
+        }
+
+        let peer = request.remote_addr().map(|addr| addr.ip());
+        let mut sources = Vec::new();
+        let response = if path == "/notify/batch" {
+            match serde_json::from_str::<Vec<RemoteEnvelope>>(&body) {
+                Ok(envelopes) => match envelopes
+                    .iter()
+                    .find_map(|envelope| envelope.notification.check_actions().err())
+                {
+                    Some(err) => invalid_actions_response(&err),
+                    None => {
+                        for envelope in &envelopes {
+                            if let Some(source) = &envelope.notification.source
+                                && !sources.contains(source)
+                            {
+                                sources.push(source.clone());
+                            }
+                        }
+                        match accept_remote_batch(state, &settings, envelopes, peer) {
+                            Some(body) => json_response(200, &body.to_string()),
+                            None => callbacks_busy_response(),
+                        }
+                    }
+                },
+                Err(_) => json_response(400, r#"{"error":"invalid json"}"#),
+            }
+        } else {
+            match serde_json::from_str::<RemoteEnvelope>(&body) {
+                Ok(envelope) if let Err(err) = envelope.notification.check_actions() => {
+                    invalid_actions_response(&err)
+                }
+                Ok(envelope) => match reserve_callback_slots(state, [&envelope]) {
+                    Some(mut slots) => {
+                        sources.extend(envelope.notification.source.clone());
+                        let id = accept_remote_envelope(
+                            state,
+                            &settings,
+                            envelope,
+                            true,
+                            peer,
+                            slots.pop().flatten(),
+                        );
+                        let body = serde_json::json!({ "status": "ok", "id": id });
+                        json_response(200, &body.to_string())
+                    }
+                    None => callbacks_busy_response(),
+                },
+                Err(_) => json_response(400, r#"{"error":"invalid json"}"#),
+            }
+        };
+        let source = (!sources.is_empty()).then(|| sources.join(","));
+        access.respond(request, response, source.as_deref());
     }
+}
 
-    Ok(())
+// Posting a callback can take seconds, so it happens on a worker thread that
+// holds a wait slot; when one envelope can't get a slot, none are accepted.
+fn reserve_callback_slots<'a>(
+    state: &Arc<ListenerState>,
+    envelopes: impl IntoIterator<Item = &'a RemoteEnvelope>,
+) -> Option<Vec<Option<WaitSlot>>> {
+    envelopes
+        .into_iter()
+        .map(|envelope| match envelope.callback_url {
+            Some(_) => state.try_wait().map(Some),
+            None => Some(None),
+        })
+        .collect()
 }
 
-fn handle_remote(command: RemoteCmd, config_path: Option<&PathBuf>) -> Result<(), NotifallError> {
-    match command {
-        RemoteCmd::Ping(args) => handle_remote_ping(args, config_path),
-    }
+fn callbacks_busy_response() -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    json_response(503, r#"{"error":"too many waiting requests"}"#)
 }
 
-fn handle_telegram(
-    command: TelegramCmd,
-    config_path: Option<&PathBuf>,
-) -> Result<(), NotifallError> {
-    match command {
-        TelegramCmd::ChatId(args) => handle_telegram_chat_id(args, config_path),
-    }
+// A URL button would be opened on this machine, so envelopes with anything
+// but web links are refused outright rather than shown without them.
+fn invalid_actions_response(err: &str) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let body = serde_json::json!({ "error": format!("invalid action: {err}") });
+    json_response(400, &body.to_string())
 }
 
-fn handle_telegram_chat_id(
-    args: TelegramChatIdArgs,
-    config_path: Option<&PathBuf>,
-) -> Result<(), NotifallError> {
-    let path = config_path
-        .cloned()
-        .unwrap_or_else(default_config_path);
-    let existing = fs::read_to_string(&path).unwrap_or_default();
-    let doc = toml_edit::DocumentMut::from_str(&existing)?;
-    let token = args
-        .token
-        .or_else(|| {
-            doc.get("telegram")
-                .and_then(|v| v.get("bot_token"))
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string())
-        })
-        .ok_or_else(|| {
-            NotifallError::Provider(ProviderError::Message(
-                "telegram bot_token is not configured".to_string(),
-            ))
-        })?;
+// One `listener.access` record per request, written once it is answered.
+struct AccessLog {
+    remote: String,
+    method: String,
+    path: String,
+    started: Instant,
+}
 
-    let updates_url = format!("https://api.telegram.org/bot{token}/getUpdates");
-    let response = ureq::get(&updates_url).call();
-    let value = match response {
-        Ok(res) => res
-            .into_json::<serde_json::Value>()
-            .map_err(|err| NotifallError::Provider(ProviderError::Message(err.to_string())))?,
-        Err(ureq::Error::Status(code, res)) => {
-            let desc = res
-                .into_json::<serde_json::Value>()
-                .ok()
-                .and_then(|v| v.get("description").and_then(|d| d.as_str()).map(|s| s.to_string()))
-                .unwrap_or_else(|| format!("telegram error status {code}"));
-            return Err(NotifallError::Provider(ProviderError::Message(desc)));
+impl AccessLog {
+    fn start(request: &tiny_http::Request) -> Self {
+        AccessLog {
+            remote: request
+                .remote_addr()
+                .map(|addr| addr.ip().to_string())
+                .unwrap_or_else(|| "unix".to_string()),
+            method: request.method().to_string(),
+            path: request.url().split('?').next().unwrap_or("").to_string(),
+            started: Instant::now(),
         }
-        Err(err) => {
-            return Err(NotifallError::Provider(ProviderError::Message(
-                err.to_string(),
-            )))
+    }
+
+    fn respond<R: std::io::Read>(
+        self,
+        request: tiny_http::Request,
+        response: tiny_http::Response<R>,
+        source: Option<&str>,
+    ) {
+        let status = response.status_code().0;
+        let _ = request.respond(response);
+        self.finish(status, source);
+    }
+
+    fn finish(self, status: u16, source: Option<&str>) {
+        let mut fields = vec![
+            ("remote", serde_json::Value::from(self.remote)),
+            ("method", serde_json::Value::from(self.method)),
+            ("path", serde_json::Value::from(self.path)),
+            ("status", serde_json::Value::from(status)),
+            (
+                "latency_ms",
+                serde_json::Value::from(self.started.elapsed().as_millis() as u64),
+            ),
+        ];
+        if let Some(source) = source {
+            fields.push(("source", serde_json::Value::from(source)));
         }
-    };
+        // Without a `[log]` section, service output goes to a file nothing
+        // rotates, so one line per request stays at debug.
+        let level = if logging::configured() {
+            Level::Info
+        } else {
+            Level::Debug
+        };
+        logging::log(level, "listener.access", "request", &fields);
+    }
+}
 
-    let result = value
-        .get("result")
-        .and_then(|v| v.as_array())
-        .ok_or_else(|| {
-            NotifallError::Provider(ProviderError::Message(
-                "telegram getUpdates returned no results".to_string(),
-            ))
-        })?;
+#[cfg(unix)]
+fn bind_listener_socket(path: &std::path::Path) -> Result<tiny_http::Server, NotifallError> {
+    use std::os::unix::fs::FileTypeExt;
 
-    let mut chat_ids = Vec::new();
-    for item in result {
-        if let Some(chat_id) = item
-            .get("message")
-            .and_then(|m| m.get("chat"))
-            .and_then(|c| c.get("id"))
-        {
-            if let Some(id) = chat_id.as_i64() {
-                chat_ids.push(id.to_string());
-            } else if let Some(id) = chat_id.as_str() {
-                chat_ids.push(id.to_string());
-            }
-        }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
     }
+    // A socket left behind by a previous run would make the bind fail.
+    if fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_socket()) {
+        fs::remove_file(path)?;
+    }
+    // The socket is created 0600 rather than narrowed after the bind, so no
+    // other user can connect in between. No other thread is running yet to
+    // notice the umask change.
+    let umask = unsafe { libc::umask(0o177) };
+    let server = tiny_http::Server::http_unix(path);
+    unsafe { libc::umask(umask) };
+    Ok(server.map_err(std::io::Error::other)?)
+}
 
-    chat_ids.sort();
-    chat_ids.dedup();
+#[cfg(not(unix))]
+fn bind_listener_socket(path: &std::path::Path) -> Result<tiny_http::Server, NotifallError> {
+    Err(NotifallError::Provider(ProviderError::Message(format!(
+        "cannot listen on {}: unix sockets are not supported on this platform",
+        path.display()
+    ))))
+}
 
-    if chat_ids.is_empty() {
-        return Err(NotifallError::Provider(ProviderError::Message(
-            "no chat_id found in getUpdates (send a message to the bot first)".to_string(),
-        )));
+pub(crate) fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
     }
+}
 
-    for id in &chat_ids {
-        println!("{id}");
+fn accept_remote_envelope(
+    state: &Arc<ListenerState>,
+    settings: &ListenerSettings,
+    envelope: RemoteEnvelope,
+    prefix_hostname: bool,
+    peer: Option<IpAddr>,
+    callback_slot: Option<WaitSlot>,
+) -> String {
+    let mut notification = envelope.notification;
+    if logging::enabled(Level::Debug, "listener") {
+        let source = notification.source.as_deref().unwrap_or("(none)");
+        logging::debug(
+            "listener",
+            &format!(
+                "listener notify title=\"{}\" source={source}",
+                notification.title
+            ),
+        );
+    }
+    notification.icon = None;
+    if notification.title.trim().is_empty() {
+        notification.title = "Notification".to_string();
     }
 
-    if args.apply {
-        let mut doc = toml_edit::DocumentMut::from_str(&existing)?;
-        set_toml_key(
-            &mut doc,
-            "telegram.chat_id",
-            toml_edit::Value::from(chat_ids[0].as_str()),
-        )?;
-        let new_contents = doc.to_string();
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)?;
+    if prefix_hostname && settings.prefix_hostname {
+        if let Some(host) = envelope
+            .context
+            .as_ref()
+            .and_then(|ctx| ctx.origin_host.as_deref())
+        {
+            let suffix = format!(" [{host}]");
+            if !notification.title.ends_with(&suffix) {
+                notification.title = format!("{}{}", notification.title, suffix);
+            }
         }
-        fs::write(&path, new_contents)?;
-        println!("set telegram.chat_id in {}", path.display());
     }
 
-    Ok(())
-}
+    let config = settings.config.as_ref();
+    let source_key = notification.source.as_deref();
+    let source_config = resolve_source_config(config, source_key);
+    let macos_config = resolve_macos_config(config, source_config, source_key);
 
-fn handle_remote_ping(
-    args: RemotePingArgs,
-    config_path: Option<&PathBuf>,
-) -> Result<(), NotifallError> {
-    let config = load_config(config_path)?;
-    let remote_cfg = config.and_then(|c| c.remote).unwrap_or_default();
-    let target = resolve_remote_target(
-        args.remote_host.as_deref(),
-        args.remote_port,
-        remote_cfg.host.as_deref(),
-        remote_cfg.port,
-        remote_cfg.url.as_deref(),
-    )
-    .ok_or_else(|| {
-        NotifallError::Provider(ProviderError::Message(
-            "remote host is not configured".to_string(),
-        ))
-    })?;
-    let token = args.remote_token.or(remote_cfg.token);
-    let ping_url = to_ping_url(&target.0);
-
-    let agent = ureq::AgentBuilder::new()
-        .timeout_connect(Duration::from_millis(2000))
-        .timeout_read(Duration::from_millis(2000))
-        .build();
-    let mut request = agent.get(&ping_url);
-    if let Some(token) = token.as_deref() {
-        request = request.set("Authorization", &format!("Bearer {token}"));
-    }
-    match request.call() {
-        Ok(_) => {
-            println!("ok");
-            Ok(())
+    if let Some(macos) = macos_config.as_ref()
+        && let Err(err) = template::apply(
+            &mut notification,
+            macos.title_template.as_deref(),
+            macos.message_template.as_deref(),
+            Some(&envelope.context.clone().unwrap_or_default()),
+        )
+    {
+        logging::warn(
+            "listener",
+            &format!("listener macos template failed: {err}"),
+        );
+    }
+
+    let local_context = detect_context();
+    let id = state.status.register();
+    state.metrics.received(notification.source.as_deref());
+    state.events.publish(
+        "notification",
+        serde_json::json!({
+            "id": id,
+            "notification": notification,
+            "context": envelope.context,
+        }),
+    );
+    let callback_url = envelope.callback_url.filter(|url| {
+        let allowed = callback_allowed(url, peer, &settings.callback_hosts);
+        if !allowed {
+            logging::warn("listener", &format!(
+                "ignoring callback {url}: not the sender's address or in listener.callback_hosts"
+            ));
         }
-        Err(err) => Err(NotifallError::Provider(
-            ProviderError::Message(format!("remote ping failed: {err}")),
-        )),
+        allowed
+    });
+    let wait_for_click = envelope.wait_for_click
+        || settings.on_click.is_some()
+        || notification
+            .actions
+            .iter()
+            .any(|action| action.url.is_some());
+    let slot = match callback_slot {
+        Some(slot) => Some(slot),
+        None if wait_for_click => {
+            let slot = state.try_wait();
+            if slot.is_none() {
+                logging::warn(
+                    "listener",
+                    &format!(
+                        "too many notifications waiting for a click; showing {id} without click handling"
+                    ),
+                );
+            }
+            slot
+        }
+        None => None,
+    };
+    if let Some(slot) = slot {
+        let state = Arc::clone(state);
+        let on_click = settings.on_click.clone().filter(|_| wait_for_click);
+        let id = id.clone();
+        std::thread::spawn(move || {
+            let _slot = slot;
+            let delivery = deliver_listener_notification(
+                &state.metrics,
+                notification,
+                macos_config,
+                on_click.as_deref(),
+                wait_for_click,
+                local_context.as_ref(),
+            );
+            finish_listener_delivery(&state, &id, delivery, callback_url.as_deref());
+        });
+    } else {
+        let delivery = deliver_listener_notification(
+            &state.metrics,
+            notification,
+            macos_config,
+            None,
+            false,
+            local_context.as_ref(),
+        );
+        finish_listener_delivery(state, &id, delivery, None);
     }
+    id
 }
 
-fn handle_forward(
-    command: ForwardCmd,
-    config_path: Option<&PathBuf>,
-) -> Result<(), NotifallError> {
-    let path = config_path
-        .cloned()
-        .unwrap_or_else(default_config_path);
-    let existing = fs::read_to_string(&path).unwrap_or_default();
-    let mut doc = toml_edit::DocumentMut::from_str(&existing)?;
+fn accept_remote_batch(
+    state: &Arc<ListenerState>,
+    settings: &ListenerSettings,
+    envelopes: Vec<RemoteEnvelope>,
+    peer: Option<IpAddr>,
+) -> Option<serde_json::Value> {
+    // A sender waiting for a click or a callback gets its own notification, so
+    // only the others are folded into the summary.
+    let collapsible = |envelope: &RemoteEnvelope| {
+        settings.collapse_batches && !envelope.wait_for_click && envelope.callback_url.is_none()
+    };
+    if envelopes
+        .iter()
+        .filter(|envelope| collapsible(envelope))
+        .count()
+        > 1
+    {
+        let (collapsed, separate): (Vec<_>, Vec<_>) = envelopes
+            .into_iter()
+            .enumerate()
+            .partition(|(_, envelope)| collapsible(envelope));
+        let slots = reserve_callback_slots(state, separate.iter().map(|(_, envelope)| envelope))?;
+        let mut ids = vec![String::new(); collapsed.len() + separate.len()];
+        let summary = summarize_batch(collapsed.into_iter().map(|(_, e)| e).collect());
+        let summary_id = accept_remote_envelope(state, settings, summary, false, peer, None);
+        ids.fill(summary_id);
+        for ((index, envelope), slot) in separate.into_iter().zip(slots) {
+            ids[index] = accept_remote_envelope(state, settings, envelope, true, peer, slot);
+        }
+        return Some(serde_json::json!({
+            "status": "ok",
+            "ids": ids,
+            "collapsed": true,
+        }));
+    }
+
+    let slots = reserve_callback_slots(state, &envelopes)?;
+    let ids = envelopes
+        .into_iter()
+        .zip(slots)
+        .map(|(envelope, slot)| accept_remote_envelope(state, settings, envelope, true, peer, slot))
+        .collect::<Vec<_>>();
+    Some(serde_json::json!({ "status": "ok", "ids": ids }))
+}
 
-    match command {
-        ForwardCmd::Status => {
-            let enabled = forward_enabled_from_doc(&doc);
-            let targets = forward_targets_from_doc(&doc);
-            println!("forwarding: {}", if enabled { "on" } else { "off" });
-            if targets.is_empty() {
-                println!("targets: (none)");
+// Collapse a batch into one "7 notifications from build-server" notification.
+fn summarize_batch(envelopes: Vec<RemoteEnvelope>) -> RemoteEnvelope {
+    const MAX_LINES: usize = 5;
+
+    let count = envelopes.len();
+    let context = envelopes.iter().find_map(|e| e.context.clone());
+    let host = context.as_ref().and_then(|ctx| ctx.origin_host.clone());
+    let sources = envelopes
+        .iter()
+        .map(|e| e.notification.source.clone())
+        .collect::<std::collections::BTreeSet<_>>();
+    let source = match sources.len() {
+        1 => sources.into_iter().next().flatten(),
+        _ => None,
+    };
+
+    let mut lines = envelopes
+        .iter()
+        .take(MAX_LINES)
+        .map(|e| {
+            let n = &e.notification;
+            if n.title.trim().is_empty() {
+                n.message.clone()
             } else {
-                println!("targets: {}", targets.join(", "));
+                format!("{}: {}", n.title, n.message)
             }
-            return Ok(());
-        }
-        ForwardCmd::Off => {
-            set_forward_enabled(&mut doc, false);
+        })
+        .collect::<Vec<_>>();
+    if count > MAX_LINES {
+        lines.push(format!("...and {} more", count - MAX_LINES));
+    }
+
+    let title = match host {
+        Some(host) => format!("{count} notifications from {host}"),
+        None => format!("{count} notifications"),
+    };
+    RemoteEnvelope {
+        notification: Notification {
+            title,
+            message: lines.join("\n"),
+            source,
+            ..Notification::default()
+        },
+        context,
+        wait_for_click: false,
+        callback_url: None,
+    }
+}
+
+fn deliver_listener_notification(
+    metrics: &Metrics,
+    notification: Notification,
+    macos_config: Option<MacosConfig>,
+    on_click: Option<&str>,
+    wait_for_click: bool,
+    context: Option<&Context>,
+) -> DeliveryState {
+    let source = notification.source.as_deref();
+    let provider = match MacosProvider::new(macos_config) {
+        Ok(provider) => provider,
+        Err(err) => {
+            metrics.delivered(source, "macos", wait_for_click, false, Duration::ZERO);
+            return DeliveryState::Failed(err.to_string());
         }
-        ForwardCmd::Toggle => {
-            let enabled = forward_enabled_from_doc(&doc);
-            if enabled {
-                set_forward_enabled(&mut doc, false);
-            } else {
-                let targets = forward_targets_from_doc(&doc);
-                if targets.is_empty() {
-                    return Err(NotifallError::RemoteForwardMissingHost(
-                        forward_missing_targets_message(&path),
-                    ));
-                }
-                set_forward_enabled(&mut doc, true);
+    };
+    let started = Instant::now();
+    let sent = provider.send(&notification, SendOptions { wait_for_click });
+    metrics.delivered(
+        source,
+        provider.name(),
+        wait_for_click,
+        sent.is_ok(),
+        started.elapsed(),
+    );
+    match sent {
+        Ok(report) => {
+            let outcome = report.outcome.clone();
+            if let Err(err) = handle_click(
+                outcome,
+                on_click,
+                &notification,
+                context,
+                ClickSide::Listener,
+            ) {
+                logging::warn("listener", &format!("listener on_click failed: {err}"));
             }
+            DeliveryState::Finished(report.outcome.or(Some(DeliveryOutcome::Delivered)))
         }
-        ForwardCmd::On(args) => {
-            apply_forward_on(&mut doc, &path, &args)?;
+        Err(err) => DeliveryState::Failed(err.to_string()),
+    }
+}
+
+// A `--remote-callback` URL is only followed to the sender's own address or a
+// host in `listener.callback_hosts`, so a sender cannot aim the listener at
+// other machines on its network.
+fn callback_allowed(url: &str, peer: Option<IpAddr>, callback_hosts: &[String]) -> bool {
+    let Some(rest) = url
+        .strip_prefix("http://")
+        .or_else(|| url.strip_prefix("https://"))
+    else {
+        return false;
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    let host_port = authority
+        .rsplit_once('@')
+        .map_or(authority, |(_, host)| host);
+    let host = match host_port.strip_prefix('[') {
+        Some(bracketed) => bracketed.split(']').next().unwrap_or(bracketed),
+        None => host_port.split(':').next().unwrap_or(host_port),
+    };
+    callback_hosts
+        .iter()
+        .any(|allowed| allowed.eq_ignore_ascii_case(host))
+        || peer.is_some_and(|peer| host.parse::<IpAddr>() == Ok(peer))
+}
+
+fn finish_listener_delivery(
+    listener: &ListenerState,
+    id: &str,
+    state: DeliveryState,
+    callback_url: Option<&str>,
+) {
+    if let DeliveryState::Failed(err) = &state {
+        logging::warn("listener", &format!("listener delivery {id} failed: {err}"));
+    }
+    if let Some(url) = callback_url {
+        let agent = ureq::AgentBuilder::new()
+            .timeout_connect(Duration::from_millis(2000))
+            .timeout_read(Duration::from_millis(2000))
+            .build();
+        if let Err(err) = agent.post(url).send_json(state.to_json(id)) {
+            logging::warn(
+                "listener",
+                &format!("listener callback {url} failed: {err}"),
+            );
         }
     }
+    listener.events.publish("outcome", state.to_json(id));
+    listener.status.finish(id, state);
+}
 
-    let new_contents = doc.to_string();
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
+fn stream_listener_events(
+    request: tiny_http::Request,
+    subscription: crate::listener::Subscription,
+    cors_origin: Option<&str>,
+) {
+    use std::io::Write;
+    use std::sync::mpsc::RecvTimeoutError;
+
+    // tiny_http buffers chunked bodies, so write the stream by hand to get
+    // each event out as soon as it is published.
+    let mut writer = request.into_writer();
+    let mut head = "HTTP/1.1 200 OK\r\n\
+Content-Type: text/event-stream\r\n\
+Cache-Control: no-cache\r\n"
+        .to_string();
+    if let Some(origin) = cors_origin {
+        head.push_str(&format!("Access-Control-Allow-Origin: {origin}\r\n"));
+    }
+    head.push_str("Connection: close\r\n\r\n");
+    if writer
+        .write_all(head.as_bytes())
+        .and_then(|_| writer.flush())
+        .is_err()
+    {
+        return;
+    }
+    loop {
+        let frame = match subscription
+            .events
+            .recv_timeout(Duration::from_secs(EVENTS_KEEPALIVE_SECS))
+        {
+            Ok(event) => event.to_sse(),
+            Err(RecvTimeoutError::Timeout) => ": keepalive\n\n".to_string(),
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+        if writer
+            .write_all(frame.as_bytes())
+            .and_then(|_| writer.flush())
+            .is_err()
+        {
+            logging::debug("listener", "listener events client disconnected");
+            return;
+        }
     }
-    fs::write(&path, new_contents)?;
-    println!(
-        "forwarding {}",
-        if forward_enabled_from_doc(&doc) { "enabled" } else { "disabled" }
-    );
-    Ok(())
 }
 
-fn handle_config_set(
+fn handle_listen_service(
+    command: ListenCmd,
     config_path: Option<&PathBuf>,
-    args: ConfigSetArgs,
 ) -> Result<(), NotifallError> {
-    let path = config_path
-        .cloned()
-        .unwrap_or_else(default_config_path);
-    let existing = fs::read_to_string(&path).unwrap_or_default();
-    let mut doc = toml_edit::DocumentMut::from_str(&existing)?;
-    let value = parse_config_value(&args.value);
-    set_toml_key(&mut doc, &args.key, value)?;
-    let new_contents = doc.to_string();
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
+    let kind = service::ServiceKind::current();
+    let unit_path = kind.unit_path(&home_dir()?);
+    match command {
+        ListenCmd::Install(args) => install_listener_service(kind, &unit_path, config_path, args),
+        ListenCmd::Uninstall(args) => uninstall_listener_service(kind, &unit_path, args),
+        ListenCmd::Status => {
+            println!("service: {} ({})", kind.name(), unit_path.display());
+            println!(
+                "installed: {}",
+                if unit_path.exists() { "yes" } else { "no" }
+            );
+            println!(
+                "state: {}",
+                kind.state().unwrap_or_else(|| "unknown".to_string())
+            );
+            let config = load_config(config_path)?;
+            let (url, token) = local_listener_target(config.as_ref())?;
+            match ping_listener(&url, token.as_deref()) {
+                Ok(()) => println!("health: ok ({})", to_ping_url(&url)),
+                Err(err) => println!("health: {err}"),
+            }
+            Ok(())
+        }
     }
-    fs::write(&path, new_contents)?;
-    println!("set {} in {}", args.key, path.display());
-    Ok(())
-}
-
-fn handle_config_path(config_path: Option<&PathBuf>) -> Result<(), NotifallError> {
-    let path = config_path
-        .cloned()
-        .unwrap_or_else(default_config_path);
-    println!("{}", path.display());
-    Ok(())
 }
 
-fn handle_config_list(config_path: Option<&PathBuf>) -> Result<(), NotifallError> {
-    let path = config_path
-        .cloned()
-        .unwrap_or_else(default_config_path);
-    if !path.exists() {
-        println!("(no config file at {})", path.display());
+fn install_listener_service(
+    kind: service::ServiceKind,
+    unit_path: &PathBuf,
+    config_path: Option<&PathBuf>,
+    args: ListenServiceArgs,
+) -> Result<(), NotifallError> {
+    let exe = std::env::current_exe()?;
+    let log_dir = logs_dir();
+    // The service does not start in this directory.
+    let config_path = config_path
+        .map(|path| fs::canonicalize(path).or_else(|_| std::path::absolute(path)))
+        .transpose()?;
+    let config_path = config_path.as_ref();
+    let new_contents = kind.render(&exe, config_path.map(|p| p.as_path()), log_dir.as_deref());
+    let existing = fs::read_to_string(unit_path).unwrap_or_default();
+
+    if !args.apply {
+        print_diff(
+            unit_path,
+            &existing,
+            &new_contents,
+            "ding listen install --apply",
+        )?;
         return Ok(());
     }
-    let contents = fs::read_to_string(&path)?;
-    print!("{contents}");
+
+    if let Some(parent) = unit_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if let Some(dir) = log_dir.as_ref() {
+        fs::create_dir_all(dir)?;
+    }
+    if unit_path.exists() {
+        backup_file(unit_path)?;
+    }
+    fs::write(unit_path, new_contents)?;
+    kind.activate(unit_path)?;
+    println!("Installed listener service in {}", unit_path.display());
+
+    // Give the service a moment to bind before reporting health.
+    let config = load_config(config_path)?;
+    let (url, token) = local_listener_target(config.as_ref())?;
+    let mut last_err = None;
+    for _ in 0..10 {
+        match ping_listener(&url, token.as_deref()) {
+            Ok(()) => {
+                println!("listener is up at {}", to_ping_url(&url));
+                return Ok(());
+            }
+            Err(err) => last_err = Some(err),
+        }
+        std::thread::sleep(Duration::from_millis(500));
+    }
+    if let Some(err) = last_err {
+        eprintln!("listener did not answer yet: {err}");
+    }
     Ok(())
 }
 
-fn remote_url_from_doc(doc: &toml_edit::DocumentMut) -> Option<String> {
-    doc.get("remote")
-        .and_then(|v| v.get("url"))
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string())
-}
+fn uninstall_listener_service(
+    kind: service::ServiceKind,
+    unit_path: &PathBuf,
+    args: ListenServiceArgs,
+) -> Result<(), NotifallError> {
+    if !unit_path.exists() {
+        println!("no listener service installed at {}", unit_path.display());
+        return Ok(());
+    }
+    let existing = fs::read_to_string(unit_path)?;
+    if !args.apply {
+        print_diff(unit_path, &existing, "", "ding listen uninstall --apply")?;
+        return Ok(());
+    }
 
-fn remote_host_from_doc(doc: &toml_edit::DocumentMut) -> Option<String> {
-    doc.get("remote")
-        .and_then(|v| v.get("host"))
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string())
-        .or_else(|| remote_url_from_doc(doc).and_then(|url| parse_remote_url(&url).map(|t| t.0)))
+    if let Err(err) = kind.deactivate(unit_path) {
+        eprintln!("failed to stop listener service: {err}");
+    }
+    backup_file(unit_path)?;
+    fs::remove_file(unit_path)?;
+    kind.reload();
+    println!("Removed listener service {}", unit_path.display());
+    Ok(())
 }
 
-fn remote_port_from_doc(doc: &toml_edit::DocumentMut) -> Option<u16> {
-    doc.get("remote")
-        .and_then(|v| v.get("port"))
-        .and_then(|v| v.as_integer())
-        .and_then(|v| u16::try_from(v).ok())
-        .or_else(|| remote_url_from_doc(doc).and_then(|url| parse_remote_url(&url).map(|t| t.1)))
+// The listener as seen from this machine: bind-all addresses are pinged on loopback.
+fn local_listener_target(
+    config: Option<&Config>,
+) -> Result<(String, Option<String>), NotifallError> {
+    let listener_cfg = config.and_then(|c| c.listener.clone()).unwrap_or_default();
+    let host = match listener_cfg.bind.as_deref() {
+        None | Some("") | Some("0.0.0.0") | Some("::") => "127.0.0.1".to_string(),
+        Some(bind) => bind.to_string(),
+    };
+    let port = listener_cfg.port.unwrap_or(4280);
+    let token = listener_cfg.token()?;
+    Ok((format!("http://{host}:{port}/notify"), token))
 }
 
-fn forward_enabled_from_doc(doc: &toml_edit::DocumentMut) -> bool {
-    doc.get("forward")
-        .and_then(|v| v.get("enabled"))
-        .and_then(|v| v.as_bool())
-        .unwrap_or(false)
+fn handle_remote(command: RemoteCmd, config_path: Option<&PathBuf>) -> Result<(), NotifallError> {
+    match command {
+        RemoteCmd::Ping(args) => handle_remote_ping(args, config_path),
+        RemoteCmd::Discover(args) => handle_remote_discover(args),
+        RemoteCmd::SshConfig(args) => handle_remote_ssh_config(args, config_path),
+    }
 }
 
-fn forward_targets_from_doc(doc: &toml_edit::DocumentMut) -> Vec<String> {
-    doc.get("forward")
-        .and_then(|v| v.get("targets"))
-        .and_then(|v| v.as_array())
-        .map(|arr| {
-            arr.iter()
-                .filter_map(|item| item.as_str().map(|s| s.to_string()))
-                .collect::<Vec<_>>()
+fn handle_remote_ssh_config(
+    args: RemoteSshConfigArgs,
+    config_path: Option<&PathBuf>,
+) -> Result<(), NotifallError> {
+    let config = load_config(config_path)?;
+    let local_socket = args
+        .local_socket
+        .or_else(|| config.as_ref().and_then(|c| c.listener.as_ref()?.socket.clone()))
+        .map(|path| expand_home(&path))
+        .ok_or_else(|| {
+            NotifallError::Provider(ProviderError::NotConfigured(
+                "listener.socket is not configured; set it with `ding config set listener.socket ~/.config/ding/listener.sock` and restart `ding listen`".to_string(),
+            ))
+        })?;
+    let remote_socket = args
+        .remote_socket
+        .or_else(|| {
+            config
+                .as_ref()
+                .and_then(|c| c.remote.as_ref()?.socket.clone())
         })
-        .unwrap_or_default()
-}
+        .unwrap_or_else(|| match std::env::var("USER") {
+            Ok(user) if !user.is_empty() => format!("/tmp/ding-{user}.sock"),
+            _ => "/tmp/ding.sock".to_string(),
+        });
 
-fn set_forward_enabled(doc: &mut toml_edit::DocumentMut, enabled: bool) {
-    let table = doc.entry("forward").or_insert(toml_edit::table());
-    if let Some(table) = table.as_table_mut() {
-        table["enabled"] = toml_edit::Item::Value(toml_edit::Value::from(enabled));
+    match args.host {
+        Some(host) => {
+            println!("Host {host}");
+            println!(
+                "    RemoteForward {remote_socket} {}",
+                local_socket.display()
+            );
+        }
+        None => println!("RemoteForward {remote_socket} {}", local_socket.display()),
     }
+    eprintln!("# Add the above to ~/.ssh/config on this machine, then on the remote host run:");
+    eprintln!("#   ding config set remote.socket {remote_socket}");
+    eprintln!(
+        "# Set `StreamLocalBindUnlink yes` in the remote sshd_config so reconnects can replace a stale socket."
+    );
+    Ok(())
 }
 
-fn set_forward_targets(doc: &mut toml_edit::DocumentMut, targets: &[String]) {
-    let table = doc.entry("forward").or_insert(toml_edit::table());
-    if let Some(table) = table.as_table_mut() {
-        let mut arr = toml_edit::Array::new();
-        for target in targets {
-            arr.push(target.as_str());
-        }
-        table["targets"] = toml_edit::Item::Value(toml_edit::Value::Array(arr));
+fn handle_remote_discover(args: RemoteDiscoverArgs) -> Result<(), NotifallError> {
+    let listeners = discovery::discover(Duration::from_millis(args.timeout_ms)).map_err(|err| {
+        NotifallError::Provider(ProviderError::Message(format!(
+            "mDNS discovery failed: {err}"
+        )))
+    })?;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&listeners)?);
+        return Ok(());
     }
+    if listeners.is_empty() {
+        println!("(no ding listeners found)");
+        return Ok(());
+    }
+    for listener in listeners {
+        let addresses = listener
+            .addresses
+            .iter()
+            .map(|ip| ip.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let auth = listener.auth.as_deref().unwrap_or("-");
+        println!(
+            "{}\t{}:{}\t{addresses}\tauth={auth}",
+            listener.name, listener.hostname, listener.port
+        );
+    }
+    Ok(())
 }
 
-fn apply_forward_on(
-    doc: &mut toml_edit::DocumentMut,
-    path: &PathBuf,
-    args: &ForwardOnArgs,
-) -> Result<(), NotifallError> {
-    let mut targets = forward_targets_from_doc(doc);
-    let provided = args
-        .targets
-        .iter()
-        .map(|t| match t {
-            ForwardTarget::Remote => "remote".to_string(),
-            ForwardTarget::Telegram => "telegram".to_string(),
-        })
-        .collect::<Vec<_>>();
+fn handle_tail(args: TailArgs, config_path: Option<&PathBuf>) -> Result<(), NotifallError> {
+    use std::io::BufRead;
 
-    if args.append {
-        for target in provided {
-            if !targets.contains(&target) {
-                targets.push(target);
+    let config = load_config(config_path)?;
+    let remote_cfg = config
+        .as_ref()
+        .and_then(|c| c.remote.clone())
+        .unwrap_or_default();
+    let listener_cfg = config.and_then(|c| c.listener).unwrap_or_default();
+    // Tailing usually happens on the listener's own machine, so fall back to
+    // the local listener settings when no remote is configured.
+    let token = match args.remote_token.clone() {
+        Some(token) => Some(token),
+        None => match remote_cfg.token()? {
+            Some(token) => Some(token),
+            None => listener_cfg.token()?,
+        },
+    };
+    let target = resolve_remote_target(
+        args.remote_host.as_deref(),
+        args.remote_port,
+        &remote_cfg,
+        token.is_some(),
+    )?
+    .unwrap_or_else(|| {
+        let port = args.remote_port.or(listener_cfg.port).unwrap_or(4280);
+        (
+            format!("http://127.0.0.1:{port}/notify"),
+            "127.0.0.1".to_string(),
+            port,
+        )
+    });
+    let events_url = listener_endpoint(&target.0, "events");
+
+    let response = transport::request(
+        "GET",
+        &events_url,
+        token.as_deref(),
+        None,
+        Duration::from_millis(2000),
+        None,
+    )
+    .map_err(|err| {
+        NotifallError::Provider(ProviderError::Message(format!(
+            "failed to connect to {events_url}: {err}"
+        )))
+    })?;
+    if !response.is_success() {
+        return Err(NotifallError::Provider(ProviderError::Message(format!(
+            "failed to connect to {events_url}: status {}",
+            response.status
+        ))));
+    }
+
+    let reader = std::io::BufReader::new(response.body);
+    let mut kind = String::new();
+    for line in reader.lines() {
+        let line = line?;
+        if let Some(value) = line.strip_prefix("event:") {
+            kind = value.trim().to_string();
+        } else if let Some(data) = line.strip_prefix("data:") {
+            let data: serde_json::Value = match serde_json::from_str(data.trim()) {
+                Ok(data) => data,
+                Err(_) => continue,
+            };
+            if args.json {
+                println!(
+                    "{}",
+                    secret::redact(&serde_json::json!({ "event": kind, "data": data }).to_string())
+                );
+            } else {
+                print_tail_event(&kind, &data);
             }
+        } else if line.is_empty() {
+            kind.clear();
         }
-    } else if !provided.is_empty() {
-        targets = provided;
-    }
-
-    if targets.is_empty() {
-        return Err(NotifallError::RemoteForwardMissingHost(
-            forward_missing_targets_message(path),
-        ));
     }
+    Ok(())
+}
 
-    if targets.iter().any(|t| t == "remote") {
-        if let Some(host) = args.host.as_deref() {
-            set_remote_field(doc, "host", toml_edit::Value::from(host));
-        }
-        if let Some(port) = args.port {
-            set_remote_field(doc, "port", toml_edit::Value::from(port as i64));
-        }
-        if remote_host_from_doc(doc).is_none() {
-            return Err(NotifallError::RemoteForwardMissingHost(
-                forward_missing_remote_message(path),
-            ));
+fn print_tail_event(kind: &str, data: &serde_json::Value) {
+    let id = data.get("id").and_then(|v| v.as_str()).unwrap_or("-");
+    match kind {
+        "notification" => {
+            let notification = data.get("notification");
+            let field = |key: &str| {
+                notification
+                    .and_then(|n| n.get(key))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+            };
+            let source = notification
+                .and_then(|n| n.get("source"))
+                .and_then(|v| v.as_str())
+                .map(|s| format!(" ({s})"))
+                .unwrap_or_default();
+            println!("{id}\t{}{source}: {}", field("title"), field("message"));
         }
-        if remote_port_from_doc(doc).is_none() {
-            set_remote_field(doc, "port", toml_edit::Value::from(4280i64));
+        "outcome" => {
+            let status = data.get("status").and_then(|v| v.as_str()).unwrap_or("-");
+            let detail = match data.get("outcome") {
+                Some(serde_json::Value::String(outcome)) => outcome.clone(),
+                Some(serde_json::Value::Null) | None => data
+                    .get("error")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string(),
+                Some(other) => other.to_string(),
+            };
+            println!("{id}\t-> {status} {detail}");
         }
+        other => println!("{id}\t{other} {data}"),
     }
+}
 
-    set_forward_targets(doc, &targets);
-    set_forward_enabled(doc, true);
-    Ok(())
+fn handle_telegram(
+    command: TelegramCmd,
+    config_path: Option<&PathBuf>,
+) -> Result<(), NotifallError> {
+    match command {
+        TelegramCmd::ChatId(args) => handle_telegram_chat_id(args, config_path),
+    }
 }
 
-fn forward_missing_targets_message(path: &PathBuf) -> String {
-    format!(
-        "Forwarding needs at least one target.\n\n\
-Set it with:\n  ding forward on remote --host mba --port 4280\n\
-or:\n  ding forward on telegram\n\n\
-Config file: {}\n\
-If missing, run: ding config init",
-        path.display()
-    )
-}
-
-fn forward_missing_remote_message(path: &PathBuf) -> String {
-    format!(
-        "Forwarding to remote needs a host.\n\n\
-Set it with:\n  ding forward on remote --host mba --port 4280\n\
-or:\n  ding config set remote.host mba\n  ding config set remote.port 4280\n\n\
-Config file: {}\n\
-If missing, run: ding config init",
-        path.display()
-    )
-}
-
-fn set_remote_field(doc: &mut toml_edit::DocumentMut, key: &str, value: toml_edit::Value) {
-    let table = doc.entry("remote").or_insert(toml_edit::table());
-    if let Some(table) = table.as_table_mut() {
-        table[key] = toml_edit::Item::Value(value);
-    }
-}
-
-fn parse_config_value(raw: &str) -> toml_edit::Value {
-    if raw.eq_ignore_ascii_case("true") {
-        return toml_edit::Value::from(true);
-    }
-    if raw.eq_ignore_ascii_case("false") {
-        return toml_edit::Value::from(false);
-    }
-    if let Ok(int_value) = raw.parse::<i64>() {
-        return toml_edit::Value::from(int_value);
-    }
-    toml_edit::Value::from(raw)
-}
-
-fn set_toml_key(
-    doc: &mut toml_edit::DocumentMut,
-    key: &str,
-    value: toml_edit::Value,
+fn handle_telegram_chat_id(
+    args: TelegramChatIdArgs,
+    config_path: Option<&PathBuf>,
 ) -> Result<(), NotifallError> {
-    let parts: Vec<&str> = key.split('.').collect();
-    if parts.is_empty() {
-        return Ok(());
-    }
-    let mut table = doc.as_table_mut();
-    for part in &parts[..parts.len().saturating_sub(1)] {
-        if !table.contains_key(part) {
-            table[part] = toml_edit::Item::Table(toml_edit::Table::new());
-        }
-        if !table[part].is_table() {
-            table[part] = toml_edit::Item::Table(toml_edit::Table::new());
+    let path = config_path.cloned().unwrap_or_else(default_config_path);
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+    let token = match args.token {
+        Some(token) => Some(token),
+        None => match load_config(config_path)?.and_then(|c| c.telegram) {
+            Some(telegram) => telegram.bot_token()?,
+            None => None,
+        },
+    };
+    let token = token.ok_or_else(|| {
+        NotifallError::Provider(ProviderError::NotConfigured(
+            "telegram bot_token is not configured".to_string(),
+        ))
+    })?;
+    secret::register(&token);
+
+    let updates_url = format!("https://api.telegram.org/bot{token}/getUpdates");
+    let response = ureq::get(&updates_url).call();
+    let value = match response {
+        Ok(res) => res
+            .into_json::<serde_json::Value>()
+            .map_err(|err| NotifallError::Provider(ProviderError::Message(err.to_string())))?,
+        Err(ureq::Error::Status(code, res)) => {
+            let desc = res
+                .into_json::<serde_json::Value>()
+                .ok()
+                .and_then(|v| {
+                    v.get("description")
+                        .and_then(|d| d.as_str())
+                        .map(|s| s.to_string())
+                })
+                .unwrap_or_else(|| format!("telegram error status {code}"));
+            return Err(NotifallError::Provider(ProviderError::Message(desc)));
         }
-        if let Some(next) = table[part].as_table_mut() {
-            table = next;
-        } else {
+        Err(err) => {
             return Err(NotifallError::Provider(ProviderError::Message(
-                "invalid config path".to_string(),
+                err.to_string(),
             )));
         }
-    }
-    let last = parts[parts.len() - 1];
-    table[last] = toml_edit::Item::Value(value);
-    Ok(())
-}
-
-fn handle_remote_send(
-    config: Option<&Config>,
-    args: &SendArgs,
-    notification: Notification,
-    remote_notification: Notification,
-    context: Option<Context>,
-    source_config: Option<&SourceConfig>,
-    source: Option<&str>,
-) -> Result<(), NotifallError> {
-    let remote_cfg = config.and_then(|c| c.remote.clone()).unwrap_or_default();
-    let target = resolve_remote_target(
-        args.remote_host.as_deref(),
-        args.remote_port,
-        remote_cfg.host.as_deref(),
-        remote_cfg.port,
-        remote_cfg.url.as_deref(),
-    );
-    let token = args.remote_token.clone().or(remote_cfg.token);
-    let timeout_ms = args.remote_timeout_ms.or(remote_cfg.timeout_ms).unwrap_or(2000);
-    let retries = args.remote_retries.or(remote_cfg.retries).unwrap_or(2);
-    let fallback = !args.no_fallback && remote_cfg.fallback_to_local.unwrap_or(true);
-
-    let envelope = RemoteEnvelope {
-        notification: remote_notification,
-        context: Some(RemoteContext::from_local(context.clone())),
     };
 
-    let send_result = match target {
-        Some((url, _host, _port)) => {
-            send_remote_request(&url, token.as_deref(), timeout_ms, retries, &envelope)
-        }
-        None => Err(NotifallError::Provider(ProviderError::Message(
-            "remote host is not configured".to_string(),
-        ))),
-    };
-    if send_result.is_ok() {
-        if args.json {
-            print_send_output("remote", None, false, None)?;
-        }
-        return Ok(());
-    }
+    let result = value
+        .get("result")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| {
+            NotifallError::Provider(ProviderError::Message(
+                "telegram getUpdates returned no results".to_string(),
+            ))
+        })?;
 
-    if fallback && cfg!(target_os = "macos") {
-        let macos_config = resolve_macos_config(config, source_config, source);
-        return deliver_macos(
-            notification,
-            macos_config,
-            args.on_click.clone(),
-            args.background,
-            args.wait_for_click,
-            args.json,
-            context,
-        );
+    let mut chat_ids = Vec::new();
+    for item in result {
+        if let Some(chat_id) = item
+            .get("message")
+            .and_then(|m| m.get("chat"))
+            .and_then(|c| c.get("id"))
+        {
+            if let Some(id) = chat_id.as_i64() {
+                chat_ids.push(id.to_string());
+            } else if let Some(id) = chat_id.as_str() {
+                chat_ids.push(id.to_string());
+            }
+        }
     }
 
-    send_result
-}
-
-fn handle_forward_send(
-    config: Option<&Config>,
-    args: &SendArgs,
-    notification: Notification,
-    remote_notification: Notification,
-    context: Option<Context>,
-    source_config: Option<&SourceConfig>,
-    source: Option<&str>,
-) -> Result<(), NotifallError> {
-    let targets = config
-        .and_then(|c| c.forward.as_ref())
-        .and_then(|f| f.targets.clone())
-        .unwrap_or_default();
+    chat_ids.sort();
+    chat_ids.dedup();
 
-    if targets.is_empty() {
+    if chat_ids.is_empty() {
         return Err(NotifallError::Provider(ProviderError::Message(
-            "forward targets are not configured".to_string(),
+            "no chat_id found in getUpdates (send a message to the bot first)".to_string(),
         )));
     }
 
-    #[derive(serde::Serialize)]
-    struct ForwardResult {
-        provider: String,
-        ok: bool,
-        error: Option<String>,
+    for id in &chat_ids {
+        println!("{id}");
     }
 
-    let mut results = Vec::new();
-    let mut successes = 0usize;
-
-    for target in targets {
-        let result = match target.as_str() {
-            "remote" => handle_remote_send(
-                config,
-                args,
-                notification.clone(),
-                remote_notification.clone(),
-                context.clone(),
-                source_config,
-                source,
-            )
-            .map_err(|e| e.to_string()),
-            "telegram" => match resolve_telegram_config(config, args) {
-                Ok(telegram_config) => match TelegramProvider::new(telegram_config) {
-                    Ok(provider) => provider
-                        .send(&notification, SendOptions { wait_for_click: false })
-                        .map(|_| ())
-                        .map_err(|e| e.to_string()),
-                    Err(err) => Err(err.to_string()),
-                },
-                Err(err) => Err(err.to_string()),
-            },
-            other => Err(format!("unknown forward target: {}", other)),
-        };
-
-        match result {
-            Ok(()) => {
-                successes += 1;
-                results.push(ForwardResult {
-                    provider: target,
-                    ok: true,
-                    error: None,
-                });
-            }
-            Err(err) => {
-                results.push(ForwardResult {
-                    provider: target,
-                    ok: false,
-                    error: Some(err),
-                });
-            }
+    if args.apply {
+        let mut doc = toml_edit::DocumentMut::from_str(&existing)?;
+        set_toml_key(
+            &mut doc,
+            "telegram.chat_id",
+            toml_edit::Value::from(chat_ids[0].as_str()),
+        )?;
+        let new_contents = doc.to_string();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
         }
+        fs::write(&path, new_contents)?;
+        println!("set telegram.chat_id in {}", path.display());
     }
 
+    Ok(())
+}
+
+fn handle_remote_ping(
+    args: RemotePingArgs,
+    config_path: Option<&PathBuf>,
+) -> Result<(), NotifallError> {
+    let config = load_config(config_path)?;
+    let remote_cfg = config.and_then(|c| c.remote).unwrap_or_default();
+    let token = match args.remote_token {
+        Some(token) => Some(token),
+        None => remote_cfg.token()?,
+    };
+    let target = resolve_remote_target(
+        args.remote_host.as_deref(),
+        args.remote_port,
+        &remote_cfg,
+        token.is_some(),
+    )?
+    .ok_or_else(|| {
+        NotifallError::Provider(ProviderError::NotConfigured(
+            "remote host is not configured".to_string(),
+        ))
+    })?;
+    let started = Instant::now();
+    ping_listener(&target.0, token.as_deref())?;
     if args.json {
         let output = serde_json::json!({
-            "provider": "forward",
-            "results": results,
+            "ok": true,
+            "url": to_ping_url(&target.0),
+            "latency_ms": started.elapsed().as_millis() as u64,
         });
-        println!("{}", serde_json::to_string_pretty(&output)?);
-    }
-
-    if successes == 0 {
-        return Err(NotifallError::Provider(ProviderError::Message(
-            "forwarding failed for all targets".to_string(),
-        )));
+        println!("{output}");
+    } else {
+        println!("ok");
     }
+    Ok(())
+}
 
-    let failed = results.iter().filter(|r| !r.ok).count();
-    if failed > 0 && !args.json {
-        eprintln!("forwarding: {} target(s) failed", failed);
+fn ping_listener(url: &str, token: Option<&str>) -> Result<(), NotifallError> {
+    let ping_url = to_ping_url(url);
+    let timeout = Duration::from_millis(2000);
+    match transport::request("GET", &ping_url, token, None, timeout, Some(timeout)) {
+        Ok(response) if response.is_success() => Ok(()),
+        Ok(response) => Err(NotifallError::Provider(ProviderError::Message(format!(
+            "remote ping failed: status {}",
+            response.status
+        )))),
+        Err(err) => Err(NotifallError::Provider(transport_provider_error(
+            err.failure,
+            format!("remote ping failed: {err}"),
+        ))),
     }
-
-    Ok(())
 }
 
-fn send_remote_request(
-    url: &str,
-    token: Option<&str>,
-    timeout_ms: u64,
-    retries: u32,
-    envelope: &RemoteEnvelope,
-) -> Result<(), NotifallError> {
-    debug_log(&format!("remote_url={url} timeout_ms={timeout_ms} retries={retries}"));
-    let agent = ureq::AgentBuilder::new()
-        .timeout_connect(Duration::from_millis(timeout_ms))
-        .timeout_read(Duration::from_millis(timeout_ms))
-        .build();
-    let body = serde_json::to_value(envelope)?;
-    let mut last_err = None;
+fn handle_forward(command: ForwardCmd, config_path: Option<&PathBuf>) -> Result<(), NotifallError> {
+    let path = config_path.cloned().unwrap_or_else(default_config_path);
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+    let mut doc = toml_edit::DocumentMut::from_str(&existing)?;
 
-    for _ in 0..=retries {
-        let mut request = agent.post(url).set("Content-Type", "application/json");
-        if let Some(token) = token {
-            request = request.set("Authorization", &format!("Bearer {token}"));
-        }
-        match request.send_json(body.clone()) {
-            Ok(response) => {
-                if response.status() >= 200 && response.status() < 300 {
-                    return Ok(());
-                }
-                last_err = Some(format!("remote error: status {}", response.status()));
-            }
-            Err(ureq::Error::Status(code, _)) => {
-                last_err = Some(format!("remote error: status {}", code));
+    match command {
+        ForwardCmd::Status(args) => {
+            let enabled = forward_enabled_from_doc(&doc);
+            let targets = forward_targets_from_doc(&doc);
+            if args.json {
+                let output = serde_json::json!({
+                    "ok": true,
+                    "enabled": enabled,
+                    "targets": targets,
+                });
+                println!("{output}");
+                return Ok(());
             }
-            Err(err) => {
-                last_err = Some(format!("remote error: {err}"));
+            println!("forwarding: {}", if enabled { "on" } else { "off" });
+            if targets.is_empty() {
+                println!("targets: (none)");
+            } else {
+                println!("targets: {}", targets.join(", "));
             }
+            return Ok(());
+        }
+        ForwardCmd::Off => {
+            set_forward_enabled(&mut doc, false);
+        }
+        ForwardCmd::Toggle => {
+            let enabled = forward_enabled_from_doc(&doc);
+            if enabled {
+                set_forward_enabled(&mut doc, false);
+            } else {
+                let targets = forward_targets_from_doc(&doc);
+                if targets.is_empty() {
+                    return Err(NotifallError::RemoteForwardMissingHost(
+                        forward_missing_targets_message(&path),
+                    ));
+                }
+                set_forward_enabled(&mut doc, true);
+            }
+        }
+        ForwardCmd::On(args) => {
+            apply_forward_on(&mut doc, &path, &args)?;
         }
     }
-
-    Err(NotifallError::Provider(ProviderError::Message(
-        last_err.unwrap_or_else(|| "remote error".to_string()),
-    )))
-}
-
-fn to_ping_url(url: &str) -> String {
-    if url.ends_with("/notify") {
-        return url.trim_end_matches("/notify").to_string() + "/ping";
-    }
-    if url.ends_with('/') {
-        return format!("{url}ping");
+
+    let new_contents = doc.to_string();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
     }
-    format!("{url}/ping")
+    fs::write(&path, new_contents)?;
+    println!(
+        "forwarding {}",
+        if forward_enabled_from_doc(&doc) {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    );
+    Ok(())
 }
 
-fn resolve_remote_target(
-    cli_host: Option<&str>,
-    cli_port: Option<u16>,
-    cfg_host: Option<&str>,
-    cfg_port: Option<u16>,
-    cfg_url: Option<&str>,
-) -> Option<(String, String, u16)> {
-    if let Some(host) = cli_host {
-        let port = cli_port.or(cfg_port).unwrap_or(4280);
-        let url = format!("http://{host}:{port}/notify");
-        return Some((url, host.to_string(), port));
+fn handle_config_set(
+    config_path: Option<&PathBuf>,
+    args: ConfigSetArgs,
+) -> Result<(), NotifallError> {
+    let path = config_path.cloned().unwrap_or_else(default_config_path);
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+    let mut doc = toml_edit::DocumentMut::from_str(&existing)?;
+    let value = if args.add || args.remove {
+        let validate::Expect::Field(schema::Field {
+            kind: schema::Kind::List | schema::Kind::EnumList(_),
+            ..
+        }) = validate::expect_key(&args.key)
+        else {
+            return Err(NotifallError::ConfigKey(format!(
+                "--add and --remove only apply to lists such as forward.targets, not {}",
+                args.key
+            )));
+        };
+        let mut items = toml_lookup_edit(doc.as_item(), &args.key)
+            .and_then(|item| item.as_array())
+            .map(|array| {
+                array
+                    .iter()
+                    .filter_map(|v| v.as_str().map(str::to_string))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let present = items.contains(&args.value);
+        if args.add && !present {
+            items.push(args.value.clone());
+        } else if args.remove && present {
+            items.retain(|item| *item != args.value);
+        } else {
+            let state = if present { "already in" } else { "not in" };
+            println!("{} is {state} {}", args.value, args.key);
+            return Ok(());
+        }
+        let array = toml_edit::Array::from_iter(items);
+        validate::parse_value(&args.key, &toml_edit::Value::Array(array).to_string())
+            .map_err(NotifallError::ConfigKey)?
+    } else {
+        validate::parse_value(&args.key, &args.value).map_err(NotifallError::ConfigKey)?
+    };
+    set_toml_key(&mut doc, &args.key, value)?;
+    let new_contents = doc.to_string();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
     }
+    fs::write(&path, new_contents)?;
+    println!("set {} in {}", args.key, path.display());
+    Ok(())
+}
 
-    if let Some(host) = cfg_host {
-        let port = cli_port.or(cfg_port).unwrap_or(4280);
-        let url = format!("http://{host}:{port}/notify");
-        return Some((url, host.to_string(), port));
+fn handle_config_get(
+    config_path: Option<&PathBuf>,
+    args: ConfigKeyArgs,
+) -> Result<(), NotifallError> {
+    if let validate::Expect::Unknown = validate::expect_key(&args.key) {
+        return Err(NotifallError::ConfigKey(validate::unknown_key_message(
+            &args.key,
+        )));
     }
-
-    if let Some(url) = cfg_url {
-        if let Some((host, port)) = parse_remote_url(url) {
-            let port = cli_port.unwrap_or(port);
-            let url = format!("http://{host}:{port}/notify");
-            return Some((url, host, port));
+    let value = resolve_config(config_path)?
+        .and_then(|resolved| display_config_value(&resolved, &args.key))
+        .ok_or_else(|| NotifallError::ConfigKey(format!("{} is not set", args.key)))?;
+    match value {
+        toml::Value::String(text) => println!("{text}"),
+        // A secret reference, shown as written: `{ env = "TOKEN" }`.
+        toml::Value::Table(table) if secret::is_secret_key(&args.key) => {
+            let fields = table
+                .iter()
+                .map(|(kind, target)| format!("{kind} = {target}"))
+                .collect::<Vec<_>>();
+            println!("{{ {} }}", fields.join(", "));
         }
+        toml::Value::Table(table) => print!("{}", toml::to_string(&table)?),
+        other => println!("{other}"),
     }
-
-    None
+    Ok(())
 }
 
-fn parse_remote_url(url: &str) -> Option<(String, u16)> {
-    let trimmed = url
-        .strip_prefix("http://")
-        .or_else(|| url.strip_prefix("https://"))
-        .unwrap_or(url);
-    let host_port = trimmed.split('/').next().unwrap_or(trimmed);
-    if let Some((host, port)) = host_port.rsplit_once(':') {
-        if let Ok(port) = port.parse::<u16>() {
-            return Some((host.to_string(), port));
-        }
+fn handle_config_unset(
+    config_path: Option<&PathBuf>,
+    args: ConfigKeyArgs,
+) -> Result<(), NotifallError> {
+    if let validate::Expect::Unknown = validate::expect_key(&args.key) {
+        return Err(NotifallError::ConfigKey(validate::unknown_key_message(
+            &args.key,
+        )));
     }
-    None
+    let path = config_path.cloned().unwrap_or_else(default_config_path);
+    let existing = fs::read_to_string(&path).unwrap_or_default();
+    let mut doc = toml_edit::DocumentMut::from_str(&existing)?;
+    if !remove_toml_key(doc.as_item_mut(), &args.key) {
+        println!("{} is not set in {}", args.key, path.display());
+        return Ok(());
+    }
+    fs::write(&path, doc.to_string())?;
+    println!("unset {} in {}", args.key, path.display());
+    Ok(())
 }
 
-fn default_focus_command() -> Option<String> {
-    let exe = std::env::current_exe().ok()?;
-    Some(format!("{} focus", exe.display()))
+fn toml_lookup_edit<'a>(item: &'a toml_edit::Item, dotted: &str) -> Option<&'a toml_edit::Item> {
+    dotted
+        .split('.')
+        .try_fold(item, |item, part| item.as_table_like()?.get(part))
 }
 
-fn extract_token(headers: &[tiny_http::Header]) -> Option<String> {
-    for header in headers {
-        let name = header.field.as_str().to_string();
-        if name.eq_ignore_ascii_case("authorization") {
-            let value = header.value.as_str();
-            if let Some(token) = value.strip_prefix("Bearer ") {
-                return Some(token.to_string());
+// Remove `dotted`, then any tables it leaves empty. False if it wasn't set.
+fn remove_toml_key(item: &mut toml_edit::Item, dotted: &str) -> bool {
+    let Some(table) = item.as_table_like_mut() else {
+        return false;
+    };
+    match dotted.split_once('.') {
+        None => table.remove(dotted).is_some(),
+        Some((head, rest)) => {
+            let Some(child) = table.get_mut(head) else {
+                return false;
+            };
+            let removed = remove_toml_key(child, rest);
+            if removed && child.as_table_like().is_some_and(|t| t.is_empty()) {
+                table.remove(head);
             }
-        }
-        if name.eq_ignore_ascii_case("x-ding-token") {
-            return Some(header.value.as_str().to_string());
+            removed
         }
     }
-    None
 }
 
-fn json_response(status: u16, body: &str) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
-    let mut response = tiny_http::Response::from_string(body.to_string());
-    let header = tiny_http::Header::from_bytes("Content-Type", "application/json").ok();
-    if let Some(header) = header {
-        response.add_header(header);
-    }
-    response.with_status_code(status)
+fn handle_completions(args: CompletionsArgs) -> Result<(), NotifallError> {
+    let mut command = <Cli as clap::CommandFactory>::command();
+    clap_complete::generate(args.shell, &mut command, "ding", &mut std::io::stdout());
+    Ok(())
 }
 
-fn handle_wait_macos(args: crate::cli::WaitMacosArgs) -> Result<(), NotifallError> {
-    let contents = fs::read_to_string(&args.payload)?;
-    let payload: WaitPayload = serde_json::from_str(&contents)?;
-    let provider = MacosProvider::new(payload.macos)?;
-    let report = provider.send(&payload.notification, SendOptions { wait_for_click: true })?;
-    handle_click(
-        report.outcome,
-        payload.on_click.as_deref(),
-        &payload.notification,
-        payload.context.as_ref(),
-    )?;
-    let _ = fs::remove_file(&args.payload);
+fn handle_config_path(config_path: Option<&PathBuf>) -> Result<(), NotifallError> {
+    let path = config_path.cloned().unwrap_or_else(default_config_path);
+    println!("{}", path.display());
     Ok(())
 }
 
-fn load_config(path: Option<&PathBuf>) -> Result<Option<Config>, NotifallError> {
-    let path = path.cloned().unwrap_or_else(default_config_path);
-    if !path.exists() {
-        return Ok(None);
+fn handle_config_list(config_path: Option<&PathBuf>) -> Result<(), NotifallError> {
+    let path = config_path.cloned().unwrap_or_else(default_config_path);
+    if path.exists() {
+        let contents = fs::read_to_string(&path)?;
+        print!("{}", secret::redact_config_text(&contents));
+    } else {
+        println!("(no config file at {})", path.display());
     }
-    let contents = fs::read_to_string(&path)?;
-    let config: Config = toml::from_str(&contents)?;
-    Ok(Some(config))
-}
-
-fn resolve_source_config<'a>(
-    config: Option<&'a Config>,
-    source: Option<&str>,
-) -> Option<&'a SourceConfig> {
-    let source = source?;
-    if let Some(cfg) = config
-        .and_then(|c| c.sources.as_ref())
-        .and_then(|sources| sources.get(source))
-    {
-        return Some(cfg);
+    let resolved = match resolve_config(config_path) {
+        Ok(Some(resolved)) => resolved,
+        Ok(None) => return Ok(()),
+        Err(err) => {
+            eprintln!("warning: {}", secret::redact(&err.to_string()));
+            return Ok(());
+        }
+    };
+    let from_env = resolved
+        .origins
+        .iter()
+        .filter_map(|(key, origin)| match origin {
+            ConfigOrigin::Env(var) => Some((key, var, display_config_value(&resolved, key)?)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if !from_env.is_empty() {
+        println!();
+        println!("# Overridden by environment variables:");
+        for (key, var, value) in from_env {
+            println!("# {key} = {value}  ({var})");
+        }
     }
-    None
+    Ok(())
 }
 
-fn resolve_title(
-    cli_title: Option<String>,
-    source_config: Option<&SourceConfig>,
-    source: Option<&str>,
-) -> String {
-    if let Some(title) = cli_title {
-        return title;
-    }
-    if let Some(display) = source_config.and_then(|cfg| cfg.display_name.as_ref()) {
-        return display.clone();
+fn handle_config_resolve(config_path: Option<&PathBuf>) -> Result<(), NotifallError> {
+    let Some(resolved) = resolve_config(config_path)? else {
+        let path = config_path.cloned().unwrap_or_else(default_config_path);
+        println!("(no config file at {})", path.display());
+        return Ok(());
+    };
+    if let Some(profile) = resolved.profile.as_deref() {
+        println!("# profile: {profile}");
     }
-    if let Some(source) = source {
-        return title_from_source(source);
+    let lines = resolved
+        .origins
+        .iter()
+        .filter_map(|(key, origin)| {
+            let value = display_config_value(&resolved, key)?;
+            Some((format!("{key} = {value}"), origin))
+        })
+        .collect::<Vec<_>>();
+    let width = lines.iter().map(|(line, _)| line.len()).max().unwrap_or(0);
+    for (line, origin) in lines {
+        println!("{line:<width$}  # {origin}");
     }
-    "Ding".to_string()
+    Ok(())
 }
 
-fn title_from_source(source: &str) -> String {
-    let mut chars = source.chars();
-    match chars.next() {
-        Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
-        None => "Ding".to_string(),
+fn handle_config_validate(config_path: Option<&PathBuf>) -> Result<(), NotifallError> {
+    let issues = config_issues(config_path);
+    for issue in &issues {
+        println!("{}", secret::redact(issue));
+    }
+    if !issues.is_empty() {
+        return Err(NotifallError::ConfigInvalid(issues.len()));
     }
+    let path = config_path.cloned().unwrap_or_else(default_config_path);
+    println!("{}: ok", path.display());
+    Ok(())
 }
 
-fn resolve_icon(
-    cli_icon: Option<PathBuf>,
-    source_config: Option<&SourceConfig>,
-    source: Option<&str>,
-) -> Option<PathBuf> {
-    if cli_icon.is_some() {
-        return cli_icon;
-    }
-    source_config.and_then(|cfg| cfg.icon.clone())
-        .or_else(|| default_source_icon(source))
+// Problems in the global config and any project .ding.toml, then in the
+// merged result, as `path:line: message`.
+fn config_issues(config_path: Option<&PathBuf>) -> Vec<String> {
+    validated_config(config_path).err().unwrap_or_default()
 }
 
-fn resolve_macos_config(
-    config: Option<&Config>,
-    source_config: Option<&SourceConfig>,
-    source: Option<&str>,
-) -> Option<MacosConfig> {
-    let mut macos = config.and_then(|c| c.macos.clone());
-    if let Some(source_cfg) = source_config {
-        if source_cfg.app_bundle_id.is_some() {
-            let entry = macos.get_or_insert_with(MacosConfig::default);
-            entry.app_bundle_id = source_cfg.app_bundle_id.clone();
-        }
-    }
-    if macos.as_ref().and_then(|m| m.app_bundle_id.as_ref()).is_none() {
-        if let Some(bundle_id) = default_source_bundle_id(source) {
-            let entry = macos.get_or_insert_with(MacosConfig::default);
-            entry.app_bundle_id = Some(bundle_id);
-        }
+// The merged config with secrets resolved, or every problem `config_issues`
+// reports. Secrets are resolved once, so `{ command }` references run once.
+fn validated_config(config_path: Option<&PathBuf>) -> Result<Option<ResolvedConfig>, Vec<String>> {
+    let path = config_path.cloned().unwrap_or_else(default_config_path);
+    let global = fs::read_to_string(&path).unwrap_or_default();
+    let mut issues = validate::check_file(&global)
+        .iter()
+        .map(|issue| issue.display(&path))
+        .collect::<Vec<_>>();
+    let project = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| config::find_project_file(&cwd));
+    if let Some(project) = project {
+        let contents = fs::read_to_string(&project).unwrap_or_default();
+        issues.extend(
+            validate::check_project_file(&contents)
+                .iter()
+                .map(|issue| issue.display(&project)),
+        );
     }
-
-    // Fallback: if still no bundle id, create a default bundle so notifications work.
-    if macos.as_ref().and_then(|m| m.app_bundle_id.as_ref()).is_none() {
-        if let Some(bundle_id) = ensure_default_bundle() {
-            let entry = macos.get_or_insert_with(MacosConfig::default);
-            entry.app_bundle_id = Some(bundle_id);
+    if !issues.is_empty() {
+        return Err(issues);
+    }
+    match resolve_config(config_path) {
+        Ok(Some(resolved)) => {
+            issues.extend(
+                validate::check_config(&resolved, &global)
+                    .iter()
+                    .map(|issue| issue.display(&path)),
+            );
+            if issues.is_empty() {
+                Ok(Some(resolved))
+            } else {
+                Err(issues)
+            }
         }
+        Ok(None) => Ok(None),
+        Err(err) => Err(vec![format!("{}: {err}", path.display())]),
     }
-
-    macos
 }
 
-fn resolve_telegram_config(
-    config: Option<&Config>,
-    args: &SendArgs,
-) -> Result<TelegramConfig, NotifallError> {
-    let mut telegram = config
-        .and_then(|c| c.telegram.clone())
-        .unwrap_or_default();
-
-    if let Some(token) = args.telegram_token.as_ref() {
-        telegram.bot_token = Some(token.clone());
-    }
-    if let Some(chat_id) = args.telegram_chat_id.as_ref() {
-        telegram.chat_id = Some(chat_id.clone());
-    }
-    if let Some(parse_mode) = args.telegram_parse_mode.as_ref() {
-        telegram.parse_mode = Some(parse_mode.clone());
-    }
-    if args.telegram_silent {
-        telegram.silent = Some(true);
+// A resolved value for display, with plaintext secrets hidden.
+fn display_config_value(resolved: &ResolvedConfig, key: &str) -> Option<toml::Value> {
+    let value = config::lookup(&resolved.values, key)?;
+    if secret::is_secret_key(key) && value.is_str() {
+        return Some(toml::Value::String(secret::REDACTED.to_string()));
     }
+    Some(value.clone())
+}
 
-    Ok(telegram)
+fn remote_url_from_doc(doc: &toml_edit::DocumentMut) -> Option<String> {
+    doc.get("remote")
+        .and_then(|v| v.get("url"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
 }
 
-fn default_source_icon(source: Option<&str>) -> Option<PathBuf> {
-    let _ = source?;
-    None
+fn remote_host_from_doc(doc: &toml_edit::DocumentMut) -> Option<String> {
+    doc.get("remote")
+        .and_then(|v| v.get("host"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .or_else(|| remote_url_from_doc(doc).and_then(|url| parse_remote_url(&url).map(|t| t.0)))
 }
 
-fn default_source_bundle_id(source: Option<&str>) -> Option<String> {
-    let source = source?;
-    if source == "claude" {
-        return ensure_source_bundle(
+fn remote_port_from_doc(doc: &toml_edit::DocumentMut) -> Option<u16> {
+    doc.get("remote")
+        .and_then(|v| v.get("port"))
+        .and_then(|v| v.as_integer())
+        .and_then(|v| u16::try_from(v).ok())
+        .or_else(|| remote_url_from_doc(doc).and_then(|url| parse_remote_url(&url).map(|t| t.1)))
+}
+
+fn forward_enabled_from_doc(doc: &toml_edit::DocumentMut) -> bool {
+    doc.get("forward")
+        .and_then(|v| v.get("enabled"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+fn forward_targets_from_doc(doc: &toml_edit::DocumentMut) -> Vec<String> {
+    doc.get("forward")
+        .and_then(|v| v.get("targets"))
+        .and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|item| item.as_str().map(|s| s.to_string()))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default()
+}
+
+fn set_forward_enabled(doc: &mut toml_edit::DocumentMut, enabled: bool) {
+    let table = doc.entry("forward").or_insert(toml_edit::table());
+    if let Some(table) = table.as_table_mut() {
+        table["enabled"] = toml_edit::Item::Value(toml_edit::Value::from(enabled));
+    }
+}
+
+fn set_forward_targets(doc: &mut toml_edit::DocumentMut, targets: &[String]) {
+    let table = doc.entry("forward").or_insert(toml_edit::table());
+    if let Some(table) = table.as_table_mut() {
+        let mut arr = toml_edit::Array::new();
+        for target in targets {
+            arr.push(target.as_str());
+        }
+        table["targets"] = toml_edit::Item::Value(toml_edit::Value::Array(arr));
+    }
+}
+
+fn apply_forward_on(
+    doc: &mut toml_edit::DocumentMut,
+    path: &PathBuf,
+    args: &ForwardOnArgs,
+) -> Result<(), NotifallError> {
+    let mut targets = forward_targets_from_doc(doc);
+    let provided = args
+        .targets
+        .iter()
+        .map(|t| match t {
+            ForwardTarget::Remote => "remote".to_string(),
+            ForwardTarget::Telegram => "telegram".to_string(),
+        })
+        .collect::<Vec<_>>();
+
+    if args.append {
+        for target in provided {
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+    } else if !provided.is_empty() {
+        targets = provided;
+    }
+
+    if targets.is_empty() {
+        return Err(NotifallError::RemoteForwardMissingHost(
+            forward_missing_targets_message(path),
+        ));
+    }
+
+    if targets.iter().any(|t| t == "remote") {
+        if let Some(host) = args.host.as_deref() {
+            set_remote_field(doc, "host", toml_edit::Value::from(host));
+        }
+        if let Some(port) = args.port {
+            set_remote_field(doc, "port", toml_edit::Value::from(port as i64));
+        }
+        let has_socket = doc
+            .get("remote")
+            .and_then(|v| v.get("socket"))
+            .and_then(|v| v.as_str())
+            .is_some();
+        if remote_host_from_doc(doc).is_none() && !has_socket {
+            return Err(NotifallError::RemoteForwardMissingHost(
+                forward_missing_remote_message(path),
+            ));
+        }
+        if !has_socket && remote_port_from_doc(doc).is_none() {
+            set_remote_field(doc, "port", toml_edit::Value::from(4280i64));
+        }
+    }
+
+    set_forward_targets(doc, &targets);
+    set_forward_enabled(doc, true);
+    Ok(())
+}
+
+fn forward_missing_targets_message(path: &PathBuf) -> String {
+    format!(
+        "Forwarding needs at least one target.\n\n\
+Set it with:\n  ding forward on remote --host mba --port 4280\n\
+or:\n  ding forward on telegram\n\n\
+Config file: {}\n\
+If missing, run: ding config init",
+        path.display()
+    )
+}
+
+fn forward_missing_remote_message(path: &PathBuf) -> String {
+    format!(
+        "Forwarding to remote needs a host.\n\n\
+Set it with:\n  ding forward on remote --host mba --port 4280\n\
+or:\n  ding config set remote.host mba\n  ding config set remote.port 4280\n\n\
+Config file: {}\n\
+If missing, run: ding config init",
+        path.display()
+    )
+}
+
+fn set_remote_field(doc: &mut toml_edit::DocumentMut, key: &str, value: toml_edit::Value) {
+    let table = doc.entry("remote").or_insert(toml_edit::table());
+    if let Some(table) = table.as_table_mut() {
+        table[key] = toml_edit::Item::Value(value);
+    }
+}
+
+fn set_toml_key(
+    doc: &mut toml_edit::DocumentMut,
+    key: &str,
+    value: toml_edit::Value,
+) -> Result<(), NotifallError> {
+    let parts: Vec<&str> = key.split('.').collect();
+    if parts.is_empty() {
+        return Ok(());
+    }
+    let mut table = doc.as_table_mut();
+    for part in &parts[..parts.len().saturating_sub(1)] {
+        if !table.contains_key(part) {
+            table[part] = toml_edit::Item::Table(toml_edit::Table::new());
+        }
+        if !table[part].is_table() {
+            table[part] = toml_edit::Item::Table(toml_edit::Table::new());
+        }
+        if let Some(next) = table[part].as_table_mut() {
+            table = next;
+        } else {
+            return Err(NotifallError::Provider(ProviderError::Message(
+                "invalid config path".to_string(),
+            )));
+        }
+    }
+    let last = parts[parts.len() - 1];
+    table[last] = toml_edit::Item::Value(value);
+    Ok(())
+}
+
+fn handle_remote_send(
+    config: Option<&Config>,
+    args: &SendArgs,
+    notification: Notification,
+    mut remote_notification: Notification,
+    origin: RemoteContext,
+    context: Option<Context>,
+) -> Result<(), NotifallError> {
+    let remote_cfg = config.and_then(|c| c.remote.clone()).unwrap_or_default();
+    let token = match args.remote_token.clone() {
+        Some(token) => Some(token),
+        None => remote_cfg.token()?,
+    };
+    let target = resolve_remote_target(
+        args.remote_host.as_deref(),
+        args.remote_port,
+        &remote_cfg,
+        token.is_some(),
+    );
+    let timeout_ms = args
+        .remote_timeout_ms
+        .or(remote_cfg.timeout_ms)
+        .unwrap_or(2000);
+    let retries = args.remote_retries.or(remote_cfg.retries).unwrap_or(2);
+    let fallback = !args.no_fallback && remote_cfg.fallback_to_local.unwrap_or(true);
+
+    // Background sends are fire-and-forget; only a blocking send waits for
+    // the listener to report the click back.
+    let wait_for_click = args.wait_for_click && !args.background;
+    if args.batch && wait_for_click {
+        return Err(NotifallError::BatchWithWait);
+    }
+    // A `!command` button runs here once the click is reported back, so
+    // without waiting for it the button would do nothing.
+    if !wait_for_click
+        && remote_notification
+            .actions
+            .iter()
+            .any(|a| a.command.is_some())
+    {
+        logging::warn(
+            "remote",
+            "dropping !command buttons: they only work with --wait-for-click",
+        );
+        remote_notification
+            .actions
+            .retain(|action| action.command.is_none());
+    }
+    let batch_window_ms = if (args.batch || remote_cfg.batch.unwrap_or(false)) && !wait_for_click {
+        Some(remote_cfg.batch_window_ms.unwrap_or(500))
+    } else {
+        None
+    };
+    let envelope = RemoteEnvelope {
+        notification: remote_notification,
+        context: Some(origin),
+        wait_for_click,
+        callback_url: args.remote_callback.clone(),
+    };
+
+    let send_result = match target {
+        Ok(Some((url, _host, _port))) => send_remote_request(
+            &url,
+            token.as_deref(),
+            timeout_ms,
+            retries,
+            batch_window_ms.map(|window_ms| (window_ms, fallback)),
+            &envelope,
+        )
+        .map(|id| (url, id)),
+        Ok(None) => Err(NotifallError::Provider(ProviderError::NotConfigured(
+            "remote host is not configured".to_string(),
+        ))),
+        Err(err) => Err(err),
+    };
+    let err = match send_result {
+        Ok((url, id)) => {
+            let outcome = match id.as_deref() {
+                Some(id) if wait_for_click => {
+                    let outcome = wait_remote_outcome(&url, token.as_deref(), timeout_ms, id)?;
+                    // The listener ran its own on_click; ours runs here, on the origin host.
+                    handle_click(
+                        outcome.clone(),
+                        args.on_click.as_deref(),
+                        &notification,
+                        context.as_ref(),
+                        ClickSide::Origin,
+                    )?;
+                    outcome
+                }
+                None if wait_for_click => {
+                    logging::warn(
+                        "remote",
+                        "remote listener returned no id; cannot wait for click",
+                    );
+                    None
+                }
+                _ => None,
+            };
+            if args.json {
+                SendOutput {
+                    id,
+                    ..SendOutput::new("remote", outcome.as_ref())
+                }
+                .print()?;
+            }
+            return ensure_not_dismissed(outcome.as_ref(), wait_for_click);
+        }
+        Err(err) => err,
+    };
+
+    if fallback && cfg!(target_os = "macos") {
+        let source = notification.source.clone();
+        let source_config = resolve_source_config(config, source.as_deref());
+        let macos_config = resolve_macos_config(config, source_config, source.as_deref());
+        return deliver_macos(
+            notification,
+            macos_config,
+            args.on_click.clone(),
+            args.background,
+            args.wait_for_click,
+            args.json,
+            context,
+        );
+    }
+
+    Err(err)
+}
+
+fn handle_forward_send(
+    config: Option<&Config>,
+    args: &SendArgs,
+    notification: Notification,
+    remote_notification: Notification,
+    origin: RemoteContext,
+    local: Option<Context>,
+) -> Result<(), NotifallError> {
+    let targets = config
+        .and_then(|c| c.forward.as_ref())
+        .and_then(|f| f.targets.clone())
+        .unwrap_or_default();
+
+    if targets.is_empty() {
+        return Err(NotifallError::Provider(ProviderError::NotConfigured(
+            "forward targets are not configured".to_string(),
+        )));
+    }
+
+    // Each target reports on its own in the combined output below.
+    let mut target_args = args.clone();
+    target_args.json = false;
+    let total = targets.len();
+    let mut results = Vec::new();
+    let mut errors = Vec::new();
+
+    for target in targets {
+        let result = match target.as_str() {
+            "remote" => handle_remote_send(
+                config,
+                &target_args,
+                notification.clone(),
+                remote_notification.clone(),
+                origin.clone(),
+                local.clone(),
+            ),
+            "telegram" => resolve_telegram_config(config, args)
+                .and_then(|telegram_config| Ok(TelegramProvider::new(telegram_config)?))
+                .and_then(|provider| {
+                    provider.send(
+                        &notification,
+                        SendOptions {
+                            wait_for_click: false,
+                        },
+                    )?;
+                    Ok(())
+                }),
+            other => Err(NotifallError::ProviderUnsupported(format!(
+                "unknown forward target: {other}"
+            ))),
+        };
+        results.push(match result {
+            Ok(()) => ForwardResult {
+                provider: target,
+                ok: true,
+                exit_code: 0,
+                error: None,
+            },
+            Err(err) => {
+                let result = ForwardResult {
+                    provider: target,
+                    ok: false,
+                    exit_code: err.exit_code(),
+                    error: Some(err.to_string()),
+                };
+                errors.push(err);
+                result
+            }
+        });
+    }
+
+    let failed = results.iter().filter(|r| !r.ok).count();
+    if args.json {
+        SendOutput {
+            ok: failed == 0,
+            results,
+            ..SendOutput::new("forward", None)
+        }
+        .print()?;
+    } else {
+        for result in results.iter().filter(|r| !r.ok) {
+            let error = result.error.as_deref().unwrap_or_default();
+            eprintln!(
+                "forwarding to {}: {}",
+                result.provider,
+                secret::redact(error)
+            );
+        }
+    }
+
+    if failed == total {
+        // Each target's error was printed above; when they all failed the
+        // same way, exit the way a single target would.
+        let message = "forwarding failed for all targets".to_string();
+        let class = errors[0].class();
+        let error = match class {
+            _ if errors.iter().any(|err| err.class() != class) => ProviderError::Message(message),
+            "config" => ProviderError::NotConfigured(message),
+            "unavailable" => ProviderError::Unreachable(message),
+            "timeout" => ProviderError::Timeout(message),
+            _ => ProviderError::Message(message),
+        };
+        return Err(NotifallError::Provider(error));
+    }
+    if failed > 0 {
+        return Err(NotifallError::ForwardPartial { failed, total });
+    }
+    Ok(())
+}
+
+fn send_remote_request(
+    url: &str,
+    token: Option<&str>,
+    timeout_ms: u64,
+    retries: u32,
+    // The batch window, and whether a failed batch falls back to local delivery.
+    batch: Option<(u64, bool)>,
+    envelope: &RemoteEnvelope,
+) -> Result<Option<String>, NotifallError> {
+    if let Some((window_ms, fallback_to_local)) = batch {
+        enqueue_remote_envelope(
+            QueuedEnvelope {
+                url: url.to_string(),
+                token: token.map(|t| t.to_string()),
+                timeout_ms,
+                retries,
+                fallback_to_local,
+                attempts: 0,
+                envelope: envelope.clone(),
+            },
+            window_ms,
+        )?;
+        return Ok(None);
+    }
+
+    let body = serde_json::to_value(envelope)?;
+    // Older listeners reply without an id; that only matters when the caller
+    // wants to wait for the outcome.
+    let response = post_remote_json(url, token, timeout_ms, retries, &body)?;
+    Ok(response.and_then(|v| {
+        v.get("id")
+            .and_then(|id| id.as_str())
+            .map(|s| s.to_string())
+    }))
+}
+
+fn post_remote_json(
+    url: &str,
+    token: Option<&str>,
+    timeout_ms: u64,
+    retries: u32,
+    body: &serde_json::Value,
+) -> Result<Option<serde_json::Value>, NotifallError> {
+    logging::debug(
+        "remote",
+        &format!("remote_url={url} timeout_ms={timeout_ms} retries={retries}"),
+    );
+    let timeout = Duration::from_millis(timeout_ms);
+    let mut last_err = None;
+    let mut failure = transport::Failure::Other;
+
+    for _ in 0..=retries {
+        match transport::request("POST", url, token, Some(body), timeout, Some(timeout)) {
+            Ok(response) => {
+                if response.is_success() {
+                    return Ok(response.into_json().ok());
+                }
+                failure = transport::Failure::Other;
+                last_err = Some(format!("remote error: status {}", response.status));
+            }
+            Err(err) => {
+                failure = err.failure;
+                last_err = Some(format!("remote error: {err}"));
+            }
+        }
+    }
+
+    Err(NotifallError::Provider(transport_provider_error(
+        failure,
+        last_err.unwrap_or_else(|| "remote error".to_string()),
+    )))
+}
+
+fn remote_queue_path() -> PathBuf {
+    cache_dir().join("ding/queue/remote.jsonl")
+}
+
+// Append to the shared queue. The sender that finds the queue empty spawns
+// the flusher, which sends everything queued during the batch window.
+fn enqueue_remote_envelope(entry: QueuedEnvelope, window_ms: u64) -> Result<(), NotifallError> {
+    use std::io::Write;
+
+    let path = remote_queue_path();
+    let line = serde_json::to_string(&entry)?;
+    let mut file = open_remote_queue(&path)?;
+    lock_file(&file)?;
+    let first = file.metadata()?.len() == 0;
+    writeln!(file, "{line}")?;
+    drop(file);
+
+    if first {
+        let exe = std::env::current_exe()?;
+        let mut cmd = Command::new(exe);
+        cmd.arg("flush-remote-queue")
+            .arg("--window-ms")
+            .arg(window_ms.to_string());
+        spawn_detached(cmd)?;
+    }
+    logging::debug("remote", &format!("queued remote envelope (first={first})"));
+    Ok(())
+}
+
+// Entries carry the listener token, so only the user may read the queue.
+fn open_remote_queue(path: &std::path::Path) -> Result<fs::File, NotifallError> {
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+            fs::set_permissions(parent, fs::Permissions::from_mode(0o700))?;
+        }
+        options.mode(0o600);
+        let file = options.open(path)?;
+        // Queues written before the mode was set keep theirs otherwise.
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        Ok(file)
+    }
+    #[cfg(not(unix))]
+    {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(options.open(path)?)
+    }
+}
+
+// Flushes a queued notification may fail before it is dropped.
+const QUEUE_MAX_ATTEMPTS: u32 = 3;
+
+fn handle_flush_remote_queue(
+    args: FlushRemoteQueueArgs,
+    config_path: Option<&PathBuf>,
+) -> Result<(), NotifallError> {
+    use std::io::Read;
+
+    std::thread::sleep(Duration::from_millis(args.window_ms));
+
+    let path = remote_queue_path();
+    let mut contents = String::new();
+    {
+        let mut file = OpenOptions::new().read(true).write(true).open(&path)?;
+        lock_file(&file)?;
+        file.read_to_string(&mut contents)?;
+        file.set_len(0)?;
+    }
+
+    // Group by destination, keeping the order envelopes were queued in.
+    let mut groups: Vec<Vec<QueuedEnvelope>> = Vec::new();
+    for line in contents.lines().filter(|l| !l.trim().is_empty()) {
+        let entry: QueuedEnvelope = match serde_json::from_str(line) {
+            Ok(entry) => entry,
+            Err(err) => {
+                logging::warn("remote", &format!("skipping invalid queue entry: {err}"));
+                continue;
+            }
+        };
+        match groups
+            .iter_mut()
+            .find(|group| group[0].url == entry.url && group[0].token == entry.token)
+        {
+            Some(group) => group.push(entry),
+            None => groups.push(vec![entry]),
+        }
+    }
+
+    for group in groups {
+        let head = &group[0];
+        let url = listener_endpoint(&head.url, "notify/batch");
+        let envelopes = group
+            .iter()
+            .map(|entry| &entry.envelope)
+            .collect::<Vec<_>>();
+        let body = serde_json::to_value(&envelopes)?;
+        let Err(err) = post_remote_json(
+            &url,
+            head.token.as_deref(),
+            head.timeout_ms,
+            head.retries,
+            &body,
+        ) else {
+            continue;
+        };
+        logging::warn(
+            "remote",
+            &format!(
+                "sending {} queued notification(s) to {url} failed: {err}",
+                group.len()
+            ),
+        );
+        for mut entry in group {
+            if entry.fallback_to_local && cfg!(target_os = "macos") {
+                deliver_queued_locally(config_path, entry.envelope.notification);
+                continue;
+            }
+            entry.attempts += 1;
+            if entry.attempts >= QUEUE_MAX_ATTEMPTS {
+                logging::warn(
+                    "remote",
+                    &format!(
+                        "dropping queued notification after {} attempts: {}",
+                        entry.attempts, entry.envelope.notification.title
+                    ),
+                );
+                continue;
+            }
+            let window_ms = args.window_ms * 2u64.pow(entry.attempts);
+            if let Err(err) = enqueue_remote_envelope(entry, window_ms) {
+                logging::warn("remote", &format!("could not re-queue notification: {err}"));
+            }
+        }
+    }
+    Ok(())
+}
+
+// `remote.fallback_to_local` for a batch the listener did not take. Actions
+// are left out, since nothing waits for the click.
+fn deliver_queued_locally(config_path: Option<&PathBuf>, mut notification: Notification) {
+    notification.actions.clear();
+    let config = load_config(config_path).ok().flatten();
+    let source = notification.source.clone();
+    let source_config = resolve_source_config(config.as_ref(), source.as_deref());
+    let macos_config = resolve_macos_config(config.as_ref(), source_config, source.as_deref());
+    if let Err(err) = deliver_macos(notification, macos_config, None, false, false, false, None) {
+        logging::warn(
+            "remote",
+            &format!("local fallback for a queued notification failed: {err}"),
+        );
+    }
+}
+
+fn lock_file(file: &fs::File) -> Result<(), NotifallError> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::io::AsRawFd;
+        // Released when the file is closed.
+        if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+            return Err(NotifallError::Io(std::io::Error::last_os_error()));
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = file;
+    Ok(())
+}
+
+// The provider error for a request that got no usable response.
+fn transport_provider_error(failure: transport::Failure, message: String) -> ProviderError {
+    match failure {
+        transport::Failure::Unreachable => ProviderError::Unreachable(message),
+        transport::Failure::Timeout => ProviderError::Timeout(message),
+        transport::Failure::Other => ProviderError::Message(message),
+    }
+}
+
+fn wait_remote_outcome(
+    url: &str,
+    token: Option<&str>,
+    timeout_ms: u64,
+    id: &str,
+) -> Result<Option<DeliveryOutcome>, NotifallError> {
+    let status_url = listener_endpoint(url, &format!("status/{id}"));
+    let deadline = Instant::now() + Duration::from_secs(REMOTE_CLICK_TIMEOUT_SECS);
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(NotifallError::Provider(ProviderError::Timeout(format!(
+                "no click reported within {REMOTE_CLICK_TIMEOUT_SECS}s"
+            ))));
+        }
+        let wait = remaining.as_secs().clamp(1, STATUS_POLL_SECS);
+        logging::debug(
+            "remote",
+            &format!("remote status_url={status_url} wait={wait}"),
+        );
+        let result = transport::request(
+            "GET",
+            &format!("{status_url}?wait={wait}"),
+            token,
+            None,
+            Duration::from_millis(timeout_ms),
+            Some(Duration::from_secs(wait) + Duration::from_millis(timeout_ms)),
+        )
+        .and_then(|response| {
+            if response.is_success() {
+                response.into_json()
+            } else {
+                Err(format!("status {}", response.status).into())
+            }
+        });
+        let value = match result {
+            Ok(value) => value,
+            Err(err) => {
+                return Err(NotifallError::Provider(transport_provider_error(
+                    err.failure,
+                    format!("remote status failed: {err}"),
+                )));
+            }
+        };
+        match value.get("status").and_then(|v| v.as_str()) {
+            Some("done") => {
+                return Ok(value.get("outcome").and_then(DeliveryOutcome::from_json));
+            }
+            Some("error") => {
+                let err = value
+                    .get("error")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("remote delivery failed");
+                return Err(NotifallError::Provider(ProviderError::Message(
+                    err.to_string(),
+                )));
+            }
+            _ => continue,
+        }
+    }
+}
+
+fn to_ping_url(url: &str) -> String {
+    listener_endpoint(url, "ping")
+}
+
+fn listener_endpoint(url: &str, path: &str) -> String {
+    if url.ends_with("/notify") {
+        return format!("{}/{path}", url.trim_end_matches("/notify"));
+    }
+    if url.ends_with('/') {
+        return format!("{url}{path}");
+    }
+    format!("{url}/{path}")
+}
+
+// `sends_token` says whether the caller will send a token to the target.
+fn resolve_remote_target(
+    cli_host: Option<&str>,
+    cli_port: Option<u16>,
+    remote_cfg: &RemoteConfig,
+    sends_token: bool,
+) -> Result<Option<(String, String, u16)>, NotifallError> {
+    // An explicit --remote-host wins over a configured socket.
+    if let (None, Some(socket)) = (cli_host, remote_cfg.socket.as_deref()) {
+        let socket = expand_home(socket).display().to_string();
+        let url = transport::unix_url(&socket, "/notify");
+        return Ok(Some((url, socket, 0)));
+    }
+
+    if let Some(host) = cli_host.or(remote_cfg.host.as_deref()) {
+        if host.eq_ignore_ascii_case("auto") {
+            return discover_remote_target(cli_port, remote_cfg, sends_token).map(Some);
+        }
+        let port = cli_port.or(remote_cfg.port).unwrap_or(4280);
+        let url = format!("http://{host}:{port}/notify");
+        return Ok(Some((url, host.to_string(), port)));
+    }
+
+    if let Some(url) = remote_cfg.url.as_deref() {
+        if let Some((host, port)) = parse_remote_url(url) {
+            let port = cli_port.unwrap_or(port);
+            let url = format!("http://{host}:{port}/notify");
+            return Ok(Some((url, host, port)));
+        }
+    }
+
+    Ok(None)
+}
+
+// `remote.host = "auto"`: find the listener over mDNS at send time.
+fn discover_remote_target(
+    cli_port: Option<u16>,
+    remote_cfg: &RemoteConfig,
+    sends_token: bool,
+) -> Result<(String, String, u16), NotifallError> {
+    let name = remote_cfg.listener_name.as_deref();
+    // Anything on the network can answer mDNS, so the token only goes to a
+    // listener picked by name.
+    if sends_token && name.is_none() {
+        return Err(NotifallError::Provider(ProviderError::NotConfigured(
+            "remote.host = \"auto\" with a token needs remote.listener_name, so the token is not sent to whichever listener answers first".to_string(),
+        )));
+    }
+    let timeout = Duration::from_millis(remote_cfg.timeout_ms.unwrap_or(2000));
+    let listener = discovery::resolve(name, timeout)
+        .map_err(|err| {
+            NotifallError::Provider(ProviderError::Message(format!(
+                "mDNS discovery failed: {err}"
+            )))
+        })?
+        .ok_or_else(|| {
+            let wanted = name.map(|n| format!(" named \"{n}\"")).unwrap_or_default();
+            NotifallError::Provider(ProviderError::Message(format!(
+                "no ding listener{wanted} found on the local network"
+            )))
+        })?;
+    let host = match listener.address() {
+        Some(IpAddr::V6(ip)) => format!("[{ip}]"),
+        Some(ip) => ip.to_string(),
+        None => listener.hostname.clone(),
+    };
+    let port = cli_port.unwrap_or(listener.port);
+    logging::debug(
+        "discovery",
+        &format!("discovered listener {} at {host}:{port}", listener.name),
+    );
+    let url = format!("http://{host}:{port}/notify");
+    Ok((url, host, port))
+}
+
+fn parse_remote_url(url: &str) -> Option<(String, u16)> {
+    let trimmed = url
+        .strip_prefix("http://")
+        .or_else(|| url.strip_prefix("https://"))
+        .unwrap_or(url);
+    let host_port = trimmed.split('/').next().unwrap_or(trimmed);
+    if let Some((host, port)) = host_port.rsplit_once(':') {
+        if let Ok(port) = port.parse::<u16>() {
+            return Some((host.to_string(), port));
+        }
+    }
+    None
+}
+
+fn default_focus_command() -> Option<String> {
+    let exe = std::env::current_exe().ok()?;
+    Some(format!("{} focus", exe.display()))
+}
+
+fn authorize_request(
+    request: &tiny_http::Request,
+    allow_hosts: &[String],
+    require_token: bool,
+    token: Option<&str>,
+    allow_query_token: bool,
+) -> Option<tiny_http::Response<std::io::Cursor<Vec<u8>>>> {
+    if !allow_hosts.is_empty() {
+        if let Some(remote) = request.remote_addr() {
+            let host = remote.ip().to_string();
+            if !allow_hosts.iter().any(|allowed| allowed == &host) {
+                return Some(json_response(403, r#"{"error":"forbidden"}"#));
+            }
+        }
+    }
+
+    if require_token {
+        // Browser EventSource clients cannot set headers, so `GET /events`
+        // also accepts `?token=`. Elsewhere it would only end up in logs.
+        let incoming = extract_token(request.headers()).or_else(|| {
+            allow_query_token
+                .then(|| query_param(request.url(), "token"))
+                .flatten()
+        });
+        if token != incoming.as_deref() {
+            return Some(json_response(401, r#"{"error":"unauthorized"}"#));
+        }
+    }
+
+    None
+}
+
+fn query_param(url: &str, key: &str) -> Option<String> {
+    let (_, query) = url.split_once('?')?;
+    query.split('&').find_map(|pair| {
+        let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+        (percent_decode(name)? == key)
+            .then(|| percent_decode(value))
+            .flatten()
+    })
+}
+
+// `a%2Bb+c` -> `a+b c`; `None` when an escape is malformed or not UTF-8.
+fn percent_decode(text: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        match byte {
+            b'%' => {
+                let hex = rest
+                    .get(..2)
+                    .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))?;
+                bytes.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+                rest = &rest[2..];
+            }
+            b'+' => bytes.push(b' '),
+            _ => bytes.push(byte),
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn extract_token(headers: &[tiny_http::Header]) -> Option<String> {
+    for header in headers {
+        let name = header.field.as_str().to_string();
+        if name.eq_ignore_ascii_case("authorization") {
+            let value = header.value.as_str();
+            if let Some(token) = value.strip_prefix("Bearer ") {
+                return Some(token.to_string());
+            }
+        }
+        if name.eq_ignore_ascii_case("x-ding-token") {
+            return Some(header.value.as_str().to_string());
+        }
+    }
+    None
+}
+
+fn json_response(status: u16, body: &str) -> tiny_http::Response<std::io::Cursor<Vec<u8>>> {
+    let mut response = tiny_http::Response::from_string(body.to_string());
+    let header = tiny_http::Header::from_bytes("Content-Type", "application/json").ok();
+    if let Some(header) = header {
+        response.add_header(header);
+    }
+    response.with_status_code(status)
+}
+
+fn handle_wait_macos(args: crate::cli::WaitMacosArgs) -> Result<(), NotifallError> {
+    let contents = fs::read_to_string(&args.payload)?;
+    let payload: WaitPayload = serde_json::from_str(&contents)?;
+    let provider = MacosProvider::new(payload.macos)?;
+    let report = provider.send(
+        &payload.notification,
+        SendOptions {
+            wait_for_click: true,
+        },
+    )?;
+    handle_click(
+        report.outcome,
+        payload.on_click.as_deref(),
+        &payload.notification,
+        payload.context.as_ref(),
+        ClickSide::Local,
+    )?;
+    let _ = fs::remove_file(&args.payload);
+    Ok(())
+}
+
+// Profile chosen with --profile; DING_PROFILE applies when it is unset.
+static PROFILE: OnceLock<String> = OnceLock::new();
+
+fn selected_profile() -> Option<String> {
+    PROFILE
+        .get()
+        .cloned()
+        .or_else(|| std::env::var("DING_PROFILE").ok().filter(|p| !p.is_empty()))
+}
+
+fn resolve_config(path: Option<&PathBuf>) -> Result<Option<ResolvedConfig>, NotifallError> {
+    let path = path.cloned().unwrap_or_else(default_config_path);
+    let cwd = std::env::current_dir().ok();
+    ResolvedConfig::load(&path, selected_profile().as_deref(), cwd.as_deref())
+}
+
+fn load_config(path: Option<&PathBuf>) -> Result<Option<Config>, NotifallError> {
+    Ok(resolve_config(path)?.map(|resolved| resolved.config))
+}
+
+fn resolve_source_config<'a>(
+    config: Option<&'a Config>,
+    source: Option<&str>,
+) -> Option<&'a SourceConfig> {
+    let source = source?;
+    if let Some(cfg) = config
+        .and_then(|c| c.sources.as_ref())
+        .and_then(|sources| sources.get(source))
+    {
+        return Some(cfg);
+    }
+    None
+}
+
+fn resolve_title(
+    cli_title: Option<String>,
+    source_config: Option<&SourceConfig>,
+    source: Option<&str>,
+) -> String {
+    if let Some(title) = cli_title {
+        return title;
+    }
+    if let Some(display) = source_config.and_then(|cfg| cfg.display_name.as_ref()) {
+        return display.clone();
+    }
+    if let Some(source) = source {
+        return title_from_source(source);
+    }
+    "Ding".to_string()
+}
+
+fn title_from_source(source: &str) -> String {
+    let mut chars = source.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+        None => "Ding".to_string(),
+    }
+}
+
+fn resolve_icon(
+    cli_icon: Option<PathBuf>,
+    source_config: Option<&SourceConfig>,
+    source: Option<&str>,
+) -> Option<PathBuf> {
+    if cli_icon.is_some() {
+        return cli_icon;
+    }
+    source_config
+        .and_then(|cfg| cfg.icon.clone())
+        .or_else(|| default_source_icon(source))
+}
+
+fn resolve_macos_config(
+    config: Option<&Config>,
+    source_config: Option<&SourceConfig>,
+    source: Option<&str>,
+) -> Option<MacosConfig> {
+    let mut macos = config.and_then(|c| c.macos.clone());
+    if let Some(source_cfg) = source_config {
+        if source_cfg.app_bundle_id.is_some() {
+            let entry = macos.get_or_insert_with(MacosConfig::default);
+            entry.app_bundle_id = source_cfg.app_bundle_id.clone();
+        }
+    }
+    if macos
+        .as_ref()
+        .and_then(|m| m.app_bundle_id.as_ref())
+        .is_none()
+    {
+        if let Some(bundle_id) = default_source_bundle_id(source) {
+            let entry = macos.get_or_insert_with(MacosConfig::default);
+            entry.app_bundle_id = Some(bundle_id);
+        }
+    }
+
+    // Fallback: if still no bundle id, create a default bundle so notifications work.
+    if macos
+        .as_ref()
+        .and_then(|m| m.app_bundle_id.as_ref())
+        .is_none()
+    {
+        if let Some(bundle_id) = ensure_default_bundle() {
+            let entry = macos.get_or_insert_with(MacosConfig::default);
+            entry.app_bundle_id = Some(bundle_id);
+        }
+    }
+
+    macos
+}
+
+fn resolve_telegram_config(
+    config: Option<&Config>,
+    args: &SendArgs,
+) -> Result<TelegramConfig, NotifallError> {
+    let mut telegram = config.and_then(|c| c.telegram.clone()).unwrap_or_default();
+
+    if let Some(token) = args.telegram_token.as_ref() {
+        secret::register(token);
+        telegram.bot_token = Some(token.clone().into());
+    }
+    if let Some(chat_id) = args.telegram_chat_id.as_ref() {
+        telegram.chat_id = Some(chat_id.clone());
+    }
+    if let Some(parse_mode) = args.telegram_parse_mode.as_ref() {
+        telegram.parse_mode = Some(parse_mode.clone());
+    }
+    if args.telegram_silent {
+        telegram.silent = Some(true);
+    }
+
+    Ok(telegram)
+}
+
+fn default_source_icon(source: Option<&str>) -> Option<PathBuf> {
+    let _ = source?;
+    None
+}
+
+fn default_source_bundle_id(source: Option<&str>) -> Option<String> {
+    let source = source?;
+    if source == "claude" {
+        return ensure_source_bundle(
             "claude",
             "Ding Claude",
             "com.ding.claude",
-            include_bytes!(
-                "../assets/brands/anthropic/claude/icons/claude-symbol-clay.icns"
-            ),
+            include_bytes!("../assets/brands/anthropic/claude/icons/claude-symbol-clay.icns"),
+        );
+    }
+    if source == "codex" {
+        return ensure_source_bundle(
+            "codex",
+            "Ding Codex",
+            "com.ding.codex",
+            include_bytes!("../assets/brands/codex/icons/openai-blossom-light.icns"),
+        );
+    }
+    None
+}
+
+fn ensure_default_bundle() -> Option<String> {
+    ensure_source_bundle("default", "Ding", "com.ding.default", &[])
+}
+
+fn ensure_source_bundle(
+    source: &str,
+    display_name: &str,
+    bundle_id: &str,
+    icon_bytes: &[u8],
+) -> Option<String> {
+    #[cfg(target_os = "macos")]
+    use crate::provider::macos::NOTIFIER_BINARY;
+
+    let base_dir = std::env::var("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|_| std::env::var("HOME").map(|h| PathBuf::from(h).join(".cache")))
+        .unwrap_or_else(|_| std::env::temp_dir());
+    let app_dir = base_dir.join("ding/apps").join(format!("{}.app", source));
+    let contents = app_dir.join("Contents");
+    let macos_dir = contents.join("MacOS");
+    let resources = contents.join("Resources");
+
+    if fs::create_dir_all(&macos_dir).is_err() || fs::create_dir_all(&resources).is_err() {
+        return None;
+    }
+
+    let mut anything_changed = false;
+
+    // Write icon if provided.
+    let has_icon = !icon_bytes.is_empty();
+    let icon_name = format!("{}.icns", source);
+    if has_icon {
+        let icon_path = resources.join(&icon_name);
+        match write_if_changed(&icon_path, icon_bytes) {
+            Ok(changed) => {
+                if changed {
+                    anything_changed = true;
+                }
+            }
+            Err(_) => return None,
+        }
+    }
+
+    // Write Info.plist (rebuild if anything changed or it doesn't exist).
+    let plist_path = contents.join("Info.plist");
+    if !plist_path.exists() || anything_changed {
+        let icon_version = icon_bytes
+            .iter()
+            .fold(0u32, |acc, byte| acc.wrapping_add(*byte as u32));
+
+        // Only include CFBundleIconFile when there's an actual icon.
+        let icon_plist_entry = if has_icon {
+            format!(
+                "  <key>CFBundleIconFile</key>\n  <string>{}</string>\n",
+                icon_name
+            )
+        } else {
+            String::new()
+        };
+
+        let plist = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+  <key>CFBundleName</key>
+  <string>{display_name}</string>
+  <key>CFBundleIdentifier</key>
+  <string>{bundle_id}</string>
+  <key>CFBundleVersion</key>
+  <string>{icon_version}</string>
+  <key>CFBundleShortVersionString</key>
+  <string>{icon_version}</string>
+  <key>CFBundleExecutable</key>
+  <string>ding-helper</string>
+{icon_entry}  <key>LSUIElement</key>
+  <true/>
+</dict>
+</plist>
+"#,
+            display_name = display_name,
+            bundle_id = bundle_id,
+            icon_version = icon_version,
+            icon_entry = icon_plist_entry,
         );
+        if fs::write(&plist_path, plist).is_err() {
+            return None;
+        }
+        anything_changed = true;
+    }
+
+    // Write the real notifier binary (not a dummy shell script).
+    // We can't use write_if_changed on the binary directly because codesigning
+    // modifies the Mach-O on disk, causing a mismatch with the embedded bytes
+    // on every run. Instead, track a hash of the embedded binary in a sidecar file.
+    let exec_path = macos_dir.join("ding-helper");
+    #[cfg(target_os = "macos")]
+    {
+        let version_path = macos_dir.join(".ding-helper-version");
+        let current_hash = NOTIFIER_BINARY
+            .iter()
+            .fold(0u64, |acc, &b| acc.wrapping_mul(31).wrapping_add(b as u64));
+        let version_tag = format!("{:016x}", current_hash);
+        let needs_update = !exec_path.exists()
+            || fs::read_to_string(&version_path)
+                .map(|v| v.trim() != version_tag)
+                .unwrap_or(true);
+
+        if needs_update {
+            if fs::write(&exec_path, NOTIFIER_BINARY).is_err() {
+                return None;
+            }
+            let _ = fs::write(&version_path, &version_tag);
+            anything_changed = true;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                if let Ok(mut perms) = fs::metadata(&exec_path).map(|m| m.permissions()) {
+                    perms.set_mode(0o755);
+                    let _ = fs::set_permissions(&exec_path, perms);
+                }
+            }
+        }
+    }
+
+    // Non-macOS: write a placeholder so the build doesn't break.
+    #[cfg(not(target_os = "macos"))]
+    {
+        if !exec_path.exists() {
+            let script = b"#!/bin/sh\nexit 0\n";
+            if fs::write(&exec_path, script).is_err() {
+                return None;
+            }
+        }
+    }
+
+    // Ad-hoc codesign the bundle whenever anything changed.
+    if anything_changed {
+        let _ = Command::new("codesign")
+            .args(["-s", "-", "--force", "--deep"])
+            .arg(&app_dir)
+            .status();
+    }
+
+    let lsregister = "/System/Library/Frameworks/CoreServices.framework/Frameworks/LaunchServices.framework/Support/lsregister";
+    let _ = Command::new(lsregister).arg("-f").arg(&app_dir).status();
+
+    Some(bundle_id.to_string())
+}
+
+fn allow_image_icons() -> bool {
+    std::env::var("DING_ALLOW_IMAGE_ICONS")
+        .map(|v| v == "1")
+        .unwrap_or(false)
+}
+
+fn write_if_changed(path: &PathBuf, contents: &[u8]) -> Result<bool, std::io::Error> {
+    if let Ok(existing) = fs::read(path) {
+        if existing == contents {
+            return Ok(false);
+        }
+    }
+    fs::write(path, contents)?;
+    Ok(true)
+}
+
+fn cache_dir() -> PathBuf {
+    std::env::var("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|_| std::env::var("HOME").map(|h| PathBuf::from(h).join(".cache")))
+        .unwrap_or_else(|_| std::env::temp_dir())
+}
+
+fn default_config_path() -> PathBuf {
+    if let Ok(dir) = std::env::var("XDG_CONFIG_HOME") {
+        return PathBuf::from(dir).join("ding/config.toml");
+    }
+    if let Ok(home) = std::env::var("HOME") {
+        return PathBuf::from(home).join(".config/ding/config.toml");
+    }
+    PathBuf::from("ding.toml")
+}
+
+fn resolve_provider(
+    cli_provider: Option<&str>,
+    config: Option<&Config>,
+) -> Result<String, NotifallError> {
+    if let Some(provider) = cli_provider {
+        return Ok(provider.to_lowercase());
+    }
+    if let Some(enabled) = config
+        .and_then(|c| c.forward.as_ref())
+        .and_then(|f| f.enabled)
+    {
+        if enabled {
+            return Ok("forward".to_string());
+        }
+    }
+    if let Some(default_provider) = config.and_then(|c| c.default_provider.as_ref()) {
+        return Ok(default_provider.to_lowercase());
+    }
+    if cfg!(target_os = "macos") {
+        return Ok("macos".to_string());
+    }
+    Err(NotifallError::NoProviderAvailable)
+}
+
+fn urgency_arg(urgency: Urgency) -> UrgencyArg {
+    match urgency {
+        Urgency::Low => UrgencyArg::Low,
+        Urgency::Normal => UrgencyArg::Normal,
+        Urgency::High => UrgencyArg::High,
+    }
+}
+
+fn map_urgency(arg: UrgencyArg) -> Urgency {
+    match arg {
+        UrgencyArg::Low => Urgency::Low,
+        UrgencyArg::Normal => Urgency::Normal,
+        UrgencyArg::High => Urgency::High,
+    }
+}
+
+/// Where a click is handled. URL actions open on the machine that showed the
+/// notification; `!command` actions run on the host that sent it, never on a
+/// listener.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ClickSide {
+    Local,
+    Listener,
+    Origin,
+}
+
+fn handle_click(
+    outcome: Option<DeliveryOutcome>,
+    on_click: Option<&str>,
+    notification: &Notification,
+    context: Option<&Context>,
+    side: ClickSide,
+) -> Result<(), NotifallError> {
+    let mut action_label = None;
+    let cmd = match outcome {
+        Some(DeliveryOutcome::Clicked) => on_click.map(|cmd| cmd.to_string()),
+        Some(DeliveryOutcome::ActionButton(label)) => {
+            let command = match notification.actions.iter().find(|a| a.label == label) {
+                Some(action) => action_command(action, side),
+                None => on_click.map(|cmd| cmd.to_string()),
+            };
+            action_label = Some(label);
+            command
+        }
+        _ => None,
+    };
+    let Some(cmd) = cmd else {
+        return Ok(());
+    };
+
+    let mut child = Command::new("sh");
+    child.arg("-c").arg(cmd);
+    if let Some(label) = action_label {
+        child.env("DING_ACTION", label);
+    }
+    if let Some(source) = notification.source.as_deref() {
+        child.env("DING_SOURCE", source);
+    }
+    child.env("DING_TITLE", &notification.title);
+    child.env("DING_MESSAGE", &notification.message);
+    if let Some(tag) = notification.tag.as_deref() {
+        child.env("DING_TAG", tag);
+    }
+    if let Some(context) = context {
+        if let Some(tmux) = context.tmux.as_ref() {
+            child.env("DING_TMUX_SESSION", &tmux.session);
+            child.env("DING_TMUX_WINDOW", &tmux.window);
+            child.env("DING_TMUX_PANE", &tmux.pane);
+            if let Some(client) = tmux.client.as_deref() {
+                child.env("DING_TMUX_CLIENT", client);
+            }
+        }
+        if let Some(terminal) = context.terminal.as_ref().and_then(|t| t.app.as_deref()) {
+            child.env("DING_TERMINAL_APP", terminal);
+        }
+        if let Ok(json) = serde_json::to_string(context) {
+            child.env("DING_CONTEXT_JSON", json);
+        }
     }
-    if source == "codex" {
-        return ensure_source_bundle(
-            "codex",
-            "Ding Codex",
-            "com.ding.codex",
-            include_bytes!("../assets/brands/codex/icons/openai-blossom-light.icns"),
-        );
+
+    child.spawn()?;
+    Ok(())
+}
+
+fn action_command(action: &Action, side: ClickSide) -> Option<String> {
+    if let Some(url) = action.url.as_deref()
+        && side != ClickSide::Origin
+    {
+        let opener = if cfg!(target_os = "macos") {
+            "open"
+        } else {
+            "xdg-open"
+        };
+        return Some(format!("{opener} {}", shell_escape(url)));
+    }
+    match action.command.as_deref() {
+        Some(command) if side != ClickSide::Listener => Some(command.to_string()),
+        _ => None,
     }
-    None
 }
 
-fn ensure_default_bundle() -> Option<String> {
-    ensure_source_bundle("default", "Ding", "com.ding.default", &[])
+fn spawn_background_wait(payload: WaitPayload) -> Result<PathBuf, NotifallError> {
+    let payload_path = write_payload(payload)?;
+    let exe = std::env::current_exe()?;
+    let mut cmd = Command::new(exe);
+    cmd.arg("wait-macos").arg("--payload").arg(&payload_path);
+    spawn_detached(cmd)?;
+    Ok(payload_path)
 }
 
-fn ensure_source_bundle(
-    source: &str,
-    display_name: &str,
-    bundle_id: &str,
-    icon_bytes: &[u8],
-) -> Option<String> {
-    #[cfg(target_os = "macos")]
-    use crate::provider::macos::NOTIFIER_BINARY;
+fn spawn_detached(mut cmd: Command) -> Result<(), NotifallError> {
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        unsafe {
+            cmd.pre_exec(|| {
+                libc::setsid();
+                Ok(())
+            });
+        }
+    }
+    cmd.spawn()?;
+    Ok(())
+}
 
-    let base_dir = std::env::var("XDG_CACHE_HOME")
-        .map(PathBuf::from)
-        .or_else(|_| std::env::var("HOME").map(|h| PathBuf::from(h).join(".cache")))
-        .unwrap_or_else(|_| std::env::temp_dir());
-    let app_dir = base_dir.join("ding/apps").join(format!("{}.app", source));
-    let contents = app_dir.join("Contents");
-    let macos_dir = contents.join("MacOS");
-    let resources = contents.join("Resources");
+fn write_payload(payload: WaitPayload) -> Result<PathBuf, NotifallError> {
+    let ts = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let file_name = format!("ding-payload-{}-{}.json", std::process::id(), ts);
+    let path = std::env::temp_dir().join(file_name);
+    let data = serde_json::to_vec(&payload)?;
+    fs::write(&path, data)?;
+    Ok(path)
+}
 
-    if fs::create_dir_all(&macos_dir).is_err() || fs::create_dir_all(&resources).is_err() {
-        return None;
+fn activate_terminal(terminal: Option<&str>) {
+    if !cfg!(target_os = "macos") {
+        return;
     }
 
-    let mut anything_changed = false;
+    let app = match terminal {
+        Some(name) if name.eq_ignore_ascii_case("ghostty") => Some("Ghostty"),
+        Some(name) if name.eq_ignore_ascii_case("iterm") => Some("iTerm"),
+        Some(name) if name.eq_ignore_ascii_case("iterm.app") => Some("iTerm"),
+        Some(name) if name.eq_ignore_ascii_case("terminal") => Some("Terminal"),
+        Some(name) if name.eq_ignore_ascii_case("apple_terminal") => Some("Terminal"),
+        Some(name) if name.eq_ignore_ascii_case("apple_terminal.app") => Some("Terminal"),
+        Some(name) if name.eq_ignore_ascii_case("Apple_Terminal") => Some("Terminal"),
+        Some(name) if name.eq_ignore_ascii_case("Apple_Terminal.app") => Some("Terminal"),
+        _ => None,
+    };
 
-    // Write icon if provided.
-    let has_icon = !icon_bytes.is_empty();
-    let icon_name = format!("{}.icns", source);
-    if has_icon {
-        let icon_path = resources.join(&icon_name);
-        match write_if_changed(&icon_path, icon_bytes) {
-            Ok(changed) => {
-                if changed {
-                    anything_changed = true;
-                }
-            }
-            Err(_) => return None,
-        }
+    if let Some(app) = app {
+        let _ = Command::new("osascript")
+            .args(["-e", &format!("tell application \"{}\" to activate", app)])
+            .status();
     }
+}
 
-    // Write Info.plist (rebuild if anything changed or it doesn't exist).
-    let plist_path = contents.join("Info.plist");
-    if !plist_path.exists() || anything_changed {
-        let icon_version = icon_bytes
-            .iter()
-            .fold(0u32, |acc, byte| acc.wrapping_add(*byte as u32));
+fn is_ghostty_terminal(terminal: Option<&str>) -> bool {
+    match terminal {
+        Some(name) if name.eq_ignore_ascii_case("ghostty") => true,
+        Some(name) if name.eq_ignore_ascii_case("ghostty.app") => true,
+        _ => false,
+    }
+}
 
-        // Only include CFBundleIconFile when there's an actual icon.
-        let icon_plist_entry = if has_icon {
-            format!(
-                "  <key>CFBundleIconFile</key>\n  <string>{}</string>\n",
-                icon_name
-            )
-        } else {
-            String::new()
-        };
+fn focus_ghostty_tab(project: &str) {
+    if !cfg!(target_os = "macos") {
+        return;
+    }
+    if project.trim().is_empty() {
+        return;
+    }
 
-        let plist = format!(
-            r#"<?xml version="1.0" encoding="UTF-8"?>
-<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
-<plist version="1.0">
-<dict>
-  <key>CFBundleName</key>
-  <string>{display_name}</string>
-  <key>CFBundleIdentifier</key>
-  <string>{bundle_id}</string>
-  <key>CFBundleVersion</key>
-  <string>{icon_version}</string>
-  <key>CFBundleShortVersionString</key>
-  <string>{icon_version}</string>
-  <key>CFBundleExecutable</key>
-  <string>ding-helper</string>
-{icon_entry}  <key>LSUIElement</key>
-  <true/>
-</dict>
-</plist>
-"#,
-            display_name = display_name,
-            bundle_id = bundle_id,
-            icon_version = icon_version,
-            icon_entry = icon_plist_entry,
-        );
-        if fs::write(&plist_path, plist).is_err() {
-            return None;
+    let script = r#"
+tell application "System Events"
+  tell process "Ghostty"
+    set targetProject to (system attribute "DING_PROJECT")
+    if targetProject is missing value then
+      return
+    end if
+    set frontmost to true
+    repeat with w in windows
+      try
+        set tg to tab group 1 of w
+        repeat with t in UI elements of tg
+          set tname to name of t
+          if tname contains targetProject then
+            perform action "AXRaise" of w
+            perform action "AXPress" of t
+            return
+          end if
+        end repeat
+      end try
+    end repeat
+  end tell
+end tell
+"#;
+
+    let _ = Command::new("osascript")
+        .arg("-e")
+        .arg(script)
+        .env("DING_PROJECT", project)
+        .status();
+}
+
+/// One line of `ding send --json`. The fields are documented in the README;
+/// `results` is only filled in by the forward provider.
+#[derive(serde::Serialize)]
+struct SendOutput {
+    ok: bool,
+    provider: &'static str,
+    id: Option<String>,
+    background: bool,
+    outcome: serde_json::Value,
+    payload: Option<String>,
+    results: Vec<ForwardResult>,
+}
+
+#[derive(serde::Serialize)]
+struct ForwardResult {
+    provider: String,
+    ok: bool,
+    exit_code: i32,
+    error: Option<String>,
+}
+
+impl SendOutput {
+    fn new(provider: &'static str, outcome: Option<&DeliveryOutcome>) -> Self {
+        SendOutput {
+            ok: !matches!(outcome, Some(DeliveryOutcome::Closed(_))),
+            provider,
+            id: None,
+            background: false,
+            outcome: outcome
+                .map(DeliveryOutcome::to_json)
+                .unwrap_or(serde_json::Value::Null),
+            payload: None,
+            results: Vec::new(),
         }
-        anything_changed = true;
     }
 
-    // Write the real notifier binary (not a dummy shell script).
-    // We can't use write_if_changed on the binary directly because codesigning
-    // modifies the Mach-O on disk, causing a mismatch with the embedded bytes
-    // on every run. Instead, track a hash of the embedded binary in a sidecar file.
-    let exec_path = macos_dir.join("ding-helper");
-    #[cfg(target_os = "macos")]
-    {
-        let version_path = macos_dir.join(".ding-helper-version");
-        let current_hash = NOTIFIER_BINARY
-            .iter()
-            .fold(0u64, |acc, &b| acc.wrapping_mul(31).wrapping_add(b as u64));
-        let version_tag = format!("{:016x}", current_hash);
-        let needs_update = !exec_path.exists()
-            || fs::read_to_string(&version_path)
-                .map(|v| v.trim() != version_tag)
-                .unwrap_or(true);
+    fn print(&self) -> Result<(), NotifallError> {
+        println!("{}", secret::redact(&serde_json::to_string(self)?));
+        Ok(())
+    }
+}
 
-        if needs_update {
-            if fs::write(&exec_path, NOTIFIER_BINARY).is_err() {
-                return None;
-            }
-            let _ = fs::write(&version_path, &version_tag);
-            anything_changed = true;
-            #[cfg(unix)]
-            {
-                use std::os::unix::fs::PermissionsExt;
-                if let Ok(mut perms) = fs::metadata(&exec_path).map(|m| m.permissions()) {
-                    perms.set_mode(0o755);
-                    let _ = fs::set_permissions(&exec_path, perms);
-                }
-            }
-        }
+// A notification closed while we waited for a click is reported as a failure
+// so scripts can tell it from a click.
+fn ensure_not_dismissed(
+    outcome: Option<&DeliveryOutcome>,
+    waited: bool,
+) -> Result<(), NotifallError> {
+    match outcome {
+        Some(DeliveryOutcome::Closed(_)) if waited => Err(NotifallError::Dismissed),
+        _ => Ok(()),
+    }
+}
+
+fn claude_settings_path() -> Result<PathBuf, NotifallError> {
+    Ok(home_dir()?.join(".claude").join("settings.json"))
+}
+
+fn gemini_settings_path() -> Result<PathBuf, NotifallError> {
+    Ok(home_dir()?.join(".gemini").join("settings.json"))
+}
+
+fn codex_config_path() -> Result<PathBuf, NotifallError> {
+    Ok(home_dir()?.join(".codex").join("config.toml"))
+}
+
+fn opencode_plugin_path() -> Result<PathBuf, NotifallError> {
+    let config_dir = std::env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|_| home_dir().map(|home| home.join(".config")))?;
+    Ok(config_dir.join("opencode/plugin/ding.js"))
+}
+
+fn install_claude(apply: bool, config: Option<&Config>) -> Result<(), NotifallError> {
+    let exe = std::env::current_exe()?;
+    let command = format!("{} hook claude", exe.display());
+    let group = serde_json::json!({
+        "matcher": "",
+        "hooks": [{ "type": "command", "command": command }]
+    });
+    install_json_hooks(
+        &claude_settings_path()?,
+        &hooks::enabled_events("claude", hooks::CLAUDE_HOOK_EVENTS, config),
+        "claude",
+        &group,
+        apply,
+    )
+}
+
+fn install_gemini(apply: bool, config: Option<&Config>) -> Result<(), NotifallError> {
+    let exe = std::env::current_exe()?;
+    let command = format!("{} hook gemini", exe.display());
+    let group = serde_json::json!({
+        "matcher": "*",
+        "hooks": [{ "name": "ding", "type": "command", "command": command }]
+    });
+    install_json_hooks(
+        &gemini_settings_path()?,
+        &hooks::enabled_events("gemini", hooks::GEMINI_HOOK_EVENTS, config),
+        "gemini",
+        &group,
+        apply,
+    )
+}
+
+// Claude Code and Gemini CLI share the `hooks.<event>[].hooks[]` layout.
+fn install_json_hooks(
+    settings_path: &PathBuf,
+    events: &[&str],
+    target: &str,
+    group: &serde_json::Value,
+    apply: bool,
+) -> Result<(), NotifallError> {
+    let settings = fs::read_to_string(settings_path).unwrap_or_else(|_| "{}".to_string());
+    let mut json: serde_json::Value = serde_json::from_str(&settings)?;
+    let before = json.clone();
+    install::merge_json_hooks(&mut json, events, target, group);
+
+    // Compare values, not text: the file is rarely formatted exactly like
+    // `to_string_pretty` would format it.
+    if json == before {
+        println!(
+            "ding hooks already installed in {}",
+            settings_path.display()
+        );
+        return Ok(());
+    }
+    let new_contents = serde_json::to_string_pretty(&json)?;
+    if !apply {
+        print_diff(
+            settings_path,
+            &settings,
+            &new_contents,
+            &format!("ding install {target} --apply"),
+        )?;
+        return Ok(());
     }
 
-    // Non-macOS: write a placeholder so the build doesn't break.
-    #[cfg(not(target_os = "macos"))]
-    {
-        if !exec_path.exists() {
-            let script = b"#!/bin/sh\nexit 0\n";
-            if fs::write(&exec_path, script).is_err() {
-                return None;
-            }
-        }
+    if settings_path.exists() {
+        backup_file(settings_path)?;
     }
+    if let Some(parent) = settings_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(settings_path, new_contents)?;
 
-    // Ad-hoc codesign the bundle whenever anything changed.
-    if anything_changed {
-        let _ = Command::new("codesign")
-            .args(["-s", "-", "--force", "--deep"])
-            .arg(&app_dir)
-            .status();
+    println!("Installed {target} hooks in {}", settings_path.display());
+    Ok(())
+}
+
+fn uninstall_json_hooks(
+    settings_path: &PathBuf,
+    target: &str,
+    apply: bool,
+) -> Result<(), NotifallError> {
+    let Ok(settings) = fs::read_to_string(settings_path) else {
+        println!("ding is not installed in {}", settings_path.display());
+        return Ok(());
+    };
+    let mut json: serde_json::Value = serde_json::from_str(&settings)?;
+    if install::remove_json_hooks(&mut json, target) == 0 {
+        println!("ding is not installed in {}", settings_path.display());
+        return Ok(());
     }
 
-    let lsregister = "/System/Library/Frameworks/CoreServices.framework/Frameworks/LaunchServices.framework/Support/lsregister";
-    let _ = Command::new(lsregister).arg("-f").arg(&app_dir).status();
+    let new_contents = serde_json::to_string_pretty(&json)?;
+    if !apply {
+        print_diff(
+            settings_path,
+            &settings,
+            &new_contents,
+            &format!("ding uninstall {target} --apply"),
+        )?;
+        return Ok(());
+    }
 
-    Some(bundle_id.to_string())
+    backup_file(settings_path)?;
+    fs::write(settings_path, new_contents)?;
+    println!("Removed {target} hooks from {}", settings_path.display());
+    Ok(())
 }
 
-fn allow_image_icons() -> bool {
-    std::env::var("DING_ALLOW_IMAGE_ICONS").map(|v| v == "1").unwrap_or(false)
+fn codex_notify(doc: &toml_edit::DocumentMut) -> Option<Vec<String>> {
+    let notify = doc.get("notify")?.as_array()?;
+    Some(
+        notify
+            .iter()
+            .filter_map(|v| v.as_str().map(|s| s.to_string()))
+            .collect(),
+    )
 }
 
-fn write_if_changed(path: &PathBuf, contents: &[u8]) -> Result<bool, std::io::Error> {
-    if let Ok(existing) = fs::read(path) {
-        if existing == contents {
-            return Ok(false);
-        }
-    }
-    fs::write(path, contents)?;
-    Ok(true)
+fn is_ding_codex_notify(notify: &[String]) -> bool {
+    let argv = notify.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+    install::is_ding_hook_argv(&argv, "codex")
 }
 
-fn default_config_path() -> PathBuf {
-    if let Ok(dir) = std::env::var("XDG_CONFIG_HOME") {
-        return PathBuf::from(dir).join("ding/config.toml");
-    }
-    if let Ok(home) = std::env::var("HOME") {
-        return PathBuf::from(home).join(".config/ding/config.toml");
+fn install_codex(apply: bool, force: bool) -> Result<(), NotifallError> {
+    let config_path = codex_config_path()?;
+
+    let config = fs::read_to_string(&config_path).unwrap_or_default();
+    let mut doc = toml_edit::DocumentMut::from_str(&config)?;
+    // Codex runs a single notify program, so another one can't be merged with ours.
+    if let Some(existing) = codex_notify(&doc)
+        && !existing.is_empty()
+        && !is_ding_codex_notify(&existing)
+        && !force
+    {
+        return Err(NotifallError::InstallConflict(format!(
+            "{} already sets notify = {:?}.\nCodex supports only one notify command; re-run with --force to replace it.",
+            config_path.display(),
+            existing
+        )));
     }
-    PathBuf::from("ding.toml")
-}
 
-fn resolve_provider(
-    cli_provider: Option<&str>,
-    config: Option<&Config>,
-) -> Result<String, NotifallError> {
-    if let Some(provider) = cli_provider {
-        return Ok(provider.to_lowercase());
+    let exe = std::env::current_exe()?;
+    let mut notify = toml_edit::Array::default();
+    notify.push(exe.display().to_string());
+    notify.push("hook");
+    notify.push("codex");
+    doc["notify"] = toml_edit::value(notify);
+
+    let new_contents = doc.to_string();
+    if new_contents == config {
+        println!("ding notify already installed in {}", config_path.display());
+        return Ok(());
     }
-    if let Some(enabled) = config
-        .and_then(|c| c.forward.as_ref())
-        .and_then(|f| f.enabled)
-    {
-        if enabled {
-            return Ok("forward".to_string());
-        }
+    if !apply {
+        let apply_cmd = if force {
+            "ding install codex --force --apply"
+        } else {
+            "ding install codex --apply"
+        };
+        print_diff(&config_path, &config, &new_contents, apply_cmd)?;
+        return Ok(());
     }
-    if let Some(default_provider) = config.and_then(|c| c.default_provider.as_ref()) {
-        return Ok(default_provider.to_lowercase());
+
+    if config_path.exists() {
+        backup_file(&config_path)?;
     }
-    if cfg!(target_os = "macos") {
-        return Ok("macos".to_string());
+    if let Some(parent) = config_path.parent() {
+        fs::create_dir_all(parent)?;
     }
-    Err(NotifallError::NoProviderAvailable)
+    fs::write(&config_path, new_contents)?;
+
+    println!("Installed Codex notify in {}", config_path.display());
+    Ok(())
 }
 
-fn map_urgency(arg: UrgencyArg) -> Urgency {
-    match arg {
-        UrgencyArg::Low => Urgency::Low,
-        UrgencyArg::Normal => Urgency::Normal,
-        UrgencyArg::High => Urgency::High,
+fn uninstall_codex(apply: bool) -> Result<(), NotifallError> {
+    let config_path = codex_config_path()?;
+    let config = fs::read_to_string(&config_path).unwrap_or_default();
+    let mut doc = toml_edit::DocumentMut::from_str(&config)?;
+    if !codex_notify(&doc).is_some_and(|notify| is_ding_codex_notify(&notify)) {
+        println!("ding is not installed in {}", config_path.display());
+        return Ok(());
+    }
+    doc.remove("notify");
+
+    let new_contents = doc.to_string();
+    if !apply {
+        print_diff(
+            &config_path,
+            &config,
+            &new_contents,
+            "ding uninstall codex --apply",
+        )?;
+        return Ok(());
     }
+
+    backup_file(&config_path)?;
+    fs::write(&config_path, new_contents)?;
+    println!("Removed Codex notify from {}", config_path.display());
+    Ok(())
 }
 
-fn handle_click(
-    outcome: Option<DeliveryOutcome>,
-    on_click: Option<&str>,
-    notification: &Notification,
-    context: Option<&Context>,
-) -> Result<(), NotifallError> {
-    let cmd = match (outcome, on_click) {
-        (Some(DeliveryOutcome::Clicked), Some(cmd)) => cmd,
-        (Some(DeliveryOutcome::ActionButton(_)), Some(cmd)) => cmd,
-        _ => return Ok(()),
-    };
+// OpenCode has no command hooks in its config; events reach ding through a
+// small plugin that forwards the interesting ones to `ding hook opencode`.
+fn install_opencode(apply: bool) -> Result<(), NotifallError> {
+    let plugin_path = opencode_plugin_path()?;
 
-    let mut child = Command::new("sh");
-    child.arg("-c").arg(cmd);
-    if let Some(source) = notification.source.as_deref() {
-        child.env("DING_SOURCE", source);
+    let existing = fs::read_to_string(&plugin_path).unwrap_or_default();
+    let exe = std::env::current_exe()?;
+    let new_contents = hooks::opencode_plugin(&exe.display().to_string());
+    if new_contents == existing {
+        println!("ding plugin already installed in {}", plugin_path.display());
+        return Ok(());
     }
-    child.env("DING_TITLE", &notification.title);
-    child.env("DING_MESSAGE", &notification.message);
-    if let Some(tag) = notification.tag.as_deref() {
-        child.env("DING_TAG", tag);
+
+    if !apply {
+        print_diff(
+            &plugin_path,
+            &existing,
+            &new_contents,
+            "ding install opencode --apply",
+        )?;
+        return Ok(());
     }
-    if let Some(context) = context {
-        if let Some(tmux) = context.tmux.as_ref() {
-            child.env("DING_TMUX_SESSION", &tmux.session);
-            child.env("DING_TMUX_WINDOW", &tmux.window);
-            child.env("DING_TMUX_PANE", &tmux.pane);
-            if let Some(client) = tmux.client.as_deref() {
-                child.env("DING_TMUX_CLIENT", client);
-            }
-        }
-        if let Some(terminal) = context.terminal.as_ref().and_then(|t| t.app.as_deref()) {
-            child.env("DING_TERMINAL_APP", terminal);
-        }
-        if let Ok(json) = serde_json::to_string(context) {
-            child.env("DING_CONTEXT_JSON", json);
-        }
+
+    if plugin_path.exists() {
+        backup_file(&plugin_path)?;
+    }
+    if let Some(parent) = plugin_path.parent() {
+        fs::create_dir_all(parent)?;
     }
+    fs::write(&plugin_path, new_contents)?;
 
-    child.spawn()?;
+    println!("Installed OpenCode plugin in {}", plugin_path.display());
     Ok(())
 }
 
-fn spawn_background_wait(payload: WaitPayload) -> Result<PathBuf, NotifallError> {
-    let payload_path = write_payload(payload)?;
-    let exe = std::env::current_exe()?;
-    let mut cmd = Command::new(exe);
-    cmd.arg("wait-macos").arg("--payload").arg(&payload_path);
-    cmd.stdin(Stdio::null())
-        .stdout(Stdio::null())
-        .stderr(Stdio::null());
-    #[cfg(unix)]
-    {
-        use std::os::unix::process::CommandExt;
-        unsafe {
-            cmd.pre_exec(|| {
-                libc::setsid();
-                Ok(())
-            });
-        }
+fn uninstall_opencode(apply: bool) -> Result<(), NotifallError> {
+    let plugin_path = opencode_plugin_path()?;
+    let existing = fs::read_to_string(&plugin_path).unwrap_or_default();
+    if !hooks::is_opencode_plugin(&existing) {
+        println!("ding is not installed in {}", plugin_path.display());
+        return Ok(());
     }
-    cmd.spawn()?;
-    Ok(payload_path)
+    if !apply {
+        print_diff(
+            &plugin_path,
+            &existing,
+            "",
+            "ding uninstall opencode --apply",
+        )?;
+        return Ok(());
+    }
+    backup_file(&plugin_path)?;
+    fs::remove_file(&plugin_path)?;
+    println!("Removed OpenCode plugin {}", plugin_path.display());
+    Ok(())
 }
 
-fn write_payload(payload: WaitPayload) -> Result<PathBuf, NotifallError> {
-    let ts = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis();
-    let file_name = format!("ding-payload-{}-{}.json", std::process::id(), ts);
-    let path = std::env::temp_dir().join(file_name);
-    let data = serde_json::to_vec(&payload)?;
-    fs::write(&path, data)?;
-    Ok(path)
+fn git_command(args: &[&str]) -> Option<String> {
+    let output = std::process::Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+// Used for `core.hooksPath` when a global install finds none configured.
+fn default_git_hooks_dir() -> PathBuf {
+    default_config_path()
+        .parent()
+        .map(|dir| dir.join("git-hooks"))
+        .unwrap_or_else(|| PathBuf::from("git-hooks"))
+}
+
+fn global_git_hooks_path() -> Option<PathBuf> {
+    git_command(&["config", "--global", "--get", "core.hooksPath"]).map(|dir| expand_home(&dir))
 }
 
-fn activate_terminal(terminal: Option<&str>) {
-    if !cfg!(target_os = "macos") {
-        return;
+// The hooks git runs for the current repository, unless that is the global
+// core.hooksPath, which only `--global` manages.
+fn repo_git_hooks_dir() -> Result<PathBuf, NotifallError> {
+    let dir = git_command(&["rev-parse", "--git-path", "hooks"])
+        .ok_or(NotifallError::NotGitRepository)?;
+    let dir = std::env::current_dir()?.join(dir);
+    if global_git_hooks_path().is_some_and(|global_dir| global_dir == dir) {
+        return Err(NotifallError::InstallConflict(format!(
+            "this repository uses the global core.hooksPath {}; use --global",
+            dir.display()
+        )));
+    }
+    Ok(dir)
+}
+
+fn git_hook_paths(dir: &std::path::Path, hook: &str) -> (PathBuf, PathBuf) {
+    (
+        dir.join(hook),
+        dir.join(format!("{hook}.{}", install::GIT_CHAINED_SUFFIX)),
+    )
+}
+
+fn install_git(apply: bool, global: bool) -> Result<(), NotifallError> {
+    let configured = if global {
+        global_git_hooks_path()
+    } else {
+        None
+    };
+    let dir = match (global, &configured) {
+        (true, Some(dir)) => dir.clone(),
+        (true, None) => default_git_hooks_dir(),
+        (false, _) => repo_git_hooks_dir()?,
+    };
+
+    let exe = shell_escape(&std::env::current_exe()?.display().to_string());
+    let mut plan = Vec::new();
+    let mut writes = Vec::new();
+    for hook in install::GIT_HOOKS {
+        let (path, chained) = git_hook_paths(&dir, hook);
+        let script = install::git_hook_script(&exe, hook, global);
+        let existing = fs::read_to_string(&path).ok();
+        let move_aside = match existing.as_deref() {
+            Some(existing) if existing == script => continue,
+            Some(existing) if install::is_git_hook_script(existing) => {
+                plan.push(format!("update {}", path.display()));
+                false
+            }
+            Some(_) => {
+                if chained.exists() {
+                    return Err(NotifallError::InstallConflict(format!(
+                        "{} exists and {} is already taken; move one of them and re-run",
+                        path.display(),
+                        chained.display()
+                    )));
+                }
+                plan.push(format!(
+                    "move {} to {} (ding runs it first)",
+                    path.display(),
+                    chained.display()
+                ));
+                true
+            }
+            None => {
+                plan.push(format!("create {}", path.display()));
+                false
+            }
+        };
+        writes.push((path, chained, script, move_aside));
+    }
+    let set_hooks_path = global && configured.is_none();
+    if set_hooks_path {
+        plan.push(format!(
+            "run `git config --global core.hooksPath {}`",
+            dir.display()
+        ));
+    }
+
+    let apply_cmd = if global {
+        "ding install git --global --apply"
+    } else {
+        "ding install git --apply"
+    };
+    if plan.is_empty() {
+        println!("ding git hooks already installed in {}", dir.display());
+        return Ok(());
+    }
+    if !apply {
+        println!("The following changes would be made:\n");
+        for step in &plan {
+            println!("  {step}");
+        }
+        println!(
+            "\nTo apply these changes automatically re-run `{apply_cmd}` with the --apply command."
+        );
+        return Ok(());
+    }
+
+    fs::create_dir_all(&dir)?;
+    for (path, chained, script, move_aside) in writes {
+        if move_aside {
+            fs::rename(&path, &chained)?;
+        }
+        fs::write(&path, script)?;
+        set_executable(&path)?;
+    }
+    if set_hooks_path {
+        let status = std::process::Command::new("git")
+            .args(["config", "--global", "core.hooksPath"])
+            .arg(&dir)
+            .status()?;
+        if !status.success() {
+            return Err(NotifallError::InstallConflict(
+                "git config --global core.hooksPath failed".to_string(),
+            ));
+        }
     }
+    println!("Installed git hooks in {}", dir.display());
+    Ok(())
+}
 
-    let app = match terminal {
-        Some(name) if name.eq_ignore_ascii_case("ghostty") => Some("Ghostty"),
-        Some(name) if name.eq_ignore_ascii_case("iterm") => Some("iTerm"),
-        Some(name) if name.eq_ignore_ascii_case("iterm.app") => Some("iTerm"),
-        Some(name) if name.eq_ignore_ascii_case("terminal") => Some("Terminal"),
-        Some(name) if name.eq_ignore_ascii_case("apple_terminal") => Some("Terminal"),
-        Some(name) if name.eq_ignore_ascii_case("apple_terminal.app") => Some("Terminal"),
-        Some(name) if name.eq_ignore_ascii_case("Apple_Terminal") => Some("Terminal"),
-        Some(name) if name.eq_ignore_ascii_case("Apple_Terminal.app") => Some("Terminal"),
-        _ => None,
+fn uninstall_git(apply: bool, global: bool) -> Result<(), NotifallError> {
+    let dir = if global {
+        global_git_hooks_path().unwrap_or_else(default_git_hooks_dir)
+    } else {
+        repo_git_hooks_dir()?
     };
 
-    if let Some(app) = app {
-        let _ = Command::new("osascript")
-            .args(["-e", &format!("tell application \"{}\" to activate", app)])
-            .status();
+    let mut plan = Vec::new();
+    let mut removals = Vec::new();
+    for hook in install::GIT_HOOKS {
+        let (path, chained) = git_hook_paths(&dir, hook);
+        let is_ding = fs::read_to_string(&path).is_ok_and(|s| install::is_git_hook_script(&s));
+        if !is_ding {
+            continue;
+        }
+        if chained.exists() {
+            plan.push(format!(
+                "restore {} from {}",
+                path.display(),
+                chained.display()
+            ));
+        } else {
+            plan.push(format!("remove {}", path.display()));
+        }
+        removals.push((path, chained));
+    }
+    let unset_hooks_path = global
+        && global_git_hooks_path().is_some_and(|configured| configured == default_git_hooks_dir());
+    if unset_hooks_path {
+        plan.push("run `git config --global --unset core.hooksPath`".to_string());
     }
-}
 
-fn is_ghostty_terminal(terminal: Option<&str>) -> bool {
-    match terminal {
-        Some(name) if name.eq_ignore_ascii_case("ghostty") => true,
-        Some(name) if name.eq_ignore_ascii_case("ghostty.app") => true,
-        _ => false,
+    if plan.is_empty() {
+        println!("ding is not installed in {}", dir.display());
+        return Ok(());
+    }
+    if !apply {
+        let apply_cmd = if global {
+            "ding uninstall git --global --apply"
+        } else {
+            "ding uninstall git --apply"
+        };
+        println!("The following changes would be made:\n");
+        for step in &plan {
+            println!("  {step}");
+        }
+        println!(
+            "\nTo apply these changes automatically re-run `{apply_cmd}` with the --apply command."
+        );
+        return Ok(());
     }
-}
 
-fn focus_ghostty_tab(project: &str) {
-    if !cfg!(target_os = "macos") {
-        return;
+    for (path, chained) in removals {
+        if chained.exists() {
+            fs::rename(&chained, &path)?;
+        } else {
+            fs::remove_file(&path)?;
+        }
     }
-    if project.trim().is_empty() {
-        return;
+    if unset_hooks_path {
+        let _ = std::process::Command::new("git")
+            .args(["config", "--global", "--unset", "core.hooksPath"])
+            .status();
     }
+    println!("Removed git hooks from {}", dir.display());
+    Ok(())
+}
 
-    let script = r#"
-tell application "System Events"
-  tell process "Ghostty"
-    set targetProject to (system attribute "DING_PROJECT")
-    if targetProject is missing value then
-      return
-    end if
-    set frontmost to true
-    repeat with w in windows
-      try
-        set tg to tab group 1 of w
-        repeat with t in UI elements of tg
-          set tname to name of t
-          if tname contains targetProject then
-            perform action "AXRaise" of w
-            perform action "AXPress" of t
-            return
-          end if
-        end repeat
-      end try
-    end repeat
-  end tell
-end tell
-"#;
+#[cfg(unix)]
+fn set_executable(path: &std::path::Path) -> Result<(), NotifallError> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    Ok(())
+}
 
-    let _ = Command::new("osascript")
-        .arg("-e")
-        .arg(script)
-        .env("DING_PROJECT", project)
-        .status();
+#[cfg(not(unix))]
+fn set_executable(_path: &std::path::Path) -> Result<(), NotifallError> {
+    Ok(())
 }
 
-fn print_send_output(
-    provider: &str,
-    outcome: Option<DeliveryOutcome>,
-    background: bool,
-    payload: Option<String>,
-) -> Result<(), NotifallError> {
-    #[derive(serde::Serialize)]
-    struct SendOutput<'a> {
-        provider: &'a str,
-        background: bool,
-        outcome: serde_json::Value,
-        payload: Option<String>,
-    }
-
-    let outcome_value = match outcome {
-        None => serde_json::Value::Null,
-        Some(DeliveryOutcome::Delivered) => serde_json::Value::String("delivered".to_string()),
-        Some(DeliveryOutcome::Clicked) => serde_json::Value::String("clicked".to_string()),
-        Some(DeliveryOutcome::ActionButton(label)) => serde_json::json!({
-            "type": "action",
-            "label": label,
-        }),
-        Some(DeliveryOutcome::Closed(label)) => serde_json::json!({
-            "type": "closed",
-            "label": label,
-        }),
-        Some(DeliveryOutcome::Replied(text)) => serde_json::json!({
-            "type": "reply",
-            "text": text,
-        }),
-    };
+fn git_hooks_state(dir: &std::path::Path, exe: &str, global: bool) -> String {
+    let escaped = shell_escape(exe);
+    let mut installed = 0;
+    let mut stale = false;
+    for hook in install::GIT_HOOKS {
+        let Ok(source) = fs::read_to_string(dir.join(hook)) else {
+            continue;
+        };
+        if install::is_git_hook_script(&source) {
+            installed += 1;
+            stale |= source != install::git_hook_script(&escaped, hook, global);
+        }
+    }
+    match installed {
+        0 => "not installed".to_string(),
+        _ if stale => "stale (different binary or script version)".to_string(),
+        n => format!("installed ({n}/{} hooks)", install::GIT_HOOKS.len()),
+    }
+}
 
-    let output = SendOutput {
-        provider,
-        background,
-        outcome: outcome_value,
-        payload,
-    };
-    println!("{}", serde_json::to_string(&output)?);
-    Ok(())
+enum Check {
+    Ok(String),
+    Warn(String),
+    Fail(String),
+    Skip(String),
 }
 
-fn install_claude(apply: bool) -> Result<(), NotifallError> {
-    let home = home_dir()?;
-    let claude_dir = home.join(".claude");
-    let settings_path = claude_dir.join("settings.json");
+fn handle_doctor(config_path: Option<&PathBuf>) -> Result<(), NotifallError> {
+    let mut checks = Vec::new();
+
+    let issues = config_issues(config_path);
+    let path = config_path.cloned().unwrap_or_else(default_config_path);
+    checks.push((
+        "config",
+        match issues.first() {
+            None if path.exists() => Check::Ok(path.display().to_string()),
+            None => Check::Skip(format!("no config file at {}", path.display())),
+            Some(first) if issues.len() == 1 => Check::Fail(first.clone()),
+            Some(first) => Check::Fail(format!("{first} (and {} more)", issues.len() - 1)),
+        },
+    ));
+    let config = resolve_config(config_path)
+        .ok()
+        .flatten()
+        .map(|resolved| resolved.config)
+        .unwrap_or_default();
 
-    let settings = fs::read_to_string(&settings_path).unwrap_or_else(|_| "{}".to_string());
-    let mut json: serde_json::Value = serde_json::from_str(&settings)?;
+    #[cfg(target_os = "macos")]
+    {
+        let macos = resolve_macos_config(Some(&config), None, None);
+        let check = match MacosProvider::new(macos).and_then(|p| p.helper_path()) {
+            Ok(helper) => Check::Ok(format!("helper {}", helper.display())),
+            Err(err) => Check::Fail(err.to_string()),
+        };
+        checks.push(("macos", check));
+    }
+    #[cfg(not(target_os = "macos"))]
+    checks.push((
+        "local",
+        Check::Warn(
+            "no local notification provider on this platform; use remote or telegram".to_string(),
+        ),
+    ));
 
-    let exe = std::env::current_exe()?;
-    let command = format!("{} hook claude", exe.display());
-    let hook_entry = serde_json::json!([{
-        "matcher": "",
-        "hooks": [{ "type": "command", "command": command }]
-    }]);
+    let telegram = config.telegram.clone().unwrap_or_default();
+    checks.push((
+        "telegram",
+        match telegram.bot_token() {
+            Err(err) => Check::Fail(err.to_string()),
+            Ok(None) => Check::Skip("bot_token not set".to_string()),
+            Ok(Some(token)) => telegram_check(&token, telegram.chat_id.as_deref()),
+        },
+    ));
 
-    json["hooks"]["Notification"] = hook_entry.clone();
-    json["hooks"]["Stop"] = hook_entry.clone();
+    let remote = config.remote.clone().unwrap_or_default();
+    checks.push((
+        "remote",
+        match resolve_remote_target(None, None, &remote, remote.token.is_some()) {
+            Ok(None) => Check::Skip("remote.host, remote.url or remote.socket not set".to_string()),
+            Ok(Some((url, host, port))) => match remote
+                .token()
+                .and_then(|token| ping_listener(&url, token.as_deref()))
+            {
+                Ok(()) if port == 0 => Check::Ok(format!("listener at {host} answers /ping")),
+                Ok(()) => Check::Ok(format!("listener at {host}:{port} answers /ping")),
+                Err(NotifallError::Provider(
+                    ProviderError::Message(message)
+                    | ProviderError::Unreachable(message)
+                    | ProviderError::Timeout(message),
+                )) => Check::Fail(message),
+                Err(err) => Check::Fail(err.to_string()),
+            },
+            Err(err) => Check::Fail(err.to_string()),
+        },
+    ));
 
-    let new_contents = serde_json::to_string_pretty(&json)?;
-    if !apply {
-        print_diff(
-            &settings_path,
-            &settings,
-            &new_contents,
-            "ding install claude --apply",
-        )?;
-        return Ok(());
-    }
+    checks.push(("listener", local_listener_check(config.listener.as_ref())));
+
+    checks.push((
+        "tmux",
+        match Command::new("tmux").arg("-V").output() {
+            Ok(output) if output.status.success() => {
+                let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                if std::env::var_os("TMUX").is_some() {
+                    Check::Ok(format!("{version}, inside a session"))
+                } else {
+                    Check::Ok(version)
+                }
+            }
+            _ => {
+                Check::Skip("tmux not installed (click-to-focus for panes unavailable)".to_string())
+            }
+        },
+    ));
 
-    if settings_path.exists() {
-        backup_file(&settings_path)?;
+    match install_status_rows(Some(&config)) {
+        Ok(rows) => {
+            for (name, state, _) in rows {
+                let check = if state.starts_with("installed") {
+                    Check::Ok(state)
+                } else if state.starts_with("stale") {
+                    Check::Warn(format!(
+                        "{state}; re-run `ding install {}`",
+                        name.trim_end_matches("-global")
+                    ))
+                } else {
+                    Check::Skip(state)
+                };
+                checks.push((name, check));
+            }
+        }
+        Err(err) => checks.push(("hooks", Check::Fail(err.to_string()))),
     }
-    fs::write(&settings_path, new_contents)?;
 
-    println!("Installed Claude hooks in {}", settings_path.display());
+    let mut failed = 0;
+    for (name, check) in &checks {
+        let (label, detail) = match check {
+            Check::Ok(detail) => ("ok", detail),
+            Check::Warn(detail) => ("warn", detail),
+            Check::Fail(detail) => {
+                failed += 1;
+                ("FAIL", detail)
+            }
+            Check::Skip(detail) => ("-", detail),
+        };
+        println!("{label:<5} {name:<10} {}", secret::redact(detail));
+    }
+    if failed > 0 {
+        return Err(NotifallError::DoctorFailed(failed));
+    }
     Ok(())
 }
 
-fn install_codex(apply: bool) -> Result<(), NotifallError> {
-    let home = home_dir()?;
-    let codex_dir = home.join(".codex");
-    let config_path = codex_dir.join("config.toml");
+fn telegram_check(token: &str, chat_id: Option<&str>) -> Check {
+    let url = format!("https://api.telegram.org/bot{token}/getMe");
+    let response = ureq::get(&url).timeout(Duration::from_secs(5)).call();
+    let description = |value: serde_json::Value| {
+        value
+            .get("description")
+            .and_then(|d| d.as_str())
+            .map(|d| d.to_string())
+    };
+    match response {
+        Ok(res) => {
+            let value = res.into_json::<serde_json::Value>().unwrap_or_default();
+            let username = value["result"]["username"].as_str().unwrap_or("?");
+            match chat_id {
+                Some(_) => Check::Ok(format!("bot @{username}")),
+                None => Check::Warn(format!(
+                    "bot @{username}, but chat_id is not set (see `ding telegram chat-id`)"
+                )),
+            }
+        }
+        Err(ureq::Error::Status(code, res)) => Check::Fail(format!(
+            "getMe: {}",
+            res.into_json::<serde_json::Value>()
+                .ok()
+                .and_then(description)
+                .unwrap_or_else(|| format!("status {code}"))
+        )),
+        Err(err) => Check::Fail(format!("getMe: {}", secret::redact(&err.to_string()))),
+    }
+}
 
-    let config = fs::read_to_string(&config_path).unwrap_or_default();
-    let mut doc = toml_edit::DocumentMut::from_str(&config)?;
-    let exe = std::env::current_exe()?;
-    let mut notify = toml_edit::Array::default();
-    notify.push(exe.display().to_string());
-    notify.push("hook");
-    notify.push("codex");
-    doc["notify"] = toml_edit::value(notify);
+// The listener configured on this machine, via its socket or bind address.
+fn local_listener_check(listener: Option<&crate::config::ListenerConfig>) -> Check {
+    let Some(listener) = listener else {
+        return Check::Skip("no [listener] section".to_string());
+    };
+    let (url, address) = match listener.socket.as_deref() {
+        Some(socket) => {
+            let socket = expand_home(socket).display().to_string();
+            (transport::unix_url(&socket, "/notify"), socket)
+        }
+        None => {
+            let host = match listener.bind.as_deref() {
+                None | Some("0.0.0.0") | Some("::") => "127.0.0.1",
+                Some(bind) => bind,
+            };
+            let address = format!("{host}:{}", listener.port.unwrap_or(4280));
+            (format!("http://{address}/notify"), address)
+        }
+    };
+    let token = match listener.token() {
+        Ok(token) => token,
+        Err(err) => return Check::Fail(err.to_string()),
+    };
+    match ping_listener(&url, token.as_deref()) {
+        Ok(()) => Check::Ok(format!("running at {address}")),
+        Err(_) => Check::Warn(format!("not running at {address}")),
+    }
+}
 
-    let new_contents = doc.to_string();
-    if !apply {
-        print_diff(
-            &config_path,
-            &config,
-            &new_contents,
-            "ding install codex --apply",
-        )?;
-        return Ok(());
+fn handle_install_status(config: Option<&Config>) -> Result<(), NotifallError> {
+    for (name, state, path) in install_status_rows(config)? {
+        println!("{name:<10} {state:<40} {}", path.display());
     }
+    Ok(())
+}
 
-    if config_path.exists() {
-        backup_file(&config_path)?;
+// (integration, state, file or directory) for every integration ding installs.
+fn install_status_rows(
+    config: Option<&Config>,
+) -> Result<Vec<(&'static str, String, PathBuf)>, NotifallError> {
+    let exe = std::env::current_exe()?.display().to_string();
+    let mut rows = Vec::new();
+
+    for (name, path, events) in [
+        ("claude", claude_settings_path()?, hooks::CLAUDE_HOOK_EVENTS),
+        ("gemini", gemini_settings_path()?, hooks::GEMINI_HOOK_EVENTS),
+    ] {
+        let events = hooks::enabled_events(name, events, config);
+        let json = fs::read_to_string(&path)
+            .ok()
+            .and_then(|raw| serde_json::from_str::<serde_json::Value>(&raw).ok())
+            .unwrap_or(serde_json::Value::Null);
+        let found = install::json_hook_commands(&json, name);
+        let current = format!("{exe} hook {name}");
+        let state = if found.is_empty() {
+            "not installed".to_string()
+        } else if let Some((_, stale)) = found.iter().find(|(_, command)| *command != current) {
+            format!("stale ({stale})")
+        } else {
+            let covered = events
+                .iter()
+                .filter(|event| found.iter().any(|(e, _)| e == *event))
+                .count();
+            // An event enabled since the install has no hook to run yet.
+            if covered < events.len() {
+                format!("stale ({covered}/{} enabled events hooked)", events.len())
+            } else {
+                format!("installed ({covered}/{} events)", events.len())
+            }
+        };
+        rows.push((name, state, path));
+    }
+
+    let codex_path = codex_config_path()?;
+    let codex_state = fs::read_to_string(&codex_path)
+        .ok()
+        .and_then(|raw| toml_edit::DocumentMut::from_str(&raw).ok())
+        .and_then(|doc| codex_notify(&doc))
+        .map(|notify| {
+            if !is_ding_codex_notify(&notify) {
+                format!("not installed (notify = {notify:?})")
+            } else if notify.first().map(|s| s.as_str()) != Some(exe.as_str()) {
+                format!("stale ({})", notify.join(" "))
+            } else {
+                "installed".to_string()
+            }
+        })
+        .unwrap_or_else(|| "not installed".to_string());
+    rows.push(("codex", codex_state, codex_path));
+
+    let plugin_path = opencode_plugin_path()?;
+    let plugin = fs::read_to_string(&plugin_path).unwrap_or_default();
+    let opencode_state = if !hooks::is_opencode_plugin(&plugin) {
+        "not installed".to_string()
+    } else if plugin != hooks::opencode_plugin(&exe) {
+        "stale (different binary or plugin version)".to_string()
+    } else {
+        "installed".to_string()
+    };
+    rows.push(("opencode", opencode_state, plugin_path));
+
+    if let Ok(dir) = repo_git_hooks_dir() {
+        rows.push(("git", git_hooks_state(&dir, &exe, false), dir));
     }
-    fs::write(&config_path, new_contents)?;
+    let global_dir = global_git_hooks_path();
+    let global_state = match &global_dir {
+        Some(dir) => git_hooks_state(dir, &exe, true),
+        None => "not installed (core.hooksPath unset)".to_string(),
+    };
+    rows.push((
+        "git-global",
+        global_state,
+        global_dir.unwrap_or_else(default_git_hooks_dir),
+    ));
 
-    println!("Installed Codex notify in {}", config_path.display());
-    Ok(())
+    Ok(rows)
 }
 
 fn home_dir() -> Result<PathBuf, NotifallError> {
@@ -2073,10 +5075,7 @@ fn print_diff(
     fs::write(&old_path, old)?;
     fs::write(&new_path, new)?;
 
-    let output = diff_output(
-        old_path.to_str().unwrap(),
-        new_path.to_str().unwrap(),
-    );
+    let output = diff_output(old_path.to_str().unwrap(), new_path.to_str().unwrap());
 
     let _ = fs::remove_file(&old_path);
     let _ = fs::remove_file(&new_path);
@@ -2280,171 +5279,6 @@ fn read_hook_payload(json: Option<&str>) -> Result<serde_json::Value, NotifallEr
     Ok(serde_json::from_slice(&buf)?)
 }
 
-fn handle_claude_hook(payload: serde_json::Value) -> Result<(), NotifallError> {
-    let project = project_name_from_payload(&payload);
-    log_hook_payload("claude", &payload, project.as_deref());
-    let hook = payload
-        .get("hook_event_name")
-        .and_then(|v| v.as_str())
-        .unwrap_or("Unknown");
-    let mut message = payload
-        .get("message")
-        .and_then(|v| v.as_str())
-        .or_else(|| payload.get("prompt").and_then(|v| v.as_str()))
-        .unwrap_or("")
-        .to_string();
-
-    let summary = if hook == "Notification" {
-        let ntype = payload
-            .get("notification_type")
-            .and_then(|v| v.as_str())
-            .unwrap_or("notification");
-        humanize_label(ntype)
-    } else if hook == "Stop" || hook == "SubagentStop" {
-        if message.is_empty() {
-            message = "Task completed".to_string();
-        }
-        "Finished".to_string()
-    } else {
-        humanize_label(hook)
-    };
-
-    let title = if let Some(project) = project.as_deref() {
-        format!("[{project}] {summary}")
-    } else {
-        summary
-    };
-
-    if message.is_empty() {
-        if let Some(tool) = payload.get("tool_name").and_then(|v| v.as_str()) {
-            message = tool.to_string();
-        } else {
-            message = " ".to_string();
-        }
-    }
-
-    let (title, message) = truncate_message(title, message);
-    let exe = std::env::current_exe()?;
-    let on_click = if let Some(project) = project.as_deref() {
-        format!(
-            "{} focus --project {}",
-            exe.display(),
-            shell_escape(project)
-        )
-    } else {
-        format!("{} focus", exe.display())
-    };
-    let args = SendArgs {
-        title: Some(title),
-        message,
-        icon: None,
-        no_icon: false,
-        link: None,
-        sound: None,
-        telegram_token: None,
-        telegram_chat_id: None,
-        telegram_parse_mode: None,
-        telegram_silent: false,
-        silent: false,
-        urgency: None,
-        tag: None,
-        source: Some("claude".to_string()),
-        on_click: Some(on_click),
-        wait_for_click: false,
-        background: true,
-        json: false,
-        provider: None,
-        remote_host: None,
-        remote_port: None,
-        remote_token: None,
-        remote_timeout_ms: None,
-        remote_retries: None,
-        no_fallback: false,
-    };
-
-    handle_send(None, args)
-}
-
-fn handle_codex_hook(payload: serde_json::Value) -> Result<(), NotifallError> {
-    let project = project_name_from_payload(&payload);
-    log_hook_payload("codex", &payload, project.as_deref());
-    let ntype = payload.get("type").and_then(|v| v.as_str()).unwrap_or("");
-    if ntype != "agent-turn-complete" {
-        return Ok(());
-    }
-
-    let assistant_message = payload
-        .get("last-assistant-message")
-        .and_then(|v| v.as_str());
-    let summary = "Turn Complete".to_string();
-
-    let input_messages = payload
-        .get("input_messages")
-        .and_then(|v| v.as_array())
-        .cloned()
-        .unwrap_or_default();
-    let mut message = if let Some(msg) = assistant_message {
-        msg.to_string()
-    } else {
-        input_messages
-            .iter()
-            .filter_map(|v| v.as_str())
-            .collect::<Vec<_>>()
-            .join(" ")
-            .trim()
-            .to_string()
-    };
-    if message.is_empty() {
-        message = " ".to_string();
-    }
-
-    let title = if let Some(project) = project.as_deref() {
-        format!("[{project}] {summary}")
-    } else {
-        summary
-    };
-    let (title, message) = truncate_message(title, message);
-    let exe = std::env::current_exe()?;
-    let on_click = if let Some(project) = project.as_deref() {
-        format!(
-            "{} focus --project {}",
-            exe.display(),
-            shell_escape(project)
-        )
-    } else {
-        format!("{} focus", exe.display())
-    };
-    let args = SendArgs {
-        title: Some(title),
-        message,
-        icon: None,
-        no_icon: false,
-        link: None,
-        sound: None,
-        telegram_token: None,
-        telegram_chat_id: None,
-        telegram_parse_mode: None,
-        telegram_silent: false,
-        silent: false,
-        urgency: None,
-        tag: None,
-        source: Some("codex".to_string()),
-        on_click: Some(on_click),
-        wait_for_click: false,
-        background: true,
-        json: false,
-        provider: None,
-        remote_host: None,
-        remote_port: None,
-        remote_token: None,
-        remote_timeout_ms: None,
-        remote_retries: None,
-        no_fallback: false,
-    };
-
-    handle_send(None, args)
-}
-
 fn truncate_message(title: String, message: String) -> (String, String) {
     let max_title = 120;
     let max_message = 300;
@@ -3,6 +3,7 @@ use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
 pub struct Notification {
     pub title: String,
     pub message: String,
@@ -25,6 +26,33 @@ pub struct Action {
     pub command: Option<String>,
 }
 
+impl Notification {
+    /// Clicks are reported by button label, so labels must be unique, and URL
+    /// buttons are handed to `open`/`xdg-open`, so only web pages are allowed.
+    pub fn check_actions(&self) -> Result<(), String> {
+        for (index, action) in self.actions.iter().enumerate() {
+            if self.actions[..index]
+                .iter()
+                .any(|other| other.label == action.label)
+            {
+                return Err(format!("duplicate action label {:?}", action.label));
+            }
+            if let Some(url) = action.url.as_deref() {
+                check_action_url(url)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn check_action_url(url: &str) -> Result<(), String> {
+    let scheme = url.split_once("://").map(|(scheme, _)| scheme);
+    match scheme.map(|scheme| scheme.to_ascii_lowercase()).as_deref() {
+        Some("http" | "https") => Ok(()),
+        _ => Err(format!("action URL must be http or https, got {url:?}")),
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Urgency {
@@ -38,3 +66,46 @@ impl Default for Urgency {
         Self::Normal
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn action(label: &str, url: &str) -> Action {
+        Action {
+            label: label.to_string(),
+            url: Some(url.to_string()),
+            command: None,
+        }
+    }
+
+    #[test]
+    fn check_actions_rejects_duplicates_and_other_schemes() {
+        let mut notification = Notification {
+            actions: vec![
+                action("Open", "https://ci.example.com"),
+                action("Logs", "HTTP://x"),
+            ],
+            ..Default::default()
+        };
+        assert!(notification.check_actions().is_ok());
+
+        notification
+            .actions
+            .push(action("Open", "https://other.example.com"));
+        assert_eq!(
+            notification.check_actions().unwrap_err(),
+            "duplicate action label \"Open\""
+        );
+
+        for url in [
+            "file:///etc/passwd",
+            "javascript:alert(1)",
+            "-a Calculator",
+            "ssh://host",
+        ] {
+            notification.actions = vec![action("Open", url)];
+            assert!(notification.check_actions().is_err(), "{url}");
+        }
+    }
+}
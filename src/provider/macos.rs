@@ -29,7 +29,7 @@ impl MacosProvider {
 
     // Derive the helper binary path from the app_bundle_id.
     // com.ding.claude -> ~/.cache/ding/apps/claude.app/Contents/MacOS/ding-helper
-    fn helper_path(&self) -> Result<PathBuf, ProviderError> {
+    pub fn helper_path(&self) -> Result<PathBuf, ProviderError> {
         let bundle_id = self
             .config
             .app_bundle_id
@@ -99,6 +99,14 @@ impl Provider for MacosProvider {
             "sound".into(),
             serde_json::Value::String(sound_value.into()),
         );
+        if !notification.actions.is_empty() {
+            let labels = notification
+                .actions
+                .iter()
+                .map(|action| serde_json::Value::String(action.label.clone()))
+                .collect();
+            req.insert("actions".into(), serde_json::Value::Array(labels));
+        }
         req.insert(
             "wait_for_click".into(),
             serde_json::Value::Bool(options.wait_for_click),
@@ -114,10 +122,7 @@ impl Provider for MacosProvider {
             .stderr(Stdio::piped())
             .spawn()
             .map_err(|e| {
-                ProviderError::Message(format!(
-                    "failed to spawn {}: {e}",
-                    helper.display()
-                ))
+                ProviderError::Message(format!("failed to spawn {}: {e}", helper.display()))
             })?;
 
         // Write JSON to stdin and close it.
@@ -128,9 +133,9 @@ impl Provider for MacosProvider {
         }
 
         // Wait for the helper to finish and read stdout.
-        let output = child.wait_with_output().map_err(|e| {
-            ProviderError::Message(format!("failed to wait for helper: {e}"))
-        })?;
+        let output = child
+            .wait_with_output()
+            .map_err(|e| ProviderError::Message(format!("failed to wait for helper: {e}")))?;
 
         if output.stdout.is_empty() {
             let stderr = String::from_utf8_lossy(&output.stderr);
@@ -142,13 +147,12 @@ impl Provider for MacosProvider {
         }
 
         // Parse the JSON response.
-        let resp: serde_json::Value =
-            serde_json::from_slice(&output.stdout).map_err(|e| {
-                ProviderError::Message(format!(
-                    "failed to parse helper response: {e} (raw: {})",
-                    String::from_utf8_lossy(&output.stdout)
-                ))
-            })?;
+        let resp: serde_json::Value = serde_json::from_slice(&output.stdout).map_err(|e| {
+            ProviderError::Message(format!(
+                "failed to parse helper response: {e} (raw: {})",
+                String::from_utf8_lossy(&output.stdout)
+            ))
+        })?;
 
         let status = resp["status"].as_str().unwrap_or("error");
         if status == "error" {
@@ -163,6 +167,9 @@ impl Provider for MacosProvider {
             Some(match status {
                 "clicked" => DeliveryOutcome::Clicked,
                 "closed" => DeliveryOutcome::Closed(String::new()),
+                "action" => DeliveryOutcome::ActionButton(
+                    resp["action"].as_str().unwrap_or_default().to_string(),
+                ),
                 _ => DeliveryOutcome::Delivered,
             })
         } else {
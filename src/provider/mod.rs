@@ -18,6 +18,51 @@ pub enum DeliveryOutcome {
     Replied(String),
 }
 
+impl DeliveryOutcome {
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            DeliveryOutcome::Delivered => serde_json::Value::String("delivered".to_string()),
+            DeliveryOutcome::Clicked => serde_json::Value::String("clicked".to_string()),
+            DeliveryOutcome::ActionButton(label) => serde_json::json!({
+                "type": "action",
+                "label": label,
+            }),
+            DeliveryOutcome::Closed(label) => serde_json::json!({
+                "type": "closed",
+                "label": label,
+            }),
+            DeliveryOutcome::Replied(text) => serde_json::json!({
+                "type": "reply",
+                "text": text,
+            }),
+        }
+    }
+
+    // Inverse of `to_json`, used when an outcome comes back from a listener.
+    pub fn from_json(value: &serde_json::Value) -> Option<Self> {
+        if let Some(kind) = value.as_str() {
+            return match kind {
+                "delivered" => Some(DeliveryOutcome::Delivered),
+                "clicked" => Some(DeliveryOutcome::Clicked),
+                _ => None,
+            };
+        }
+        let text = |key: &str| {
+            value
+                .get(key)
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        match value.get("type").and_then(|v| v.as_str())? {
+            "action" => Some(DeliveryOutcome::ActionButton(text("label"))),
+            "closed" => Some(DeliveryOutcome::Closed(text("label"))),
+            "reply" => Some(DeliveryOutcome::Replied(text("text"))),
+            _ => None,
+        }
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct DeliveryReport {
@@ -31,6 +76,14 @@ pub struct DeliveryReport {
 pub enum ProviderError {
     #[error("provider not available on this platform")]
     Unsupported,
+    /// A setting the provider needs is missing.
+    #[error("{0}")]
+    NotConfigured(String),
+    /// The remote listener could not be reached.
+    #[error("provider unavailable: {0}")]
+    Unreachable(String),
+    #[error("timed out: {0}")]
+    Timeout(String),
     #[error("provider error: {0}")]
     Message(String),
 }
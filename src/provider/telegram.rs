@@ -6,21 +6,26 @@ use serde::Deserialize;
 #[derive(Debug, Clone, Default)]
 pub struct TelegramProvider {
     config: TelegramConfig,
+    token: String,
 }
 
 impl TelegramProvider {
     pub fn new(config: TelegramConfig) -> Result<Self, ProviderError> {
-        if config.bot_token.as_deref().unwrap_or("").is_empty() {
-            return Err(ProviderError::Message(
+        let token = config
+            .bot_token()
+            .map_err(|err| ProviderError::NotConfigured(err.to_string()))?
+            .unwrap_or_default();
+        if token.is_empty() {
+            return Err(ProviderError::NotConfigured(
                 "telegram bot_token is not configured".to_string(),
             ));
         }
         if config.chat_id.as_deref().unwrap_or("").is_empty() {
-            return Err(ProviderError::Message(
+            return Err(ProviderError::NotConfigured(
                 "telegram chat_id is not configured".to_string(),
             ));
         }
-        Ok(Self { config })
+        Ok(Self { config, token })
     }
 }
 
@@ -34,24 +39,20 @@ impl Provider for TelegramProvider {
         notification: &Notification,
         _options: SendOptions,
     ) -> Result<DeliveryReport, ProviderError> {
-        let token = self
-            .config
-            .bot_token
-            .as_deref()
-            .unwrap_or_default();
-        let chat_id = self
-            .config
-            .chat_id
-            .as_deref()
-            .unwrap_or_default();
+        let token = &self.token;
+        let chat_id = self.config.chat_id.as_deref().unwrap_or_default();
         let parse_mode = self.config.parse_mode.as_deref();
         let silent = self.config.silent.unwrap_or(false);
 
-        let text = build_text(notification, parse_mode)?;
+        let text = match self.config.template.as_deref() {
+            Some(template) => {
+                let data = crate::template::data(notification, None);
+                crate::template::render(template, &data).map_err(ProviderError::Message)?
+            }
+            None => build_text(notification, parse_mode)?,
+        };
         if text.is_empty() {
-            return Err(ProviderError::Message(
-                "telegram text is empty".to_string(),
-            ));
+            return Err(ProviderError::Message("telegram text is empty".to_string()));
         }
 
         let url = format!("https://api.telegram.org/bot{token}/sendMessage");
@@ -68,6 +69,18 @@ impl Provider for TelegramProvider {
         if silent {
             payload["disable_notification"] = serde_json::Value::Bool(true);
         }
+        // Only URL buttons: callback buttons would need a bot polling for updates.
+        let buttons = notification
+            .actions
+            .iter()
+            .filter_map(|action| {
+                let url = action.url.as_deref()?;
+                Some(serde_json::json!([{ "text": action.label, "url": url }]))
+            })
+            .collect::<Vec<_>>();
+        if !buttons.is_empty() {
+            payload["reply_markup"] = serde_json::json!({ "inline_keyboard": buttons });
+        }
 
         let response = ureq::post(&url)
             .set("Content-Type", "application/json")
@@ -84,10 +97,7 @@ impl Provider for TelegramProvider {
                         .unwrap_or_else(|| "telegram error".to_string());
                     return Err(ProviderError::Message(desc));
                 }
-                let id = parsed
-                    .result
-                    .as_ref()
-                    .map(|r| r.message_id.to_string());
+                let id = parsed.result.as_ref().map(|r| r.message_id.to_string());
                 Ok(DeliveryReport {
                     provider: self.name(),
                     id,
@@ -150,6 +160,14 @@ fn build_text(
     if !link.trim().is_empty() {
         parts.push(link);
     }
+    for (key, value) in notification.metadata.iter().flatten() {
+        let line = format!("{key}: {value}");
+        parts.push(if use_markdown {
+            escape_markdown_v2(&line)
+        } else {
+            line
+        });
+    }
 
     let mut text = parts.join("\n");
     if text.len() > 4096 {
@@ -164,8 +182,8 @@ fn escape_markdown_v2(input: &str) -> String {
     let mut out = String::with_capacity(input.len());
     for ch in input.chars() {
         match ch {
-            '_' | '*' | '[' | ']' | '(' | ')' | '~' | '`' | '>' | '#' | '+' | '-' | '=' | '|' | '{'
-            | '}' | '.' | '!' => {
+            '_' | '*' | '[' | ']' | '(' | ')' | '~' | '`' | '>' | '#' | '+' | '-' | '=' | '|'
+            | '{' | '}' | '.' | '!' => {
                 out.push('\\');
                 out.push(ch);
             }
@@ -8,6 +8,28 @@ use std::env;
 pub struct RemoteEnvelope {
     pub notification: Notification,
     pub context: Option<RemoteContext>,
+    /// Ask the listener to track clicks so the sender can poll `/status/<id>`.
+    #[serde(default)]
+    pub wait_for_click: bool,
+    /// URL the listener POSTs the delivery outcome to once it is known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
+}
+
+/// An envelope waiting in the local batch queue, with where to send it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedEnvelope {
+    pub url: String,
+    pub token: Option<String>,
+    pub timeout_ms: u64,
+    pub retries: u32,
+    /// Show the notification locally if the listener cannot be reached.
+    #[serde(default)]
+    pub fallback_to_local: bool,
+    /// Flushes that already failed; the entry is dropped after a few.
+    #[serde(default)]
+    pub attempts: u32,
+    pub envelope: RemoteEnvelope,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -42,7 +64,7 @@ impl RemoteContext {
     }
 }
 
-fn local_hostname() -> Option<String> {
+pub fn local_hostname() -> Option<String> {
     let mut buf = [0u8; 256];
     let res = unsafe { gethostname(buf.as_mut_ptr() as *mut i8, buf.len()) };
     if res != 0 {
@@ -0,0 +1,810 @@
+use crate::config::KeyKind;
+use serde_json::{Map, Value, json};
+
+pub const PROVIDERS: &[&str] = &["macos", "forward", "remote", "telegram"];
+pub const FORWARD_TARGETS: &[&str] = &["remote", "telegram"];
+pub const PARSE_MODES: &[&str] = &["MarkdownV2", "Markdown", "HTML"];
+pub const URGENCIES: &[&str] = &["low", "normal", "high"];
+pub const LOG_LEVELS: &[&str] = &["off", "error", "warn", "info", "debug", "trace"];
+pub const LOG_FORMATS: &[&str] = &["text", "json"];
+
+#[derive(Debug, Clone, Copy)]
+pub enum Kind {
+    String,
+    Bool,
+    Integer,
+    Port,
+    List,
+    Enum(&'static [&'static str]),
+    EnumList(&'static [&'static str]),
+    /// A JSON pointer, `{/pointer}` text, or a list of them tried in order.
+    Rule,
+    /// A string or `{ env | file | command = "..." }`.
+    Secret,
+    /// A table of urgencies, as in `urgency_map`.
+    UrgencyMap,
+}
+
+impl Kind {
+    /// How an environment variable or `config set` value is parsed.
+    pub fn key_kind(self) -> KeyKind {
+        match self {
+            Kind::Bool => KeyKind::Bool,
+            Kind::Integer | Kind::Port => KeyKind::Integer,
+            Kind::List | Kind::EnumList(_) => KeyKind::List,
+            _ => KeyKind::String,
+        }
+    }
+}
+
+/// One config key: its type, a one-line description and an example value
+/// (TOML) shown in `Config::template()` and the JSON Schema.
+#[derive(Debug)]
+pub struct Field {
+    pub name: &'static str,
+    pub kind: Kind,
+    pub description: &'static str,
+    pub example: &'static str,
+}
+
+pub struct Section {
+    /// Empty for top-level keys.
+    pub name: &'static str,
+    pub description: &'static str,
+    pub fields: &'static [Field],
+}
+
+const fn field(
+    name: &'static str,
+    kind: Kind,
+    description: &'static str,
+    example: &'static str,
+) -> Field {
+    Field {
+        name,
+        kind,
+        description,
+        example,
+    }
+}
+
+pub const SECTIONS: &[Section] = &[
+    Section {
+        name: "",
+        description: "",
+        fields: &[
+            field(
+                "default_provider",
+                Kind::Enum(PROVIDERS),
+                "Provider used when --provider is not given",
+                "\"macos\"",
+            ),
+            field(
+                "profile",
+                Kind::String,
+                "Profile applied unless --profile, DING_PROFILE or a project .ding.toml picks one",
+                "\"work\"",
+            ),
+        ],
+    },
+    Section {
+        name: "macos",
+        description: "macOS Notification Center delivery",
+        fields: &[
+            field(
+                "sound",
+                Kind::String,
+                "Notification sound; \"none\" for silence",
+                "\"default\"",
+            ),
+            field(
+                "app_bundle_id",
+                Kind::String,
+                "App the notification appears to come from",
+                "\"com.apple.Terminal\"",
+            ),
+            field(
+                "icon",
+                Kind::String,
+                "Icon image path",
+                "\"/path/to/icon.png\"",
+            ),
+            field(
+                "title_template",
+                Kind::String,
+                "minijinja template for the title",
+                "\"{{ title }}{% if context.host %} ({{ context.host }}){% endif %}\"",
+            ),
+            field(
+                "message_template",
+                Kind::String,
+                "minijinja template for the message",
+                "\"{{ message | truncate(200) }}\"",
+            ),
+        ],
+    },
+    Section {
+        name: "remote",
+        description: "Sending to a ding listener on another machine",
+        fields: &[
+            field(
+                "host",
+                Kind::String,
+                "Listener host, or \"auto\" to find one via mDNS",
+                "\"127.0.0.1\"",
+            ),
+            field(
+                "listener_name",
+                Kind::String,
+                "With host = \"auto\", the advertised listener to use",
+                "\"my-laptop\"",
+            ),
+            field("port", Kind::Port, "Listener port", "4280"),
+            field(
+                "url",
+                Kind::String,
+                "Listener URL, instead of host and port",
+                "\"http://192.168.1.10:4280\"",
+            ),
+            field(
+                "socket",
+                Kind::String,
+                "Unix socket to send over (e.g. forwarded with ssh -R) instead of host/port",
+                "\"/tmp/ding.sock\"",
+            ),
+            field(
+                "token",
+                Kind::Secret,
+                "Bearer token the listener expects",
+                "{ env = \"DING_TOKEN\" }",
+            ),
+            field(
+                "timeout_ms",
+                Kind::Integer,
+                "Request timeout in milliseconds",
+                "2000",
+            ),
+            field(
+                "retries",
+                Kind::Integer,
+                "Retries after a failed request",
+                "2",
+            ),
+            field(
+                "fallback_to_local",
+                Kind::Bool,
+                "Notify locally when the listener can't be reached",
+                "true",
+            ),
+            field(
+                "batch",
+                Kind::Bool,
+                "Queue sends and deliver them together via /notify/batch",
+                "false",
+            ),
+            field(
+                "batch_window_ms",
+                Kind::Integer,
+                "How long to collect a batch",
+                "500",
+            ),
+        ],
+    },
+    Section {
+        name: "forward",
+        description: "Sending each notification to several targets",
+        fields: &[
+            field(
+                "enabled",
+                Kind::Bool,
+                "Forward notifications to the targets",
+                "true",
+            ),
+            field(
+                "targets",
+                Kind::EnumList(FORWARD_TARGETS),
+                "Targets to forward to",
+                "[\"remote\", \"telegram\"]",
+            ),
+        ],
+    },
+    Section {
+        name: "listener",
+        description: "`ding listen`, receiving notifications from other machines",
+        fields: &[
+            field("bind", Kind::String, "Address to listen on", "\"0.0.0.0\""),
+            field("port", Kind::Port, "Port to listen on", "4280"),
+            field(
+                "token",
+                Kind::Secret,
+                "Bearer token senders must present",
+                "{ file = \"~/.secrets/ding\" }",
+            ),
+            field(
+                "require_token",
+                Kind::Bool,
+                "Reject requests without the token",
+                "true",
+            ),
+            field(
+                "prefix_hostname",
+                Kind::Bool,
+                "Prefix titles with the sender's hostname",
+                "true",
+            ),
+            field(
+                "allow_hosts",
+                Kind::List,
+                "Only accept requests from these addresses",
+                "[\"127.0.0.1\"]",
+            ),
+            field(
+                "callback_hosts",
+                Kind::List,
+                "Hosts --remote-callback may POST to, besides the sender's own address",
+                "[\"build-server.lan\"]",
+            ),
+            field(
+                "cors_origin",
+                Kind::String,
+                "Origin browsers may read GET /events from (sent as Access-Control-Allow-Origin)",
+                "\"http://localhost:3000\"",
+            ),
+            field(
+                "on_click",
+                Kind::String,
+                "Command to run when a notification is clicked",
+                "\"ding focus\"",
+            ),
+            field(
+                "collapse_batches",
+                Kind::Bool,
+                "Show a batch as one summary notification",
+                "false",
+            ),
+            field(
+                "metrics_require_token",
+                Kind::Bool,
+                "Require the token for GET /metrics (defaults to require_token)",
+                "false",
+            ),
+            field(
+                "advertise",
+                Kind::Bool,
+                "Announce as _ding._tcp over mDNS (off by default; never for a loopback bind)",
+                "true",
+            ),
+            field(
+                "name",
+                Kind::String,
+                "Advertised name (defaults to the hostname)",
+                "\"my-laptop\"",
+            ),
+            field(
+                "socket",
+                Kind::String,
+                "Also listen on this Unix socket (see `ding remote ssh-config`)",
+                "\"~/.config/ding/listener.sock\"",
+            ),
+        ],
+    },
+    Section {
+        name: "telegram",
+        description: "Telegram bot delivery",
+        fields: &[
+            field(
+                "bot_token",
+                Kind::Secret,
+                "Bot token from @BotFather",
+                "{ command = \"pass show ding/telegram\" }",
+            ),
+            field(
+                "chat_id",
+                Kind::String,
+                "Chat to send to (see `ding telegram chat-id`)",
+                "\"123456789\"",
+            ),
+            field(
+                "parse_mode",
+                Kind::Enum(PARSE_MODES),
+                "Telegram formatting",
+                "\"MarkdownV2\"",
+            ),
+            field("silent", Kind::Bool, "Send without sound", "false"),
+            field(
+                "template",
+                Kind::String,
+                "minijinja template for the whole message, sent as-is",
+                "\"*{{ title | escape_for('markdown') }}*\\n{{ message | escape_for('markdown') }}\"",
+            ),
+        ],
+    },
+    Section {
+        name: "shell",
+        description: "`ding shell-init`, notifying when long commands finish",
+        fields: &[
+            field(
+                "threshold",
+                Kind::Integer,
+                "Seconds a command must run to notify",
+                "10",
+            ),
+            field(
+                "notify_when_focused",
+                Kind::Bool,
+                "Notify even when the terminal has focus",
+                "false",
+            ),
+            field(
+                "ignore",
+                Kind::List,
+                "Commands that never notify (replaces the defaults)",
+                "[\"vim\", \"less\", \"ssh\"]",
+            ),
+        ],
+    },
+    Section {
+        name: "log",
+        description: "Diagnostics written to stderr or a rotated log file",
+        fields: &[
+            field(
+                "level",
+                Kind::Enum(LOG_LEVELS),
+                "Lowest level written (default warn, info for `ding listen`)",
+                "\"info\"",
+            ),
+            field(
+                "format",
+                Kind::Enum(LOG_FORMATS),
+                "One line of text or one JSON object per record",
+                "\"json\"",
+            ),
+            field(
+                "file",
+                Kind::Bool,
+                "Write to logs/ding.log in the config directory instead of stderr",
+                "true",
+            ),
+            field(
+                "max_size",
+                Kind::Integer,
+                "Bytes before ding.log is rotated to ding.log.1",
+                "1048576",
+            ),
+            field("keep", Kind::Integer, "Rotated files to keep", "3"),
+            field(
+                "modules",
+                Kind::List,
+                "Per-module levels, e.g. listener.access=off",
+                "[\"remote=debug\", \"listener.access=off\"]",
+            ),
+        ],
+    },
+];
+
+/// `[sources.<name>]`, keyed by the notification's source.
+pub const SOURCE_FIELDS: &[Field] = &[
+    field(
+        "icon",
+        Kind::String,
+        "Icon image path",
+        "\"/path/to/claude.icns\"",
+    ),
+    field(
+        "app_bundle_id",
+        Kind::String,
+        "App the notification appears to come from",
+        "\"com.apple.Terminal\"",
+    ),
+    field(
+        "display_name",
+        Kind::String,
+        "Name shown for the source",
+        "\"Claude\"",
+    ),
+    field(
+        "title_template",
+        Kind::String,
+        "minijinja template for the title",
+        "\"{{ source }} · {{ context.project }}\"",
+    ),
+    field(
+        "message_template",
+        Kind::String,
+        "minijinja template for the message",
+        "\"{{ message | truncate(120) }}\"",
+    ),
+];
+
+/// `[sources.<name>.events.<event>]`, keyed by hook event.
+pub const SOURCE_EVENT_FIELDS: &[Field] = &[
+    field("enabled", Kind::Bool, "Notify for this event", "true"),
+    field(
+        "urgency",
+        Kind::Enum(URGENCIES),
+        "Urgency for this event",
+        "\"low\"",
+    ),
+    field("sound", Kind::String, "Sound for this event", "\"none\""),
+];
+
+/// `[hooks.<name>]`, an adapter for `ding hook <name>`.
+pub const HOOK_FIELDS: &[Field] = &[
+    field(
+        "source",
+        Kind::String,
+        "Source for icons and [sources] settings (defaults to the adapter name)",
+        "\"aider\"",
+    ),
+    field("event", Kind::Rule, "Where the event name is", "\"/type\""),
+    field(
+        "subtype",
+        Kind::Rule,
+        "Where the event subtype is",
+        "\"/subtype\"",
+    ),
+    field(
+        "events",
+        Kind::List,
+        "Only notify for these events",
+        "[\"done\", \"error\"]",
+    ),
+    field(
+        "title",
+        Kind::Rule,
+        "Notification title",
+        "[\"/title\", \"Aider\"]",
+    ),
+    field(
+        "message",
+        Kind::Rule,
+        "Notification message",
+        "[\"/message\", \"{/type} in {/cwd}\"]",
+    ),
+    field(
+        "project",
+        Kind::Rule,
+        "Project directory or name",
+        "\"/cwd\"",
+    ),
+    field("urgency", Kind::Rule, "Where the urgency is", "\"/level\""),
+    field(
+        "urgency_map",
+        Kind::UrgencyMap,
+        "Map the tool's levels to urgencies",
+        "{ error = \"high\" }",
+    ),
+];
+
+/// `[hooks.<name>.on.<event>]`, per-event overrides inside an adapter.
+pub const HOOK_EVENT_FIELDS: &[Field] = &[
+    field("enabled", Kind::Bool, "Notify for this event", "true"),
+    field(
+        "title",
+        Kind::Rule,
+        "Title for this event",
+        "\"Build finished\"",
+    ),
+    field(
+        "message",
+        Kind::Rule,
+        "Message for this event",
+        "\"/summary\"",
+    ),
+    field(
+        "urgency",
+        Kind::Enum(URGENCIES),
+        "Urgency for this event",
+        "\"high\"",
+    ),
+];
+
+/// Keys of the fixed sections, dotted (`remote.port`).
+pub fn keys() -> impl Iterator<Item = (String, &'static Field)> {
+    SECTIONS.iter().flat_map(|section| {
+        section.fields.iter().map(move |field| {
+            let key = match section.name {
+                "" => field.name.to_string(),
+                name => format!("{name}.{}", field.name),
+            };
+            (key, field)
+        })
+    })
+}
+
+pub fn find(fields: &'static [Field], name: &str) -> Option<&'static Field> {
+    fields.iter().find(|field| field.name == name)
+}
+
+/// JSON Schema (draft-07) for config.toml, as used by Taplo / Even Better TOML.
+pub fn json_schema() -> Value {
+    let mut properties = section_properties();
+    properties.insert(
+        "profiles".into(),
+        json!({
+            "description": "Overlays selected with --profile, DING_PROFILE or `profile`",
+            "type": "object",
+            "additionalProperties": { "$ref": "#/definitions/profile" },
+        }),
+    );
+    let mut profile = section_properties();
+    profile.remove("profile");
+
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "ding config",
+        "type": "object",
+        "additionalProperties": false,
+        "properties": properties,
+        "definitions": {
+            "profile": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": profile,
+            },
+            "source": object(SOURCE_FIELDS, Some(("events", json!({
+                "description": "Per-event settings, keyed by hook event",
+                "type": "object",
+                "additionalProperties": object(SOURCE_EVENT_FIELDS, None),
+            })))),
+            "hook": object(HOOK_FIELDS, Some(("on", json!({
+                "description": "Per-event overrides, keyed by event",
+                "type": "object",
+                "additionalProperties": object(HOOK_EVENT_FIELDS, None),
+            })))),
+            "rule": {
+                "anyOf": [
+                    { "type": "string" },
+                    { "type": "array", "items": { "type": "string" } },
+                ],
+            },
+            "secret": {
+                "anyOf": [
+                    { "type": "string" },
+                    {
+                        "type": "object",
+                        "properties": {
+                            "env": { "type": "string", "description": "Environment variable holding the secret" },
+                            "file": { "type": "string", "description": "File holding the secret" },
+                            "command": { "type": "string", "description": "Command printing the secret" },
+                        },
+                        "minProperties": 1,
+                        "maxProperties": 1,
+                        "additionalProperties": false,
+                    },
+                ],
+            },
+        },
+    })
+}
+
+// Top-level keys, the fixed sections, and the `sources`/`hooks` maps.
+fn section_properties() -> Map<String, Value> {
+    let mut properties = Map::new();
+    for section in SECTIONS {
+        if section.name.is_empty() {
+            for field in section.fields {
+                properties.insert(field.name.into(), field_schema(field));
+            }
+        } else {
+            let mut schema = object(section.fields, None);
+            schema["description"] = section.description.into();
+            properties.insert(section.name.into(), schema);
+        }
+    }
+    properties.insert(
+        "sources".into(),
+        json!({
+            "description": "Per-source settings, keyed by source name",
+            "type": "object",
+            "additionalProperties": { "$ref": "#/definitions/source" },
+        }),
+    );
+    properties.insert(
+        "hooks".into(),
+        json!({
+            "description": "Adapters for `ding hook <name>`",
+            "type": "object",
+            "additionalProperties": { "$ref": "#/definitions/hook" },
+        }),
+    );
+    properties
+}
+
+fn object(fields: &[Field], nested: Option<(&str, Value)>) -> Value {
+    let mut properties = fields
+        .iter()
+        .map(|field| (field.name.to_string(), field_schema(field)))
+        .collect::<Map<_, _>>();
+    if let Some((name, schema)) = nested {
+        properties.insert(name.into(), schema);
+    }
+    json!({
+        "type": "object",
+        "additionalProperties": false,
+        "properties": properties,
+    })
+}
+
+fn field_schema(field: &Field) -> Value {
+    let mut schema = match field.kind {
+        Kind::String => json!({ "type": "string" }),
+        Kind::Bool => json!({ "type": "boolean" }),
+        Kind::Integer => json!({ "type": "integer", "minimum": 0 }),
+        Kind::Port => json!({ "type": "integer", "minimum": 0, "maximum": 65535 }),
+        Kind::List => json!({ "type": "array", "items": { "type": "string" } }),
+        Kind::Enum(values) => json!({ "type": "string", "enum": values }),
+        Kind::EnumList(values) => {
+            json!({ "type": "array", "items": { "type": "string", "enum": values } })
+        }
+        Kind::Rule => json!({ "$ref": "#/definitions/rule" }),
+        Kind::Secret => json!({ "$ref": "#/definitions/secret" }),
+        Kind::UrgencyMap => json!({
+            "type": "object",
+            "additionalProperties": { "type": "string", "enum": URGENCIES },
+        }),
+    };
+    // A `$ref` ignores sibling keywords in draft-07, so wrap it.
+    if schema.get("$ref").is_some() {
+        schema = json!({ "allOf": [schema] });
+    }
+    schema["description"] = field.description.into();
+    if let Ok(example) = format!("v = {}", field.example).parse::<toml::Table>()
+        && let Some(example) = example.get("v")
+        && let Ok(example) = serde_json::to_value(example)
+    {
+        schema["examples"] = json!([example]);
+    }
+    schema
+}
+
+/// The commented config written by `ding config init`.
+pub fn template() -> String {
+    let mut out = String::from("# ding config\n");
+    for section in SECTIONS {
+        if !section.name.is_empty() {
+            out.push_str(&format!("\n[{}] # {}\n", section.name, section.description));
+        }
+        push_fields(&mut out, section.fields);
+    }
+    out.push_str("\n# Per-source settings, keyed by the notification's source:\n");
+    out.push_str("# [sources.claude]\n");
+    push_fields(&mut out, SOURCE_FIELDS);
+    out.push_str("# Per-event settings, keyed by hook event (or Notification type such as permission_prompt):\n");
+    out.push_str("# [sources.claude.events.SessionStart]\n");
+    push_fields(&mut out, SOURCE_EVENT_FIELDS);
+    out.push_str("\n# Adapter for any tool that emits JSON, used as `ding hook aider`:\n");
+    out.push_str("# [hooks.aider]\n");
+    push_fields(&mut out, HOOK_FIELDS);
+    out.push_str("# [hooks.aider.on.error]\n");
+    push_fields(&mut out, HOOK_EVENT_FIELDS);
+    out.push_str(
+        "\n# Overlays on everything above, chosen with `--profile work`, DING_PROFILE=work\n\
+         # or `profile = \"work\"` in a project .ding.toml:\n\
+         # [profiles.work]\n\
+         # default_provider = \"remote\"\n\
+         # [profiles.work.remote]\n\
+         # host = \"work-laptop.local\"\n",
+    );
+    out
+}
+
+fn push_fields(out: &mut String, fields: &[Field]) {
+    for field in fields {
+        out.push_str(&format!(
+            "# {} = {} # {}\n",
+            field.name, field.example, field.description
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use std::collections::BTreeSet;
+
+    // The template with every commented-out example enabled.
+    fn template_examples() -> toml::Table {
+        let text = template()
+            .lines()
+            .map(|line| match line.strip_prefix("# ") {
+                Some(rest)
+                    if rest.starts_with('[')
+                        || rest.split_once(" = ").is_some_and(|(key, _)| {
+                            key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                        }) =>
+                {
+                    rest
+                }
+                _ => line,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        toml::from_str(&text).expect("template examples parse")
+    }
+
+    // Dotted keys of `value`, descending into objects except at `stop`.
+    fn leaf_keys(value: &Value, prefix: &str, stop: &[&str], keys: &mut BTreeSet<String>) {
+        let Value::Object(map) = value else {
+            return;
+        };
+        for (key, inner) in map {
+            let path = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{prefix}.{key}")
+            };
+            if inner.is_object() && !stop.contains(&path.as_str()) {
+                leaf_keys(inner, &path, stop, keys);
+            } else {
+                keys.insert(path);
+            }
+        }
+    }
+
+    fn names(fields: &[Field], extra: &[&str]) -> BTreeSet<String> {
+        fields
+            .iter()
+            .map(|field| field.name.to_string())
+            .chain(extra.iter().map(|name| name.to_string()))
+            .collect()
+    }
+
+    fn config_from_template() -> Value {
+        let mut table = template_examples();
+        // Secret references would show up as nested keys; the schema lists the secret itself.
+        for key in crate::secret::SECRET_KEYS {
+            let (section, name) = key.split_once('.').unwrap();
+            table[section].as_table_mut().unwrap().remove(name);
+        }
+        let config: Config = table.try_into().expect("template examples deserialize");
+        serde_json::to_value(config).unwrap()
+    }
+
+    #[test]
+    fn schema_keys_match_config_fields() {
+        let config = config_from_template();
+        let mut fields = BTreeSet::new();
+        leaf_keys(&config, "", &["sources", "hooks", "profiles"], &mut fields);
+        for map in ["sources", "hooks", "profiles"] {
+            fields.remove(map);
+        }
+        let schema = keys().map(|(key, _)| key).collect::<BTreeSet<_>>();
+        assert_eq!(fields, schema);
+    }
+
+    #[test]
+    fn nested_schema_fields_match_config_fields() {
+        let config = config_from_template();
+        let keys_of = |pointer: &str| {
+            config
+                .pointer(pointer)
+                .and_then(Value::as_object)
+                .unwrap_or_else(|| panic!("{pointer} missing from the template"))
+                .keys()
+                .cloned()
+                .collect::<BTreeSet<_>>()
+        };
+        assert_eq!(
+            keys_of("/sources/claude"),
+            names(SOURCE_FIELDS, &["events"])
+        );
+        assert_eq!(
+            keys_of("/sources/claude/events/SessionStart"),
+            names(SOURCE_EVENT_FIELDS, &[])
+        );
+        assert_eq!(keys_of("/hooks/aider"), names(HOOK_FIELDS, &["on"]));
+        assert_eq!(
+            keys_of("/hooks/aider/on/error"),
+            names(HOOK_EVENT_FIELDS, &[])
+        );
+    }
+
+    #[test]
+    fn template_examples_pass_validation() {
+        let text = toml::to_string(&template_examples()).unwrap();
+        let issues = crate::validate::check_file(&text);
+        assert!(issues.is_empty(), "{issues:?}");
+    }
+}
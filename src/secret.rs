@@ -0,0 +1,268 @@
+use crate::error::NotifallError;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Keys that hold credentials. Besides a plain string each accepts
+/// `{ env = "VAR" }`, `{ file = "~/path" }` or `{ command = "pass show x" }`.
+pub const SECRET_KEYS: &[&str] = &["telegram.bot_token", "remote.token", "listener.token"];
+
+pub const REDACTED: &str = "[redacted]";
+
+// Secret values seen while loading the config, scrubbed from logs and output.
+static KNOWN: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+pub fn is_secret_key(key: &str) -> bool {
+    SECRET_KEYS.contains(&key)
+}
+
+/// A secret as written in the config. References are resolved the first time
+/// something reads the value, so a `{ command = ... }` only runs when its
+/// provider is used, and at most once per loaded config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Secret {
+    value: toml::Value,
+    #[serde(skip)]
+    resolved: Arc<OnceLock<String>>,
+}
+
+impl Secret {
+    pub fn resolve(&self, key: &str) -> Result<String, NotifallError> {
+        if let Some(secret) = self.resolved.get() {
+            return Ok(secret.clone());
+        }
+        let secret = resolve(key, &self.value)?;
+        Ok(self.resolved.get_or_init(|| secret).clone())
+    }
+}
+
+impl From<String> for Secret {
+    fn from(secret: String) -> Self {
+        Secret {
+            value: toml::Value::String(secret),
+            resolved: Arc::default(),
+        }
+    }
+}
+
+/// The secret a config value stands for: the string itself or whatever its
+/// reference points at (trailing newlines trimmed).
+pub fn resolve(key: &str, value: &toml::Value) -> Result<String, NotifallError> {
+    let fail = |message: String| NotifallError::Secret(format!("{key}: {message}"));
+    let secret = match value {
+        toml::Value::String(secret) => secret.clone(),
+        toml::Value::Table(table) => {
+            let reference = match (table.len(), table.iter().next()) {
+                (1, Some((kind, toml::Value::String(target)))) => (kind.as_str(), target),
+                _ => {
+                    return Err(fail(
+                        "expected a string or one of { env = ... }, { file = ... }, { command = ... }"
+                            .to_string(),
+                    ));
+                }
+            };
+            match reference {
+                ("env", var) => std::env::var(var)
+                    .map_err(|_| fail(format!("environment variable {var} is not set")))?,
+                ("file", path) => {
+                    let path = crate::expand_home(path);
+                    std::fs::read_to_string(&path)
+                        .map_err(|err| fail(format!("cannot read {}: {err}", path.display())))?
+                }
+                ("command", command) => {
+                    let output = Command::new("sh")
+                        .arg("-c")
+                        .arg(command)
+                        .output()
+                        .map_err(|err| fail(format!("cannot run `{command}`: {err}")))?;
+                    if !output.status.success() {
+                        return Err(fail(format!("`{command}` exited with {}", output.status)));
+                    }
+                    String::from_utf8(output.stdout)
+                        .map_err(|_| fail(format!("`{command}` printed invalid UTF-8")))?
+                }
+                (other, _) => {
+                    return Err(fail(format!(
+                        "unknown secret reference {other:?} (use env, file or command)"
+                    )));
+                }
+            }
+        }
+        _ => return Err(fail("expected a string".to_string())),
+    };
+    let secret = secret.trim_end_matches(['\r', '\n']).to_string();
+    if secret.is_empty() {
+        return Err(fail("resolved to an empty value".to_string()));
+    }
+    register(&secret);
+    Ok(secret)
+}
+
+/// Remember `secret` so [`redact`] hides it.
+pub fn register(secret: &str) {
+    // Very short values would redact unrelated text.
+    if secret.len() < 4 {
+        return;
+    }
+    if let Ok(mut known) = KNOWN.lock()
+        && !known.iter().any(|existing| existing == secret)
+    {
+        known.push(secret.to_string());
+    }
+}
+
+/// `text` with every known secret replaced by `[redacted]`.
+pub fn redact(text: &str) -> String {
+    let Ok(known) = KNOWN.lock() else {
+        return text.to_string();
+    };
+    known.iter().fold(text.to_string(), |text, secret| {
+        text.replace(secret.as_str(), REDACTED)
+    })
+}
+
+/// Config file text with plaintext secrets replaced, at the top level and in
+/// each `[profiles.<name>]`. References are left alone since they hold no
+/// secret themselves.
+pub fn redact_config_text(contents: &str) -> String {
+    let Ok(mut doc) = contents.parse::<toml_edit::DocumentMut>() else {
+        return redact(contents);
+    };
+    redact_table(doc.as_item_mut());
+    if let Some(profiles) = doc.get_mut("profiles").and_then(|p| p.as_table_like_mut()) {
+        for (_, profile) in profiles.iter_mut() {
+            redact_table(profile);
+        }
+    }
+    redact(&doc.to_string())
+}
+
+fn redact_table(item: &mut toml_edit::Item) {
+    for key in SECRET_KEYS {
+        let Some((section, name)) = key.split_once('.') else {
+            continue;
+        };
+        if let Some(value) = item
+            .get_mut(section)
+            .and_then(|section| section.get_mut(name))
+            .filter(|value| value.is_str())
+        {
+            let decor = value.as_value().map(|v| v.decor().clone());
+            *value = toml_edit::value(REDACTED);
+            if let (Some(decor), Some(value)) = (decor, value.as_value_mut()) {
+                *value.decor_mut() = decor;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reference(kind: &str, target: &str) -> toml::Value {
+        let mut table = toml::Table::new();
+        table.insert(kind.to_string(), toml::Value::String(target.to_string()));
+        toml::Value::Table(table)
+    }
+
+    #[test]
+    fn resolve_plain_string_trims_newlines() {
+        let value = toml::Value::String("plain-secret\r\n".into());
+        assert_eq!(resolve("remote.token", &value).unwrap(), "plain-secret");
+    }
+
+    #[test]
+    fn resolve_env_reference() {
+        let expected = std::env::var("PATH").unwrap();
+        assert_eq!(
+            resolve("remote.token", &reference("env", "PATH")).unwrap(),
+            expected
+        );
+        let err = resolve(
+            "remote.token",
+            &reference("env", "DING_TEST_UNSET_VARIABLE"),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("remote.token"));
+    }
+
+    #[test]
+    fn resolve_file_reference() {
+        let path = std::env::temp_dir().join(format!("ding-secret-{}", std::process::id()));
+        std::fs::write(&path, "from-a-file\n").unwrap();
+        let value = reference("file", &path.display().to_string());
+        assert_eq!(resolve("listener.token", &value).unwrap(), "from-a-file");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn resolve_command_reference() {
+        let value = reference("command", "printf 'from-a-command\\n'");
+        assert_eq!(
+            resolve("telegram.bot_token", &value).unwrap(),
+            "from-a-command"
+        );
+        assert!(resolve("telegram.bot_token", &reference("command", "exit 3")).is_err());
+        assert!(resolve("telegram.bot_token", &reference("command", "true")).is_err());
+    }
+
+    #[test]
+    fn resolve_rejects_malformed_values() {
+        assert!(resolve("remote.token", &reference("vault", "x")).is_err());
+        assert!(resolve("remote.token", &toml::Value::Integer(1)).is_err());
+        let mut two = toml::Table::new();
+        two.insert("env".into(), "A".into());
+        two.insert("file".into(), "B".into());
+        assert!(resolve("remote.token", &toml::Value::Table(two)).is_err());
+    }
+
+    #[test]
+    fn secret_resolves_once() {
+        let path = std::env::temp_dir().join(format!("ding-secret-count-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let command = format!("echo x >> {} && echo lazy-secret", path.display());
+        let secret: Secret = toml::Value::Table(
+            [("command".to_string(), toml::Value::String(command))]
+                .into_iter()
+                .collect(),
+        )
+        .try_into()
+        .unwrap();
+        // Nothing runs until the value is read.
+        assert!(!path.exists());
+        let copy = secret.clone();
+        assert_eq!(secret.resolve("remote.token").unwrap(), "lazy-secret");
+        assert_eq!(copy.resolve("remote.token").unwrap(), "lazy-secret");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "x\n");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn redact_hides_registered_secrets() {
+        register("abc");
+        register("registered-secret-value");
+        assert_eq!(
+            redact("token=registered-secret-value abc"),
+            "token=[redacted] abc"
+        );
+    }
+
+    #[test]
+    fn redact_config_text_keeps_references_and_comments() {
+        let text = "[remote]\n\
+                    token = \"config-text-secret\" # the listener's\n\
+                    host = \"h\"\n\
+                    [listener]\n\
+                    token = { env = \"TOKEN\" }\n\
+                    [profiles.work.telegram]\n\
+                    bot_token = \"profile-secret\"\n";
+        let redacted = redact_config_text(text);
+        assert!(redacted.contains("token = \"[redacted]\" # the listener's"));
+        assert!(redacted.contains("host = \"h\""));
+        assert!(redacted.contains("token = { env = \"TOKEN\" }"));
+        assert!(!redacted.contains("profile-secret"));
+        assert!(!redacted.contains("config-text-secret"));
+    }
+}
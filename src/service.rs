@@ -0,0 +1,197 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub const SYSTEMD_UNIT: &str = "ding-listener.service";
+pub const LAUNCHD_LABEL: &str = "com.ding.listener";
+
+/// Service manager used to keep `ding listen` running in the background.
+#[derive(Debug, Clone, Copy)]
+pub enum ServiceKind {
+    Systemd,
+    Launchd,
+}
+
+impl ServiceKind {
+    pub fn current() -> Self {
+        if cfg!(target_os = "macos") {
+            ServiceKind::Launchd
+        } else {
+            ServiceKind::Systemd
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ServiceKind::Systemd => "systemd",
+            ServiceKind::Launchd => "launchd",
+        }
+    }
+
+    pub fn unit_path(self, home: &Path) -> PathBuf {
+        match self {
+            ServiceKind::Systemd => std::env::var("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .unwrap_or_else(|_| home.join(".config"))
+                .join("systemd/user")
+                .join(SYSTEMD_UNIT),
+            ServiceKind::Launchd => home
+                .join("Library/LaunchAgents")
+                .join(format!("{LAUNCHD_LABEL}.plist")),
+        }
+    }
+
+    /// Render the unit file (systemd) or property list (launchd) that runs
+    /// `<exe> [--config <path>] listen`.
+    pub fn render(self, exe: &Path, config: Option<&Path>, log_dir: Option<&Path>) -> String {
+        let mut args = vec![exe.display().to_string()];
+        if let Some(config) = config {
+            args.push("--config".to_string());
+            args.push(config.display().to_string());
+        }
+        args.push("listen".to_string());
+
+        match self {
+            ServiceKind::Systemd => {
+                let exec = args
+                    .iter()
+                    .map(|arg| systemd_quote(arg))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                format!(
+                    "[Unit]\n\
+Description=ding notification listener\n\
+After=network-online.target\n\
+\n\
+[Service]\n\
+ExecStart={exec}\n\
+Restart=on-failure\n\
+RestartSec=5\n\
+\n\
+[Install]\n\
+WantedBy=default.target\n"
+                )
+            }
+            ServiceKind::Launchd => {
+                let program_args = args
+                    .iter()
+                    .map(|arg| format!("    <string>{}</string>\n", xml_escape(arg)))
+                    .collect::<String>();
+                let logs = log_dir
+                    .map(|dir| {
+                        let log = xml_escape(&dir.join("listener.log").display().to_string());
+                        format!(
+                            "  <key>StandardOutPath</key>\n  <string>{log}</string>\n  \
+<key>StandardErrorPath</key>\n  <string>{log}</string>\n"
+                        )
+                    })
+                    .unwrap_or_default();
+                format!(
+                    r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+  <key>Label</key>
+  <string>{LAUNCHD_LABEL}</string>
+  <key>ProgramArguments</key>
+  <array>
+{program_args}  </array>
+  <key>RunAtLoad</key>
+  <true/>
+  <key>KeepAlive</key>
+  <true/>
+{logs}</dict>
+</plist>
+"#
+                )
+            }
+        }
+    }
+
+    /// Load and start the service after its unit file has been written.
+    pub fn activate(self, path: &Path) -> io::Result<()> {
+        match self {
+            ServiceKind::Systemd => {
+                run(Command::new("systemctl").args(["--user", "daemon-reload"]))?;
+                run(Command::new("systemctl").args(["--user", "enable", "--now", SYSTEMD_UNIT]))?;
+                // Pick up a changed unit when the service was already running.
+                run(Command::new("systemctl").args(["--user", "restart", SYSTEMD_UNIT]))
+            }
+            ServiceKind::Launchd => {
+                let _ = Command::new("launchctl").arg("unload").arg(path).status();
+                run(Command::new("launchctl").arg("load").arg("-w").arg(path))
+            }
+        }
+    }
+
+    /// Stop the service before its unit file is removed.
+    pub fn deactivate(self, path: &Path) -> io::Result<()> {
+        match self {
+            ServiceKind::Systemd => {
+                run(Command::new("systemctl").args(["--user", "disable", "--now", SYSTEMD_UNIT]))
+            }
+            ServiceKind::Launchd => {
+                run(Command::new("launchctl").arg("unload").arg("-w").arg(path))
+            }
+        }
+    }
+
+    /// Reload the service manager after a unit file was removed.
+    pub fn reload(self) {
+        if let ServiceKind::Systemd = self {
+            let _ = Command::new("systemctl")
+                .args(["--user", "daemon-reload"])
+                .status();
+        }
+    }
+
+    /// Human-readable state as reported by the service manager.
+    pub fn state(self) -> Option<String> {
+        let output = match self {
+            ServiceKind::Systemd => Command::new("systemctl")
+                .args(["--user", "is-active", SYSTEMD_UNIT])
+                .output()
+                .ok()?,
+            ServiceKind::Launchd => Command::new("launchctl")
+                .args(["list", LAUNCHD_LABEL])
+                .output()
+                .ok()?,
+        };
+        match self {
+            ServiceKind::Systemd => {
+                let state = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                (!state.is_empty()).then_some(state)
+            }
+            ServiceKind::Launchd => Some(if output.status.success() {
+                "loaded".to_string()
+            } else {
+                "not loaded".to_string()
+            }),
+        }
+    }
+}
+
+fn run(cmd: &mut Command) -> io::Result<()> {
+    let status = cmd.status()?;
+    if status.success() {
+        return Ok(());
+    }
+    Err(io::Error::other(format!("{cmd:?} failed ({status})")))
+}
+
+fn systemd_quote(value: &str) -> String {
+    // `%` starts a specifier and `$` a variable, inside quotes or not.
+    let value = value.replace('%', "%%").replace('$', "$$");
+    if !value.is_empty() && !value.contains(|c: char| c.is_whitespace() || c == '"' || c == '\\') {
+        return value;
+    }
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,191 @@
+use std::process::Command;
+
+use crate::cli::ShellKind;
+
+pub const DEFAULT_THRESHOLD_SECS: u64 = 10;
+
+/// Full-screen and interactive programs that are "long" only because someone
+/// was using them.
+pub const DEFAULT_IGNORE: &[&str] = &[
+    "vim", "nvim", "vi", "nano", "emacs", "less", "more", "man", "top", "htop", "btop", "ssh",
+    "mosh", "tmux", "screen", "watch", "tig", "lazygit",
+];
+
+/// Integration script for `shell`, calling `exe` (already shell-escaped) when
+/// a command takes at least `threshold` seconds. `DING_SHELL_THRESHOLD`
+/// overrides the threshold at runtime.
+pub fn init_script(shell: ShellKind, exe: &str, threshold: u64) -> String {
+    match shell {
+        ShellKind::Zsh => format!(
+            r#"# ding shell integration. Add to ~/.zshrc: eval "$(ding shell-init zsh)"
+zmodload zsh/datetime 2>/dev/null
+typeset -g __ding_cmd= __ding_start=
+__ding_preexec() {{
+    __ding_cmd=$1
+    __ding_start=$EPOCHSECONDS
+}}
+__ding_precmd() {{
+    local st=$?
+    [[ -n $__ding_start ]] || return 0
+    local duration=$(( EPOCHSECONDS - __ding_start ))
+    __ding_start=
+    (( duration >= ${{DING_SHELL_THRESHOLD:-{threshold}}} )) || return 0
+    ( {exe} shell-notify --status $st --duration $duration -- "$__ding_cmd" >/dev/null 2>&1 & )
+}}
+autoload -Uz add-zsh-hook
+add-zsh-hook preexec __ding_preexec
+add-zsh-hook precmd __ding_precmd
+"#
+        ),
+        ShellKind::Bash => format!(
+            r#"# ding shell integration. Add to ~/.bashrc: eval "$(ding shell-init bash)"
+__ding_cmd=
+__ding_start=
+__ding_armed=
+# DEBUG fires before every simple command; only the first one after a prompt
+# starts the clock.
+__ding_preexec() {{
+    [[ -n $__ding_armed && -z $COMP_LINE ]] || return 0
+    [[ $BASH_COMMAND == __ding_* ]] && return 0
+    __ding_armed=
+    __ding_start=$SECONDS
+    __ding_cmd=$(HISTTIMEFORMAT= history 1 | sed 's/^ *[0-9]*[* ] *//')
+    [[ -n $__ding_cmd ]] || __ding_cmd=$BASH_COMMAND
+}}
+__ding_precmd() {{
+    local st=$?
+    [[ -n $__ding_start ]] || return 0
+    local duration=$(( SECONDS - __ding_start ))
+    __ding_start=
+    (( duration >= ${{DING_SHELL_THRESHOLD:-{threshold}}} )) || return 0
+    ( {exe} shell-notify --status $st --duration $duration -- "$__ding_cmd" >/dev/null 2>&1 & )
+}}
+__ding_arm() {{
+    __ding_armed=1
+}}
+# Keep a DEBUG trap set before ours (bash-preexec, a prompt theme) and run it
+# first, as it was; evaluating this twice does not chain ours to itself.
+__ding_prev_debug=$(trap -p DEBUG)
+__ding_prev_debug=${{__ding_prev_debug#trap -- }}
+eval "__ding_prev_debug=${{__ding_prev_debug% DEBUG}}"
+case $__ding_prev_debug in
+    *__ding_preexec*) ;;
+    '') trap '__ding_preexec' DEBUG ;;
+    *) trap "$__ding_prev_debug"$'\n''__ding_preexec' DEBUG ;;
+esac
+unset __ding_prev_debug
+# Bash 5.1+ also accepts PROMPT_COMMAND as an array; keep every element and
+# run ours first and last.
+if [[ $(declare -p PROMPT_COMMAND 2>/dev/null) == "declare -a"* ]]; then
+    PROMPT_COMMAND=(__ding_precmd "${{PROMPT_COMMAND[@]}}" __ding_arm)
+else
+    PROMPT_COMMAND="__ding_precmd;${{PROMPT_COMMAND:+$PROMPT_COMMAND;}}__ding_arm"
+fi
+"#
+        ),
+        ShellKind::Fish => format!(
+            r#"# ding shell integration. Add to ~/.config/fish/config.fish: ding shell-init fish | source
+function __ding_postexec --on-event fish_postexec
+    set -l st $status
+    set -l duration (math --scale=0 "$CMD_DURATION / 1000")
+    set -l threshold {threshold}
+    set -q DING_SHELL_THRESHOLD; and set threshold $DING_SHELL_THRESHOLD
+    test $duration -ge $threshold; or return 0
+    {exe} shell-notify --status $st --duration $duration -- $argv[1] >/dev/null 2>&1 &
+    disown 2>/dev/null
+end
+"#
+        ),
+    }
+}
+
+/// First word of a command line, skipping `sudo`, `env` and variable
+/// assignments, without any directory.
+pub fn command_name(command: &str) -> Option<&str> {
+    command
+        .split_whitespace()
+        .find(|word| !matches!(*word, "sudo" | "env" | "command" | "exec") && !word.contains('='))
+        .map(|word| word.rsplit('/').next().unwrap_or(word))
+}
+
+/// Whether the terminal running this shell has the user's attention.
+/// `None` when that can't be told (e.g. over SSH without tmux).
+pub fn terminal_focused() -> Option<bool> {
+    if std::env::var_os("TMUX").is_some()
+        && let Some(pane_visible) = tmux_pane_visible()
+        && !pane_visible
+    {
+        return Some(false);
+    }
+    if cfg!(target_os = "macos") {
+        return macos_terminal_frontmost();
+    }
+    x11_window_active()
+}
+
+// The pane is the active one in the active window of an attached session.
+fn tmux_pane_visible() -> Option<bool> {
+    let pane = std::env::var("TMUX_PANE").ok()?;
+    let output = Command::new("tmux")
+        .args([
+            "display-message",
+            "-p",
+            "-t",
+            &pane,
+            "#{pane_active} #{window_active} #{session_attached}",
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let flags = stdout.split_whitespace().collect::<Vec<_>>();
+    Some(matches!(flags.as_slice(), ["1", "1", attached] if *attached != "0"))
+}
+
+fn macos_terminal_frontmost() -> Option<bool> {
+    // Inside tmux TERM_PROGRAM is "tmux"; the outer terminal is in LC_TERMINAL
+    // for iTerm, otherwise unknown.
+    let terminal = std::env::var("TERM_PROGRAM")
+        .ok()
+        .filter(|term| term != "tmux")
+        .or_else(|| std::env::var("LC_TERMINAL").ok())?;
+    let output = Command::new("osascript")
+        .args([
+            "-e",
+            "tell application \"System Events\" to get name of first process whose frontmost is true",
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let frontmost = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .to_lowercase();
+    let terminal = terminal.trim_end_matches(".app").to_lowercase();
+    let terminal = match terminal.as_str() {
+        "apple_terminal" => "terminal",
+        "iterm" | "iterm2" => "iterm2",
+        other => other,
+    };
+    Some(frontmost == terminal)
+}
+
+fn x11_window_active() -> Option<bool> {
+    let window = std::env::var("WINDOWID").ok()?.parse::<u64>().ok()?;
+    std::env::var_os("DISPLAY")?;
+    let output = Command::new("xdotool")
+        .arg("getactivewindow")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let active = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(active == window)
+}
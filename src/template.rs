@@ -0,0 +1,183 @@
+use crate::notification::Notification;
+use crate::remote::RemoteContext;
+use minijinja::{Environment, Error, ErrorKind, Value};
+
+// Environment variables exposed as `env.<VAR>`. Templates can come from a
+// project file or, on the listener, apply to remote sends, so the rest of the
+// environment (tokens, keys) stays out of reach; pass anything else as
+// `--meta`.
+pub const TEMPLATE_ENV: &[&str] = &[
+    "USER",
+    "LOGNAME",
+    "HOME",
+    "HOSTNAME",
+    "SHELL",
+    "TERM",
+    "TERM_PROGRAM",
+    "LANG",
+    "PWD",
+];
+
+/// Variables available to title/message templates: the notification's own
+/// fields, `metadata`, `context` (host, user, cwd, project, tmux, terminal)
+/// and the `TEMPLATE_ENV` subset of `env`.
+pub fn data(notification: &Notification, context: Option<&RemoteContext>) -> serde_json::Value {
+    let local;
+    let context = match context {
+        Some(context) => context,
+        None => {
+            local = RemoteContext::from_local(crate::context::detect_context());
+            &local
+        }
+    };
+    let project = context
+        .cwd
+        .as_deref()
+        .and_then(|cwd| std::path::Path::new(cwd).file_name())
+        .and_then(|name| name.to_str());
+    serde_json::json!({
+        "title": notification.title,
+        "message": notification.message,
+        "source": notification.source,
+        "link": notification.link,
+        "sound": notification.sound,
+        "urgency": notification.urgency,
+        "tag": notification.tag,
+        "sender": notification.sender,
+        "metadata": notification.metadata.clone().unwrap_or_default(),
+        "context": {
+            "host": context.origin_host,
+            "user": context.origin_user,
+            "cwd": context.cwd,
+            "project": project,
+            "tmux": context.tmux,
+            "terminal": context.terminal.as_ref().and_then(|t| t.app.clone()),
+        },
+        "env": TEMPLATE_ENV
+            .iter()
+            .filter_map(|name| Some((*name, std::env::var(name).ok()?)))
+            .collect::<std::collections::BTreeMap<_, _>>(),
+    })
+}
+
+/// Render a minijinja template. Undefined variables render as empty strings.
+pub fn render(template: &str, data: &serde_json::Value) -> Result<String, String> {
+    let mut env = Environment::new();
+    env.add_filter("truncate", truncate);
+    env.add_filter("escape_for", escape_for);
+    env.add_filter("duration", duration);
+    env.render_str(template, data)
+        .map(|rendered| rendered.trim().to_string())
+        .map_err(|err| err.to_string())
+}
+
+/// Apply title and message templates (either may be absent) to `notification`.
+pub fn apply(
+    notification: &mut Notification,
+    title_template: Option<&str>,
+    message_template: Option<&str>,
+    context: Option<&RemoteContext>,
+) -> Result<(), String> {
+    if title_template.is_none() && message_template.is_none() {
+        return Ok(());
+    }
+    let data = data(notification, context);
+    if let Some(template) = title_template {
+        notification.title = render(template, &data)?;
+    }
+    if let Some(template) = message_template {
+        notification.message = render(template, &data)?;
+    }
+    Ok(())
+}
+
+// `{{ message | truncate(80) }}`, counting characters and ending with "…".
+fn truncate(value: String, length: Option<usize>, end: Option<String>) -> String {
+    let length = length.unwrap_or(255);
+    if value.chars().count() <= length {
+        return value;
+    }
+    let end = end.unwrap_or_else(|| "…".to_string());
+    let keep = length.saturating_sub(end.chars().count());
+    format!("{}{end}", value.chars().take(keep).collect::<String>())
+}
+
+// `{{ title | escape_for("markdown") }}` for Telegram MarkdownV2; also "html"
+// (the default) and "shell". minijinja's own `escape` stays HTML-only.
+fn escape_for(value: String, format: Option<String>) -> Result<String, Error> {
+    let escaped = match format.as_deref().unwrap_or("html") {
+        "html" => value
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;"),
+        "markdown" | "markdownv2" => {
+            let mut out = String::with_capacity(value.len());
+            for ch in value.chars() {
+                if "_*[]()~`>#+-=|{}.!\\".contains(ch) {
+                    out.push('\\');
+                }
+                out.push(ch);
+            }
+            out
+        }
+        "shell" => format!("'{}'", value.replace('\'', "'\"'\"'")),
+        other => {
+            return Err(Error::new(
+                ErrorKind::InvalidOperation,
+                format!("unknown escape format {other:?} (use html, markdown or shell)"),
+            ));
+        }
+    };
+    Ok(escaped)
+}
+
+// `{{ metadata.duration_secs | duration }}` -> "1m 23s".
+fn duration(value: Value) -> Result<String, Error> {
+    if value.is_undefined() || value.is_none() {
+        return Ok(String::new());
+    }
+    let secs = match value.as_str() {
+        Some(text) => text.trim().parse::<f64>().ok(),
+        None => f64::try_from(value.clone()).ok(),
+    }
+    .ok_or_else(|| {
+        Error::new(
+            ErrorKind::InvalidOperation,
+            format!("duration expects seconds, got {value}"),
+        )
+    })?;
+    Ok(crate::hooks::format_duration(secs.max(0.0) as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_is_limited_to_the_allow_list() {
+        let data = data(&Notification::default(), Some(&RemoteContext::default()));
+        let env = data["env"].as_object().unwrap();
+        assert!(env.keys().all(|name| TEMPLATE_ENV.contains(&name.as_str())));
+        assert!(env.contains_key("HOME"));
+        assert_eq!(render("{{ env.CARGO_PKG_NAME }}", &data).unwrap(), "");
+    }
+
+    #[test]
+    fn escape_filters() {
+        let data = serde_json::json!({"title": "a_b <c>"});
+        assert_eq!(
+            render("{{ title | escape_for('markdown') }}", &data).unwrap(),
+            "a\\_b <c\\>"
+        );
+        assert_eq!(
+            render("{{ title | escape_for }}", &data).unwrap(),
+            "a_b &lt;c&gt;"
+        );
+        assert_eq!(
+            render("{{ title | escape }}", &data).unwrap(),
+            "a_b &lt;c&gt;"
+        );
+        assert!(render("{{ title | escape_for('yaml') }}", &data).is_err());
+    }
+}
@@ -0,0 +1,437 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::time::Duration;
+
+// Listener URLs that go through a Unix domain socket (e.g. one forwarded with
+// `ssh -R`) use the `http+unix://<percent-encoded socket path>/<endpoint>` form.
+const UNIX_SCHEME: &str = "http+unix://";
+
+/// What kind of failure kept a request from getting a response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Failure {
+    /// Nothing accepted the connection (refused, no route, unknown host).
+    Unreachable,
+    /// Connecting or reading took longer than the timeout.
+    Timeout,
+    Other,
+}
+
+#[derive(Debug)]
+pub struct TransportError {
+    pub failure: Failure,
+    pub message: String,
+}
+
+impl TransportError {
+    fn io(context: &str, err: std::io::Error) -> Self {
+        use std::io::ErrorKind;
+        let failure = match err.kind() {
+            ErrorKind::TimedOut | ErrorKind::WouldBlock => Failure::Timeout,
+            ErrorKind::ConnectionRefused | ErrorKind::NotFound | ErrorKind::AddrNotAvailable => {
+                Failure::Unreachable
+            }
+            _ => Failure::Other,
+        };
+        let message = if context.is_empty() {
+            err.to_string()
+        } else {
+            format!("{context}: {err}")
+        };
+        TransportError { failure, message }
+    }
+}
+
+impl From<String> for TransportError {
+    fn from(message: String) -> Self {
+        TransportError {
+            failure: Failure::Other,
+            message,
+        }
+    }
+}
+
+impl std::fmt::Display for TransportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: Box<dyn Read + Send>,
+}
+
+impl HttpResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn into_json(mut self) -> Result<serde_json::Value, TransportError> {
+        let mut buf = Vec::new();
+        self.body
+            .read_to_end(&mut buf)
+            .map_err(|e| TransportError::io("", e))?;
+        serde_json::from_slice(&buf).map_err(|e| TransportError::from(e.to_string()))
+    }
+}
+
+pub fn unix_url(socket: &str, path: &str) -> String {
+    format!(
+        "{UNIX_SCHEME}{}{path}",
+        socket.replace('%', "%25").replace('/', "%2F")
+    )
+}
+
+/// Split an `http+unix://` URL into socket path and request path.
+pub fn parse_unix_url(url: &str) -> Option<(String, String)> {
+    let rest = url.strip_prefix(UNIX_SCHEME)?;
+    let (socket, path) = match rest.find('/') {
+        Some(idx) => rest.split_at(idx),
+        None => (rest, "/"),
+    };
+    let socket = socket
+        .replace("%2F", "/")
+        .replace("%2f", "/")
+        .replace("%25", "%");
+    Some((socket, path.to_string()))
+}
+
+/// Issue a request to a listener over HTTP or a Unix socket. Non-2xx
+/// statuses are returned as responses, not errors.
+pub fn request(
+    method: &str,
+    url: &str,
+    token: Option<&str>,
+    body: Option<&serde_json::Value>,
+    connect_timeout: Duration,
+    read_timeout: Option<Duration>,
+) -> Result<HttpResponse, TransportError> {
+    if let Some((socket, path)) = parse_unix_url(url) {
+        return unix_request(method, &socket, &path, token, body, read_timeout);
+    }
+
+    let mut builder = ureq::AgentBuilder::new().timeout_connect(connect_timeout);
+    if let Some(timeout) = read_timeout {
+        builder = builder.timeout_read(timeout);
+    }
+    let mut request = builder.build().request(method, url);
+    if let Some(token) = token {
+        request = request.set("Authorization", &format!("Bearer {token}"));
+    }
+    let result = match body {
+        Some(body) => request
+            .set("Content-Type", "application/json")
+            .send_json(body.clone()),
+        None => request.call(),
+    };
+    let response = match result {
+        Ok(response) => response,
+        Err(ureq::Error::Status(_, response)) => response,
+        Err(err) => return Err(ureq_error(err)),
+    };
+    Ok(HttpResponse {
+        status: response.status(),
+        body: Box::new(response.into_reader()),
+    })
+}
+
+#[cfg(unix)]
+fn unix_request(
+    method: &str,
+    socket: &str,
+    path: &str,
+    token: Option<&str>,
+    body: Option<&serde_json::Value>,
+    read_timeout: Option<Duration>,
+) -> Result<HttpResponse, TransportError> {
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(socket).map_err(|e| TransportError::io(socket, e))?;
+    stream
+        .set_read_timeout(read_timeout)
+        .map_err(|e| TransportError::io("", e))?;
+
+    let payload = body.map(|b| b.to_string()).unwrap_or_default();
+    let mut head = format!("{method} {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n");
+    if let Some(token) = token {
+        head.push_str(&format!("Authorization: Bearer {token}\r\n"));
+    }
+    if body.is_some() {
+        head.push_str("Content-Type: application/json\r\n");
+    }
+    head.push_str(&format!("Content-Length: {}\r\n\r\n", payload.len()));
+    stream
+        .write_all(head.as_bytes())
+        .and_then(|_| stream.write_all(payload.as_bytes()))
+        .map_err(|e| TransportError::io("", e))?;
+
+    let mut reader = BufReader::new(stream);
+    let mut status_line = String::new();
+    reader
+        .read_line(&mut status_line)
+        .map_err(|e| TransportError::io("", e))?;
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| TransportError::from(format!("invalid response from {socket}")))?;
+
+    let mut chunked = false;
+    loop {
+        let mut line = String::new();
+        if reader
+            .read_line(&mut line)
+            .map_err(|e| TransportError::io("", e))?
+            == 0
+        {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.eq_ignore_ascii_case("transfer-encoding")
+            && value.trim().eq_ignore_ascii_case("chunked")
+        {
+            chunked = true;
+        }
+    }
+
+    // `Connection: close` means the body runs to EOF unless it is chunked.
+    let body: Box<dyn Read + Send> = if chunked {
+        Box::new(ChunkedReader {
+            inner: reader,
+            remaining: 0,
+            done: false,
+        })
+    } else {
+        Box::new(reader)
+    };
+    Ok(HttpResponse { status, body })
+}
+
+#[cfg(not(unix))]
+fn unix_request(
+    _method: &str,
+    _socket: &str,
+    _path: &str,
+    _token: Option<&str>,
+    _body: Option<&serde_json::Value>,
+    _read_timeout: Option<Duration>,
+) -> Result<HttpResponse, TransportError> {
+    Err(TransportError::from(
+        "unix sockets are not supported on this platform".to_string(),
+    ))
+}
+
+fn ureq_error(err: ureq::Error) -> TransportError {
+    let message = err.to_string();
+    let ureq::Error::Transport(transport) = err else {
+        return TransportError::from(message);
+    };
+    let io_kind = std::error::Error::source(&transport)
+        .and_then(|source| source.downcast_ref::<std::io::Error>())
+        .map(std::io::Error::kind);
+    let failure = match (transport.kind(), io_kind) {
+        (_, Some(std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock)) => {
+            Failure::Timeout
+        }
+        (ureq::ErrorKind::Dns | ureq::ErrorKind::ConnectionFailed, _) => Failure::Unreachable,
+        _ => Failure::Other,
+    };
+    TransportError { failure, message }
+}
+
+struct ChunkedReader<R> {
+    inner: R,
+    remaining: usize,
+    done: bool,
+}
+
+impl<R: BufRead> Read for ChunkedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.done {
+            return Ok(0);
+        }
+        if self.remaining == 0 {
+            let mut line = String::new();
+            self.inner.read_line(&mut line)?;
+            if line.trim().is_empty() {
+                // CRLF closing the previous chunk.
+                line.clear();
+                self.inner.read_line(&mut line)?;
+            }
+            let size = line.trim().split(';').next().unwrap_or("0");
+            self.remaining = usize::from_str_radix(size, 16)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            if self.remaining == 0 {
+                self.done = true;
+                return Ok(0);
+            }
+        }
+        let max = buf.len().min(self.remaining);
+        let read = self.inner.read(&mut buf[..max])?;
+        self.remaining -= read;
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read_chunked(raw: &str) -> std::io::Result<String> {
+        let mut reader = ChunkedReader {
+            inner: BufReader::new(raw.as_bytes()),
+            remaining: 0,
+            done: false,
+        };
+        let mut out = String::new();
+        reader.read_to_string(&mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn unix_url_round_trip() {
+        let url = unix_url("/tmp/100%/ding.sock", "/notify");
+        assert_eq!(url, "http+unix://%2Ftmp%2F100%25%2Fding.sock/notify");
+        assert_eq!(
+            parse_unix_url(&url),
+            Some(("/tmp/100%/ding.sock".to_string(), "/notify".to_string()))
+        );
+        assert_eq!(
+            parse_unix_url("http+unix://%2frun%2fding.sock"),
+            Some(("/run/ding.sock".to_string(), "/".to_string()))
+        );
+        assert_eq!(parse_unix_url("http://127.0.0.1:4399/notify"), None);
+    }
+
+    #[test]
+    fn chunked_body() {
+        let raw = "5\r\nhello\r\n7;ext=1\r\n, world\r\n0\r\n\r\n";
+        assert_eq!(read_chunked(raw).unwrap(), "hello, world");
+        assert_eq!(read_chunked("0\r\n\r\n").unwrap(), "");
+    }
+
+    #[test]
+    fn chunked_body_rejects_bad_sizes() {
+        let err = read_chunked("zz\r\nhello\r\n0\r\n\r\n").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        // A stream that ends before the last chunk is an error, not a short body.
+        let err = read_chunked("5\r\nhello\r\n").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[cfg(unix)]
+    fn serve_once(name: &str, response: &'static str) -> (String, std::thread::JoinHandle<String>) {
+        use std::os::unix::net::UnixListener;
+
+        let path =
+            std::env::temp_dir().join(format!("ding-transport-{name}-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        let handle = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut head = String::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                head.push_str(&line);
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let length = head
+                .lines()
+                .find_map(|line| line.strip_prefix("Content-Length: "))
+                .and_then(|len| len.parse::<usize>().ok())
+                .unwrap_or(0);
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            reader.get_mut().write_all(response.as_bytes()).unwrap();
+            head + &String::from_utf8(body).unwrap()
+        });
+        (path.to_string_lossy().into_owned(), handle)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_request_reads_chunked_response() {
+        let (socket, server) = serve_once(
+            "chunked",
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: Chunked\r\n\r\n4\r\n{\"ok\r\n6\r\n\":true\r\n1\r\n}\r\n0\r\n\r\n",
+        );
+        let body = serde_json::json!({"title": "hi"});
+        let response = request(
+            "POST",
+            &unix_url(&socket, "/notify"),
+            Some("secret"),
+            Some(&body),
+            Duration::from_secs(1),
+            Some(Duration::from_secs(5)),
+        )
+        .unwrap();
+        assert!(response.is_success());
+        assert_eq!(
+            response.into_json().unwrap(),
+            serde_json::json!({"ok": true})
+        );
+
+        let sent = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket);
+        assert!(sent.starts_with("POST /notify HTTP/1.1\r\n"));
+        assert!(sent.contains("Authorization: Bearer secret\r\n"));
+        assert!(sent.contains("Content-Type: application/json\r\n"));
+        assert!(sent.ends_with("\r\n\r\n{\"title\":\"hi\"}"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_request_reads_body_to_eof() {
+        let (socket, server) = serve_once(
+            "eof",
+            "HTTP/1.1 401 Unauthorized\r\nContent-Type: text/plain\r\n\r\nno token",
+        );
+        let mut response = request(
+            "GET",
+            &unix_url(&socket, "/status"),
+            None,
+            None,
+            Duration::from_secs(1),
+            Some(Duration::from_secs(5)),
+        )
+        .unwrap();
+        assert_eq!(response.status, 401);
+        assert!(!response.is_success());
+        let mut text = String::new();
+        response.body.read_to_string(&mut text).unwrap();
+        assert_eq!(text, "no token");
+
+        let sent = server.join().unwrap();
+        let _ = std::fs::remove_file(&socket);
+        assert!(sent.starts_with("GET /status HTTP/1.1\r\n"));
+        assert!(!sent.contains("Authorization"));
+        assert!(sent.contains("Content-Length: 0\r\n"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_request_missing_socket_is_unreachable() {
+        let path = std::env::temp_dir().join(format!(
+            "ding-transport-missing-{}.sock",
+            std::process::id()
+        ));
+        let err = request(
+            "GET",
+            &unix_url(&path.to_string_lossy(), "/status"),
+            None,
+            None,
+            Duration::from_secs(1),
+            None,
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err.failure, Failure::Unreachable);
+    }
+}
@@ -0,0 +1,390 @@
+use crate::config::{Config, ConfigOrigin, ResolvedConfig};
+use crate::schema::{self, Field, Kind};
+use std::path::Path;
+use toml_edit::{ImDocument, Item, TableLike};
+
+/// A problem in a config file; `line` is 1-based.
+#[derive(Debug)]
+pub struct Issue {
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Issue {
+    pub fn display(&self, path: &Path) -> String {
+        match self.line {
+            Some(line) => format!("{}:{line}: {}", path.display(), self.message),
+            None => format!("{}: {}", path.display(), self.message),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Expect {
+    Table,
+    Field(&'static Field),
+    Unknown,
+}
+
+/// Unknown keys, wrongly typed values and bad enum values in one file.
+pub fn check_file(contents: &str) -> Vec<Issue> {
+    let doc = match ImDocument::parse(contents) {
+        Ok(doc) => doc,
+        Err(err) => {
+            return vec![Issue {
+                line: err.span().map(|span| line_of(contents, span.start)),
+                message: one_line(err.message()),
+            }];
+        }
+    };
+    let mut issues = Vec::new();
+    check_table(contents, doc.as_table(), &mut Vec::new(), &mut issues);
+    // Anything the walk above missed, as the loader would see it.
+    if issues.is_empty()
+        && let Ok(table) = toml::from_str::<toml::Table>(contents)
+        && let Err(err) = table.try_into::<Config>()
+    {
+        issues.push(Issue {
+            line: None,
+            message: one_line(err.message()),
+        });
+    }
+    issues
+}
+
+/// `check_file` for a project `.ding.toml`, plus keys outside
+/// `config::PROJECT_KEYS`.
+pub fn check_project_file(contents: &str) -> Vec<Issue> {
+    let mut issues = check_file(contents);
+    if !issues.is_empty() {
+        return issues;
+    }
+    let (Ok(doc), Ok(table)) = (
+        ImDocument::parse(contents),
+        toml::from_str::<toml::Table>(contents),
+    ) else {
+        return issues;
+    };
+    for key in crate::config::disallowed_project_keys(&table) {
+        let path = key.split('.').collect::<Vec<_>>();
+        issues.push(Issue {
+            line: key_line(contents, doc.as_table(), &path),
+            message: format!("{key} cannot be set in a project file"),
+        });
+    }
+    issues
+}
+
+/// Problems that only show when the merged config is used: providers and
+/// forward targets that are unknown or not set up. `contents` is the global
+/// file, used for line numbers of values that came from it.
+pub fn check_config(resolved: &ResolvedConfig, contents: &str) -> Vec<Issue> {
+    let config = &resolved.config;
+    let doc = ImDocument::parse(contents).ok();
+    let issue = |path: &[&str], message: String| match resolved.origins.get(&path.join(".")) {
+        Some(ConfigOrigin::Global(_)) => Issue {
+            line: doc
+                .as_ref()
+                .and_then(|doc| key_line(contents, doc.as_table(), path)),
+            message,
+        },
+        Some(origin) => Issue {
+            line: None,
+            message: format!("{message} ({} from {origin})", path.join(".")),
+        },
+        None => Issue {
+            line: None,
+            message,
+        },
+    };
+    let mut issues = Vec::new();
+
+    let provider = config.default_provider.as_deref();
+    if provider == Some("forward")
+        && config
+            .forward
+            .as_ref()
+            .and_then(|f| f.targets.as_ref())
+            .is_none_or(|targets| targets.is_empty())
+    {
+        issues.push(issue(
+            &["default_provider"],
+            "default_provider is \"forward\" but forward.targets is empty".to_string(),
+        ));
+    }
+    let mut used = provider
+        .filter(|p| *p != "forward")
+        .map(|p| vec![(p.to_string(), vec!["default_provider"])])
+        .unwrap_or_default();
+    for target in config
+        .forward
+        .as_ref()
+        .and_then(|f| f.targets.clone())
+        .unwrap_or_default()
+    {
+        used.push((target, vec!["forward", "targets"]));
+    }
+    let mut reported = Vec::new();
+    for (target, path) in used {
+        if reported.contains(&target) {
+            continue;
+        }
+        let missing = match target.as_str() {
+            "remote" => config
+                .remote
+                .as_ref()
+                .is_none_or(|r| r.host.is_none() && r.url.is_none() && r.socket.is_none())
+                .then(|| "remote.host, remote.url or remote.socket is not set".to_string()),
+            "telegram" => {
+                let telegram = config.telegram.clone().unwrap_or_default();
+                let unset = [
+                    ("telegram.bot_token", telegram.bot_token.is_none()),
+                    ("telegram.chat_id", telegram.chat_id.is_none()),
+                ]
+                .into_iter()
+                .filter(|(_, unset)| *unset)
+                .map(|(key, _)| key)
+                .collect::<Vec<_>>();
+                match unset.as_slice() {
+                    [] => None,
+                    [key] => Some(format!("{key} is not set")),
+                    keys => Some(format!("{} are not set", keys.join(" and "))),
+                }
+            }
+            _ => None,
+        };
+        if let Some(missing) = missing {
+            issues.push(issue(&path, format!("{target} is used but {missing}")));
+            reported.push(target);
+        }
+    }
+    issues
+}
+
+fn check_table(
+    contents: &str,
+    table: &dyn TableLike,
+    path: &mut Vec<String>,
+    issues: &mut Vec<Issue>,
+) {
+    for (name, item) in table.iter() {
+        let line = table
+            .key(name)
+            .and_then(|key| key.span())
+            .or_else(|| item.span())
+            .map(|span| line_of(contents, span.start));
+        path.push(name.to_string());
+        let dotted = path.join(".");
+        let segments = path.iter().map(String::as_str).collect::<Vec<_>>();
+        match expect(&segments) {
+            Expect::Unknown => issues.push(Issue {
+                line,
+                message: unknown_key_message(&dotted),
+            }),
+            Expect::Table => match item.as_table_like() {
+                Some(inner) => check_table(contents, inner, path, issues),
+                None => issues.push(Issue {
+                    line,
+                    message: format!("{dotted} must be a table"),
+                }),
+            },
+            Expect::Field(field) => {
+                if let Some(message) = check_value(field, item) {
+                    issues.push(Issue {
+                        line,
+                        message: format!("{dotted}: {message}"),
+                    });
+                }
+            }
+        }
+        path.pop();
+    }
+}
+
+/// What the schema allows at a dotted key such as `remote.port`.
+pub fn expect_key(dotted: &str) -> Expect {
+    expect(&dotted.split('.').collect::<Vec<_>>())
+}
+
+/// Parse `raw` as the type `dotted` holds, rejecting values `check_file`
+/// would flag. Lists take TOML (`["a", "b"]`) or comma-separated items;
+/// secrets take a string or an inline table such as `{ env = "TOKEN" }`.
+pub fn parse_value(dotted: &str, raw: &str) -> Result<toml_edit::Value, String> {
+    let field = match expect_key(dotted) {
+        Expect::Unknown => return Err(unknown_key_message(dotted)),
+        Expect::Table => {
+            return Err(format!("{dotted} is a table; set one of its keys instead"));
+        }
+        Expect::Field(field) => field,
+    };
+    let toml_literal = |raw: &str| {
+        raw.parse::<toml_edit::Value>()
+            .map_err(|err| format!("{dotted}: {}", one_line(&err.to_string())))
+    };
+    let literal = raw.trim_start();
+    let value = match field.kind {
+        Kind::List | Kind::EnumList(_) | Kind::Rule if literal.starts_with('[') => {
+            toml_literal(raw)?
+        }
+        Kind::Secret | Kind::UrgencyMap if literal.starts_with('{') => toml_literal(raw)?,
+        Kind::String | Kind::Enum(_) | Kind::Rule | Kind::Secret | Kind::UrgencyMap => {
+            toml_edit::Value::from(raw)
+        }
+        kind => {
+            let value = crate::config::parse_env_value(kind.key_kind(), raw)
+                .map_err(|message| format!("{dotted}: {message}"))?;
+            toml_literal(&value.to_string())?
+        }
+    };
+    match check_value(field, &Item::Value(value.clone())) {
+        Some(message) => Err(format!("{dotted}: {message}")),
+        None => Ok(value),
+    }
+}
+
+/// "unknown config key", with the closest known key when one is near.
+pub fn unknown_key_message(dotted: &str) -> String {
+    let closest = schema::keys()
+        .map(|(key, _)| (edit_distance(&key, dotted), key))
+        .min();
+    match closest {
+        Some((distance, key)) if distance <= 2 => {
+            format!("unknown config key {dotted} (did you mean {key}?)")
+        }
+        _ => format!("unknown config key {dotted}"),
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = previous[j] + usize::from(ca != *cb);
+            current.push(substitute.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+fn expect(path: &[&str]) -> Expect {
+    let field = |fields, name| schema::find(fields, name).map_or(Expect::Unknown, Expect::Field);
+    match path {
+        ["profiles"] | ["profiles", _] => Expect::Table,
+        ["profiles", _, "profile" | "profiles", ..] => Expect::Unknown,
+        ["profiles", _, rest @ ..] => expect(rest),
+        ["sources"] | ["sources", _] | ["sources", _, "events"] | ["sources", _, "events", _] => {
+            Expect::Table
+        }
+        ["sources", _, name] => field(schema::SOURCE_FIELDS, name),
+        ["sources", _, "events", _, name] => field(schema::SOURCE_EVENT_FIELDS, name),
+        ["hooks"] | ["hooks", _] | ["hooks", _, "on"] | ["hooks", _, "on", _] => Expect::Table,
+        ["hooks", _, name] => field(schema::HOOK_FIELDS, name),
+        ["hooks", _, "on", _, name] => field(schema::HOOK_EVENT_FIELDS, name),
+        [name] => match schema::SECTIONS
+            .iter()
+            .find(|s| !s.name.is_empty() && s.name == *name)
+        {
+            Some(_) => Expect::Table,
+            None => field(schema::SECTIONS[0].fields, name),
+        },
+        [section, name] => schema::SECTIONS
+            .iter()
+            .find(|s| !s.name.is_empty() && s.name == *section)
+            .map_or(Expect::Unknown, |section| field(section.fields, name)),
+        _ => Expect::Unknown,
+    }
+}
+
+// An error message for `item` when it isn't what `field` allows.
+fn check_value(field: &Field, item: &Item) -> Option<String> {
+    let value = item.as_value();
+    let text = value.and_then(|v| v.as_str());
+    let strings = value
+        .and_then(|v| v.as_array())
+        .and_then(|array| array.iter().map(|v| v.as_str()).collect::<Option<Vec<_>>>());
+    let one_of = |values: &[&str], text: &str| {
+        (!values.contains(&text))
+            .then(|| format!("unknown value {text:?} (expected {})", values.join(", ")))
+    };
+    match field.kind {
+        Kind::String if text.is_none() => Some("expected a string".into()),
+        Kind::Bool if !value.is_some_and(|v| v.is_bool()) => Some("expected true or false".into()),
+        Kind::Integer | Kind::Port => match value.and_then(|v| v.as_integer()) {
+            None => Some("expected an integer".into()),
+            Some(n) if n < 0 => Some("must not be negative".into()),
+            Some(n) if matches!(field.kind, Kind::Port) && n > 65535 => {
+                Some("expected a port number (0-65535)".into())
+            }
+            Some(_) => None,
+        },
+        Kind::List | Kind::EnumList(_) if strings.is_none() => {
+            Some("expected a list of strings".into())
+        }
+        Kind::EnumList(values) => strings?.iter().find_map(|text| one_of(values, text)),
+        Kind::Enum(values) => match text {
+            Some(text) => one_of(values, text),
+            None => Some(format!("expected one of {}", values.join(", "))),
+        },
+        Kind::Rule if text.is_none() && strings.is_none() => {
+            Some("expected a string or a list of strings".into())
+        }
+        Kind::UrgencyMap => {
+            let Some(table) = item.as_table_like() else {
+                return Some("expected a table of urgencies".into());
+            };
+            table
+                .iter()
+                .find_map(|(_, urgency)| match urgency.as_str() {
+                    Some(text) => one_of(schema::URGENCIES, text),
+                    None => Some(format!("expected one of {}", schema::URGENCIES.join(", "))),
+                })
+        }
+        Kind::Secret => {
+            if text.is_some() {
+                return None;
+            }
+            let reference = item.as_table_like().map(|table| {
+                table
+                    .iter()
+                    .map(|(kind, value)| (kind.to_string(), value.as_str().is_some()))
+                    .collect::<Vec<_>>()
+            });
+            match reference.as_deref() {
+                Some([(kind, true)]) if matches!(kind.as_str(), "env" | "file" | "command") => None,
+                _ => Some(
+                    "expected a string or one of { env = ... }, { file = ... }, { command = ... }"
+                        .into(),
+                ),
+            }
+        }
+        _ => None,
+    }
+}
+
+fn key_line(contents: &str, table: &dyn TableLike, path: &[&str]) -> Option<usize> {
+    let (first, rest) = path.split_first()?;
+    let (key, item) = table.get_key_value(first)?;
+    if rest.is_empty() {
+        return key
+            .span()
+            .or_else(|| item.span())
+            .map(|span| line_of(contents, span.start));
+    }
+    key_line(contents, item.as_table_like()?, rest)
+}
+
+fn line_of(contents: &str, offset: usize) -> usize {
+    contents[..offset.min(contents.len())].matches('\n').count() + 1
+}
+
+fn one_line(message: &str) -> String {
+    message
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(": ")
+}